@@ -0,0 +1,735 @@
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Sense, Ui};
+
+#[cfg(feature = "gui")]
+use crate::units::format_bytes;
+use crate::{
+    error::{Error, Result},
+    process::{command_summary, CommandCounts, Process},
+    procfs::{self, ProcSource},
+    query::{self, Query},
+};
+
+/// One line of `/proc/[pid]/cgroup`, see cgroups(7).
+#[derive(Clone, Debug)]
+pub struct CgroupEntry {
+    pub hierarchy: u32,
+    /// Empty for the unified v2 hierarchy, v1 named hierarchies look like `name=systemd`.
+    pub controllers: Vec<String>,
+    /// Relative to the mount point of the hierarchy.
+    pub path: String,
+}
+
+/// The cgroups of a process, one per mounted hierarchy.
+#[derive(Clone, Debug, Default)]
+pub struct Cgroups {
+    pub entries: Vec<CgroupEntry>,
+}
+
+impl Cgroups {
+    pub fn parse(contents: &str, path: &Path) -> Result<Self> {
+        let entries = contents
+            .lines()
+            .map(|line| {
+                let mut fields = line.splitn(3, ':');
+                let (Some(hierarchy), Some(controllers), Some(cgroup)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(Error::malformed(path, format!("invalid line '{}'", line)));
+                };
+                let hierarchy = hierarchy
+                    .parse()
+                    .map_err(|_| Error::malformed(path, format!("invalid id '{}'", hierarchy)))?;
+                Ok(CgroupEntry {
+                    hierarchy,
+                    controllers: controllers
+                        .split(',')
+                        .filter(|c| !c.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                    path: cgroup.to_string(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { entries })
+    }
+
+    /// The cgroup v2 path, also on hybrid hosts that mount both versions.
+    pub fn unified(&self) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.hierarchy == 0 && entry.controllers.is_empty())
+            .map(|entry| entry.path.as_str())
+    }
+
+    /// The path that says the most about where the process belongs: v2 and systemd's own
+    /// hierarchy are preferred over the v1 controllers, and the root only counts if there is
+    /// nothing else.
+    pub fn path(&self) -> &str {
+        let preference = |entry: &CgroupEntry| {
+            if entry.hierarchy == 0 && entry.controllers.is_empty() {
+                0
+            } else if entry.controllers.iter().any(|c| c == "name=systemd") {
+                1
+            } else {
+                2
+            }
+        };
+        let mut entries: Vec<&CgroupEntry> = self.entries.iter().collect();
+        entries.sort_by_key(|entry| preference(entry));
+        entries
+            .iter()
+            .find(|entry| entry.path != "/")
+            .or(entries.first())
+            .map_or("/", |entry| entry.path.as_str())
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn show(&self, ui: &mut Ui) {
+        egui::Grid::new("cgroups").striped(true).show(ui, |ui| {
+            let workload = Workload::from_path(self.path());
+            ui.label(RichText::new("Workload").color(Color32::WHITE));
+            ui.label(RichText::new(workload.to_string()).color(Color32::LIGHT_GRAY));
+            ui.end_row();
+
+            for entry in &self.entries {
+                ui.label(RichText::new(entry.hierarchy_name()).color(Color32::WHITE));
+                ui.label(RichText::new(&entry.path).color(Color32::LIGHT_GRAY));
+                ui.end_row();
+            }
+        });
+    }
+}
+
+impl CgroupEntry {
+    /// `v2` for the unified hierarchy, otherwise the controllers.
+    pub fn hierarchy_name(&self) -> String {
+        if self.hierarchy == 0 && self.controllers.is_empty() {
+            "v2".to_string()
+        } else {
+            self.controllers.join(",")
+        }
+    }
+}
+
+pub fn parse_cgroups(source: &dyn ProcSource, dir: &Path) -> Result<Cgroups> {
+    let path = dir.join("cgroup");
+    Cgroups::parse(&procfs::read_to_string(source, &path)?, &path)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Runtime {
+    Docker,
+    Containerd,
+    Podman,
+    Crio,
+}
+
+impl Display for Runtime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Runtime::Docker => "docker",
+            Runtime::Containerd => "containerd",
+            Runtime::Podman => "podman",
+            Runtime::Crio => "cri-o",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Container {
+    /// Unknown for containers of Kubernetes pods that use the cgroupfs driver.
+    pub runtime: Option<Runtime>,
+    /// 64 hex digits.
+    pub id: String,
+}
+
+impl Container {
+    /// The 12 digits `docker ps` shows.
+    pub fn short_id(&self) -> &str {
+        &self.id[..12]
+    }
+}
+
+/// What a process belongs to, as far as its cgroup path tells.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Workload {
+    /// A Kubernetes pod by uid, with the container if the path names one.
+    Pod {
+        uid: String,
+        container: Option<Container>,
+    },
+    Container(Container),
+    /// A systemd-nspawn or other machine registered with systemd-machined.
+    Machine(String),
+    /// A systemd service, scope or slice.
+    Unit(String),
+    /// A cgroup that follows none of the naming schemes above.
+    Cgroup(String),
+    /// The root cgroup, where kernel threads live.
+    Root,
+}
+
+impl Workload {
+    /// Looks at the components of `path` from the innermost one outwards.
+    pub fn from_path(path: &str) -> Self {
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        if components.is_empty() {
+            return Workload::Root;
+        }
+
+        let container = components
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, name)| parse_container(name, &components[..i]));
+        if let Some(uid) = components.iter().find_map(|name| parse_pod_uid(name)) {
+            return Workload::Pod { uid, container };
+        }
+        if let Some(container) = container {
+            return Workload::Container(container);
+        }
+        if let Some(machine) = components.iter().rev().find_map(|name| parse_machine(name)) {
+            return Workload::Machine(machine);
+        }
+
+        let unit = components
+            .iter()
+            .rev()
+            .find(|name| name.ends_with(".service") || name.ends_with(".scope"))
+            .or_else(|| {
+                components
+                    .iter()
+                    .rev()
+                    .find(|name| name.ends_with(".slice"))
+            });
+        match unit {
+            Some(unit) => Workload::Unit(unit.to_string()),
+            None => Workload::Cgroup(path.to_string()),
+        }
+    }
+
+    /// The bucket in the grouped process list, containers count towards their pod.
+    pub fn group(&self) -> Workload {
+        match self {
+            Workload::Pod { uid, .. } => Workload::Pod {
+                uid: uid.clone(),
+                container: None,
+            },
+            workload => workload.clone(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Workload::Pod { .. } => "pod",
+            Workload::Container(_) => "container",
+            Workload::Machine(_) => "machine",
+            Workload::Unit(_) => "unit",
+            Workload::Cgroup(_) => "cgroup",
+            Workload::Root => "root",
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Workload::Pod {
+                uid,
+                container: Some(container),
+            } => format!("{} {}", uid, container.short_id()),
+            Workload::Pod { uid, .. } => uid.clone(),
+            Workload::Container(container) => match container.runtime {
+                Some(runtime) => format!("{} {}", runtime, container.short_id()),
+                None => container.short_id().to_string(),
+            },
+            Workload::Machine(name) | Workload::Unit(name) | Workload::Cgroup(name) => name.clone(),
+            Workload::Root => "/".to_string(),
+        }
+    }
+
+    /// The container id for the `container:` search field.
+    pub fn container_id(&self) -> Option<&str> {
+        match self {
+            Workload::Pod {
+                container: Some(container),
+                ..
+            }
+            | Workload::Container(container) => Some(&container.id),
+            _ => None,
+        }
+    }
+
+    pub fn pod_uid(&self) -> Option<&str> {
+        match self {
+            Workload::Pod { uid, .. } => Some(uid),
+            _ => None,
+        }
+    }
+
+    /// The unit or machine name for the `unit:` search field.
+    pub fn unit(&self) -> Option<&str> {
+        match self {
+            Workload::Machine(name) | Workload::Unit(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// A search that finds the processes of this workload.
+    pub fn search(&self) -> String {
+        match self {
+            Workload::Pod {
+                container: Some(container),
+                ..
+            }
+            | Workload::Container(container) => query::equals("container", &container.id),
+            Workload::Pod { uid, .. } => query::equals("pod", uid),
+            Workload::Machine(name) | Workload::Unit(name) => query::equals("unit", name),
+            Workload::Cgroup(path) => query::equals("cgroup", path),
+            Workload::Root => query::equals("cgroup", "/"),
+        }
+    }
+}
+
+impl Display for Workload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.kind(), self.name())
+    }
+}
+
+fn is_container_id(id: &str) -> bool {
+    id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// `docker-<id>.scope` and friends from the systemd cgroup driver, or a bare id below `docker`
+/// or a pod from the cgroupfs driver, or below a containerd namespace like `/k8s.io/<id>`. The
+/// conmon monitors of podman and cri-o count towards their container.
+fn parse_container(name: &str, ancestors: &[&str]) -> Option<Container> {
+    let name = name.strip_suffix(".scope").unwrap_or(name);
+    for (prefix, runtime) in [
+        ("docker-", Runtime::Docker),
+        ("cri-containerd-", Runtime::Containerd),
+        ("crio-", Runtime::Crio),
+        ("libpod-", Runtime::Podman),
+    ] {
+        let Some(id) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        let id = id.strip_prefix("conmon-").unwrap_or(id);
+        if is_container_id(id) {
+            return Some(Container {
+                runtime: Some(runtime),
+                id: id.to_string(),
+            });
+        }
+    }
+
+    is_container_id(name).then(|| Container {
+        runtime: match ancestors {
+            [.., "docker"] => Some(Runtime::Docker),
+            [.., "libpod_parent"] => Some(Runtime::Podman),
+            [namespace] if !namespace.ends_with(".slice") => Some(Runtime::Containerd),
+            _ => None,
+        },
+        id: name.to_string(),
+    })
+}
+
+/// `pod<uid>` from the cgroupfs driver or `kubepods-<qos>-pod<uid>.slice` from the systemd
+/// driver, which writes the dashes of the uid as underscores.
+fn parse_pod_uid(name: &str) -> Option<String> {
+    let name = name.strip_suffix(".slice").unwrap_or(name);
+    let (_, uid) = name.rsplit_once("pod")?;
+    let valid = uid.len() == 36
+        && uid
+            .bytes()
+            .all(|b| b.is_ascii_hexdigit() || b == b'-' || b == b'_');
+    valid.then(|| uid.replace('_', "-"))
+}
+
+/// `machine-<name>.scope` or `systemd-nspawn@<name>.service`.
+fn parse_machine(name: &str) -> Option<String> {
+    let machine = name
+        .strip_prefix("machine-")
+        .and_then(|name| name.strip_suffix(".scope"))
+        .or_else(|| {
+            name.strip_prefix("systemd-nspawn@")
+                .and_then(|name| name.strip_suffix(".service"))
+        })?;
+    // systemd escapes dashes in unit names.
+    Some(machine.replace("\\x2d", "-"))
+}
+
+/// Processes of one workload with their combined usage.
+pub struct WorkloadGroup {
+    pub workload: Workload,
+    pub processes: usize,
+    pub cpu_usage: f64,
+    pub rss_bytes: u64,
+    /// Sum over the processes whose PSS could be read.
+    pub pss: u64,
+    /// Most common command names first.
+    pub commands: Vec<(String, usize)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupSort {
+    Processes,
+    #[default]
+    Cpu,
+    Rss,
+    Pss,
+}
+
+impl GroupSort {
+    pub const ALL: [GroupSort; 4] = [
+        GroupSort::Processes,
+        GroupSort::Cpu,
+        GroupSort::Rss,
+        GroupSort::Pss,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GroupSort::Processes => "Processes",
+            GroupSort::Cpu => "CPU%",
+            GroupSort::Rss => "RSS",
+            GroupSort::Pss => "PSS",
+        }
+    }
+}
+
+impl WorkloadGroup {
+    fn key(&self, sort: GroupSort) -> f64 {
+        match sort {
+            GroupSort::Processes => self.processes as f64,
+            GroupSort::Cpu => self.cpu_usage,
+            GroupSort::Rss => self.rss_bytes as f64,
+            GroupSort::Pss => self.pss as f64,
+        }
+    }
+
+    pub fn command_summary(&self) -> String {
+        command_summary(&self.commands)
+    }
+}
+
+/// Buckets the processes that match `query`, biggest first by `sort`.
+pub fn group_by_workload(
+    processes: &[Process],
+    query: &Query,
+    sort: GroupSort,
+) -> Vec<WorkloadGroup> {
+    puffin::profile_function!();
+
+    let mut groups: HashMap<Workload, WorkloadGroup> = HashMap::new();
+    let mut commands: HashMap<Workload, CommandCounts> = HashMap::new();
+    for process in processes.iter().filter(|p| query.matches(p)) {
+        let Some(workload) = process.workload() else {
+            continue;
+        };
+        let workload = workload.group();
+        let group = groups
+            .entry(workload.clone())
+            .or_insert_with(|| WorkloadGroup {
+                workload: workload.clone(),
+                processes: 0,
+                cpu_usage: 0.0,
+                rss_bytes: 0,
+                pss: 0,
+                commands: Vec::new(),
+            });
+        group.processes += 1;
+        group.cpu_usage += process.cpu_usage.unwrap_or(0.0);
+        group.rss_bytes += process.stats.rss_bytes();
+        group.pss += process.memory.pss().unwrap_or(0);
+        commands.entry(workload).or_default().add(process);
+    }
+
+    let mut groups: Vec<WorkloadGroup> = groups
+        .into_values()
+        .map(|mut group| {
+            group.commands = commands
+                .remove(&group.workload)
+                .unwrap_or_default()
+                .sorted();
+            group
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.key(sort)
+            .total_cmp(&a.key(sort))
+            .then_with(|| a.workload.name().cmp(&b.workload.name()))
+    });
+    groups
+}
+
+/// The grouped process list, returns the workload that was clicked.
+#[cfg(feature = "gui")]
+pub fn show_workload_groups(
+    ui: &mut Ui,
+    groups: &[WorkloadGroup],
+    sort: &mut GroupSort,
+) -> Option<Workload> {
+    puffin::profile_function!();
+
+    let mut clicked = None;
+    egui::ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+        egui::Grid::new("workload_groups")
+            .striped(true)
+            .show(ui, |ui| {
+                let columns = [("Kind", None), ("Name", None)]
+                    .into_iter()
+                    .chain(GroupSort::ALL.map(|sort| (sort.name(), Some(sort))))
+                    .chain([("Commands", None)]);
+                for (name, column) in columns {
+                    let arrow = if column == Some(*sort) { " ⏷" } else { "" };
+                    let text = RichText::new(format!("{}{}", name, arrow))
+                        .color(Color32::WHITE)
+                        .strong();
+                    let label = ui.add(egui::Label::new(text).sense(Sense::click()));
+                    if let (Some(column), true) = (column, label.clicked()) {
+                        *sort = column;
+                    }
+                }
+                ui.end_row();
+
+                for group in groups {
+                    let kind = ui.add(
+                        egui::Label::new(
+                            RichText::new(group.workload.kind()).color(Color32::LIGHT_GRAY),
+                        )
+                        .sense(Sense::click()),
+                    );
+                    let name = ui.add(
+                        egui::Label::new(
+                            RichText::new(group.workload.name()).color(Color32::WHITE),
+                        )
+                        .sense(Sense::click()),
+                    );
+                    if kind.clicked() || name.clicked() {
+                        clicked = Some(group.workload.clone());
+                    }
+                    name.on_hover_text("Show the processes of this group");
+
+                    for value in [
+                        group.processes.to_string(),
+                        format!("{:.1}", group.cpu_usage),
+                        format_bytes(group.rss_bytes),
+                        format_bytes(group.pss),
+                        group.command_summary(),
+                    ] {
+                        ui.label(RichText::new(value).color(Color32::LIGHT_GRAY));
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+    clicked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "3f1a9c0e5b7d2f4a6c8e0b1d3f5a7c9e1b3d5f7a9c0e2b4d6f8a0c2e4b6d8f0a";
+    const UID: &str = "5e8f2c1a-9b3d-4e7f-8a6c-0d2b4f6e8a1c";
+
+    fn container(runtime: Option<Runtime>) -> Option<Container> {
+        Some(Container {
+            runtime,
+            id: ID.to_string(),
+        })
+    }
+
+    fn pod(runtime: Option<Option<Runtime>>) -> Workload {
+        Workload::Pod {
+            uid: UID.to_string(),
+            container: runtime.and_then(container),
+        }
+    }
+
+    #[test]
+    fn workloads() {
+        let systemd_uid = UID.replace('-', "_");
+        let kubepods = format!(
+            "/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod{}.slice",
+            systemd_uid
+        );
+        for (path, expected) in [
+            // docker with the systemd and the cgroupfs driver.
+            (
+                format!("/system.slice/docker-{}.scope", ID),
+                Workload::Container(container(Some(Runtime::Docker)).unwrap()),
+            ),
+            (
+                format!("/docker/{}", ID),
+                Workload::Container(container(Some(Runtime::Docker)).unwrap()),
+            ),
+            // podman, its conmon, rootless below the user's manager and with cgroupfs.
+            (
+                format!("/machine.slice/libpod-{}.scope/container", ID),
+                Workload::Container(container(Some(Runtime::Podman)).unwrap()),
+            ),
+            (
+                format!("/machine.slice/libpod-conmon-{}.scope", ID),
+                Workload::Container(container(Some(Runtime::Podman)).unwrap()),
+            ),
+            (
+                format!(
+                    "/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-{}.scope",
+                    ID
+                ),
+                Workload::Container(container(Some(Runtime::Podman)).unwrap()),
+            ),
+            (
+                format!("/libpod_parent/{}", ID),
+                Workload::Container(container(Some(Runtime::Podman)).unwrap()),
+            ),
+            // Plain containerd puts containers below their namespace.
+            (
+                format!("/k8s.io/{}", ID),
+                Workload::Container(container(Some(Runtime::Containerd)).unwrap()),
+            ),
+            (
+                format!("/default/{}", ID),
+                Workload::Container(container(Some(Runtime::Containerd)).unwrap()),
+            ),
+            // Kubernetes with the systemd driver, uids with underscores.
+            (
+                format!("{}/cri-containerd-{}.scope", kubepods, ID),
+                pod(Some(Some(Runtime::Containerd))),
+            ),
+            (
+                format!("{}/crio-{}.scope", kubepods, ID),
+                pod(Some(Some(Runtime::Crio))),
+            ),
+            (
+                format!("{}/crio-conmon-{}.scope", kubepods, ID),
+                pod(Some(Some(Runtime::Crio))),
+            ),
+            (
+                format!(
+                    "/kubepods.slice/kubepods-pod{}.slice/docker-{}.scope",
+                    systemd_uid, ID
+                ),
+                pod(Some(Some(Runtime::Docker))),
+            ),
+            (kubepods.clone(), pod(None)),
+            // Kubernetes with the cgroupfs driver, which doesn't name the runtime.
+            (
+                format!("/kubepods/besteffort/pod{}/{}", UID, ID),
+                pod(Some(None)),
+            ),
+            (format!("/kubepods/pod{}", UID), pod(None)),
+            // machined.
+            (
+                "/machine.slice/machine-debian\\x2dtest.scope/payload".to_string(),
+                Workload::Machine("debian-test".to_string()),
+            ),
+            (
+                "/machine.slice/systemd-nspawn@web.service/payload".to_string(),
+                Workload::Machine("web".to_string()),
+            ),
+            // Units, the innermost service or scope wins over slices.
+            (
+                "/system.slice/cron.service".to_string(),
+                Workload::Unit("cron.service".to_string()),
+            ),
+            (
+                "/user.slice/user-1000.slice/session-2.scope".to_string(),
+                Workload::Unit("session-2.scope".to_string()),
+            ),
+            (
+                "/user.slice/user-1000.slice".to_string(),
+                Workload::Unit("user-1000.slice".to_string()),
+            ),
+            (
+                "/system.slice/ssh.service/sub".to_string(),
+                Workload::Unit("ssh.service".to_string()),
+            ),
+            // Nothing known.
+            (
+                "/custom/group".to_string(),
+                Workload::Cgroup("/custom/group".to_string()),
+            ),
+            (
+                format!("/custom/{}/nested", &ID[..12]),
+                Workload::Cgroup(format!("/custom/{}/nested", &ID[..12])),
+            ),
+            ("/".to_string(), Workload::Root),
+            (String::new(), Workload::Root),
+        ] {
+            assert_eq!(Workload::from_path(&path), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn workload_names() {
+        let docker = Workload::from_path(&format!("/docker/{}", ID));
+        assert_eq!(docker.to_string(), "container docker 3f1a9c0e5b7d");
+        assert_eq!(docker.container_id(), Some(ID));
+        assert_eq!(docker.search(), format!("container={}", ID));
+
+        let container = pod(Some(None));
+        assert_eq!(container.name(), format!("{} 3f1a9c0e5b7d", UID));
+        assert_eq!(container.group(), pod(None));
+        assert_eq!(container.pod_uid(), Some(UID));
+        assert_eq!(pod(None).search(), format!("pod={}", UID));
+
+        let machine = Workload::Machine("web".to_string());
+        assert_eq!(machine.unit(), Some("web"));
+        assert_eq!(Workload::Root.search(), "cgroup=/");
+    }
+
+    #[test]
+    fn proc_cgroup() {
+        let path = Path::new("1/cgroup");
+        let docker = format!("/system.slice/docker-{}.scope", ID);
+
+        let v2 = Cgroups::parse("0::/system.slice/cron.service\n", path).unwrap();
+        assert_eq!(v2.unified(), Some("/system.slice/cron.service"));
+        assert_eq!(v2.path(), "/system.slice/cron.service");
+        assert_eq!(v2.entries[0].hierarchy_name(), "v2");
+
+        // Hybrid: systemd keeps the v2 hierarchy for itself, controllers are v1.
+        let hybrid = Cgroups::parse(
+            &format!(
+                "12:cpu,cpuacct:{0}\n5:memory:{0}\n1:name=systemd:{0}\n0::/\n",
+                docker
+            ),
+            path,
+        )
+        .unwrap();
+        assert_eq!(hybrid.unified(), Some("/"));
+        assert_eq!(hybrid.path(), docker);
+        assert_eq!(hybrid.entries[0].hierarchy_name(), "cpu,cpuacct");
+        assert_eq!(hybrid.entries[2].hierarchy_name(), "name=systemd");
+
+        // v1 only, the named systemd hierarchy wins over the controllers.
+        let v1 = Cgroups::parse(
+            "4:memory:/docker/abc\n1:name=systemd:/system.slice/docker.service\n",
+            path,
+        )
+        .unwrap();
+        assert_eq!(v1.unified(), None);
+        assert_eq!(v1.path(), "/system.slice/docker.service");
+
+        // Kernel threads are in the root everywhere.
+        let root = Cgroups::parse("1:name=systemd:/\n0::/\n", path).unwrap();
+        assert_eq!(root.path(), "/");
+        assert_eq!(Workload::from_path(root.path()), Workload::Root);
+        assert_eq!(Cgroups::default().path(), "/");
+
+        // Paths may contain colons.
+        let colon = Cgroups::parse("0::/a:b\n", path).unwrap();
+        assert_eq!(colon.path(), "/a:b");
+
+        for contents in ["garbage\n", "x::/\n", "0:/\n"] {
+            let err = Cgroups::parse(contents, path).unwrap_err();
+            assert_eq!(err.path, path, "{}", contents);
+        }
+    }
+}
@@ -0,0 +1,738 @@
+//! The cgroup v2 hierarchy, see https://docs.kernel.org/admin-guide/cgroup-v2.html
+//!
+//! Paths are relative to the mount point like with [`ProcSource`], the root cgroup is the empty
+//! path and shown as `/`, the way `/proc/[pid]/cgroup` names it.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "gui")]
+use egui::{collapsing_header::CollapsingState, Color32, RichText, Ui};
+
+use crate::{
+    error::{Error, ErrorKind, Result},
+    procfs::{self, ProcSource},
+    units::format_bytes,
+};
+
+/// Uses `CGROUP_ROOT` if it is set, otherwise wherever cgroup2 is mounted, which is
+/// `/sys/fs/cgroup/unified` on hosts that still mount the v1 controllers.
+pub fn find_root() -> Option<PathBuf> {
+    if let Some(root) = std::env::var_os("CGROUP_ROOT") {
+        return Some(root.into());
+    }
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    mounts.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let (Some(_), Some(mount_point), Some("cgroup2")) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            return None;
+        };
+        Some(PathBuf::from(mount_point))
+    })
+}
+
+/// `/a/b` for the relative path `a/b`.
+fn cgroup_name(path: &Path) -> String {
+    format!("/{}", path.display())
+}
+
+/// Files of controllers that aren't enabled for a cgroup don't exist, which is not an error.
+fn read_optional(source: &dyn ProcSource, path: &Path) -> Result<Option<String>> {
+    match procfs::read_to_string(source, path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn parse_procs(contents: &str, path: &Path) -> Result<Vec<u64>> {
+    contents
+        .lines()
+        .map(|line| {
+            line.trim()
+                .parse()
+                .map_err(|_| Error::malformed(path, format!("invalid pid '{}'", line)))
+        })
+        .collect()
+}
+
+/// A cgroup with the cgroups below it.
+pub struct CgroupNode {
+    /// `/` for the root.
+    pub path: String,
+    /// Members from `cgroup.procs`, not including the ones of the children.
+    pub procs: Result<Vec<u64>>,
+    pub memory_current: Option<u64>,
+    pub children: Vec<CgroupNode>,
+}
+
+impl CgroupNode {
+    fn load(source: &dyn ProcSource, path: &Path) -> Result<Self> {
+        let procs_path = path.join("cgroup.procs");
+        let procs = procfs::read_to_string(source, &procs_path)
+            .and_then(|contents| parse_procs(&contents, &procs_path));
+        let memory_current = read_optional(source, &path.join("memory.current"))
+            .ok()
+            .flatten()
+            .and_then(|contents| contents.trim().parse().ok());
+
+        let mut names = procfs::read_dir(source, path)?;
+        names.sort();
+        let mut children = Vec::new();
+        for name in names {
+            let child = path.join(name);
+            // Controller files are files, child cgroups directories.
+            if !source.is_dir(&child).unwrap_or(false) {
+                continue;
+            }
+            match CgroupNode::load(source, &child) {
+                Ok(node) => children.push(node),
+                // Removed since the directory was listed.
+                Err(err) if err.kind == ErrorKind::NotFound => {}
+                Err(err) => children.push(CgroupNode {
+                    path: cgroup_name(&child),
+                    procs: Err(err),
+                    memory_current: None,
+                    children: Vec::new(),
+                }),
+            }
+        }
+
+        Ok(Self {
+            path: cgroup_name(path),
+            procs,
+            memory_current,
+            children,
+        })
+    }
+
+    /// The last component of the path.
+    pub fn name(&self) -> &str {
+        match self.path.rsplit_once('/') {
+            Some((_, "")) | None => "/",
+            Some((_, name)) => name,
+        }
+    }
+
+    /// Members of this cgroup and all below it.
+    pub fn total_procs(&self) -> usize {
+        let own = self.procs.as_ref().map_or(0, Vec::len);
+        own + self
+            .children
+            .iter()
+            .map(CgroupNode::total_procs)
+            .sum::<usize>()
+    }
+
+    /// The nodes in depth-first order with their depth.
+    pub fn flatten(&self) -> Vec<(&CgroupNode, usize)> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![(self, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            nodes.push((node, depth));
+            stack.extend(node.children.iter().rev().map(|child| (child, depth + 1)));
+        }
+        nodes
+    }
+}
+
+/// The whole hierarchy, read in one go.
+pub struct CgroupTree {
+    pub root: CgroupNode,
+    loaded_at: Instant,
+}
+
+impl CgroupTree {
+    /// Walking every cgroup takes a while on hosts with thousands of them, so the tree isn't
+    /// read again with every sample.
+    const MAX_AGE: Duration = Duration::from_secs(30);
+
+    pub fn load(source: &dyn ProcSource) -> Result<Self> {
+        puffin::profile_function!();
+
+        Ok(Self {
+            root: CgroupNode::load(source, Path::new(""))?,
+            loaded_at: Instant::now(),
+        })
+    }
+
+    /// Drops `tree` to be loaded again if it is old or failed.
+    pub fn expire(tree: &mut Option<Result<Self>>) {
+        if let Some(Ok(loaded)) = tree {
+            if loaded.loaded_at.elapsed() < Self::MAX_AGE {
+                return;
+            }
+        }
+        *tree = None;
+    }
+}
+
+/// `max` or a number, like `memory.max` and `pids.max`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Limit {
+    Max,
+    Value(u64),
+}
+
+impl Limit {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "max" => Some(Limit::Max),
+            value => value.parse().ok().map(Limit::Value),
+        }
+    }
+
+    fn format(&self, format: impl Fn(u64) -> String) -> String {
+        match self {
+            Limit::Max => "max".to_string(),
+            Limit::Value(value) => format(*value),
+        }
+    }
+}
+
+/// The CPU bandwidth limit from `cpu.max`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CpuMax {
+    /// Microseconds per period, `None` for no limit.
+    pub quota: Option<u64>,
+    pub period: u64,
+}
+
+impl CpuMax {
+    fn parse(contents: &str) -> Option<Self> {
+        let mut fields = contents.split_whitespace();
+        let quota = match Limit::parse(fields.next()?)? {
+            Limit::Max => None,
+            Limit::Value(quota) => Some(quota),
+        };
+        let period = fields.next()?.parse().ok()?;
+        Some(Self { quota, period })
+    }
+}
+
+impl std::fmt::Display for CpuMax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.quota {
+            Some(quota) => write!(
+                f,
+                "{:.2} CPUs ({}us per {}us)",
+                quota as f64 / self.period.max(1) as f64,
+                quota,
+                self.period
+            ),
+            None => write!(f, "max (period {}us)", self.period),
+        }
+    }
+}
+
+/// One line of `io.stat`.
+#[derive(Clone, Debug)]
+pub struct IoStat {
+    /// `major:minor`.
+    pub device: String,
+    pub values: Vec<(String, u64)>,
+}
+
+/// One line of a PSI file, see https://docs.kernel.org/accounting/psi.html
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PressureLine {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    /// Microseconds stalled in total.
+    pub total: u64,
+}
+
+impl std::fmt::Display for PressureLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.2} {:.2} {:.2} total {}",
+            self.avg10,
+            self.avg60,
+            self.avg300,
+            format_usec(self.total)
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pressure {
+    /// Some tasks were stalled.
+    pub some: Option<PressureLine>,
+    /// All non-idle tasks were stalled at the same time, not reported for CPU on older kernels.
+    pub full: Option<PressureLine>,
+}
+
+impl Pressure {
+    fn parse(contents: &str) -> Self {
+        let mut pressure = Self::default();
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            let kind = fields.next();
+            let mut parsed = PressureLine::default();
+            for (key, value) in fields.filter_map(|field| field.split_once('=')) {
+                match key {
+                    "avg10" => parsed.avg10 = value.parse().unwrap_or(0.0),
+                    "avg60" => parsed.avg60 = value.parse().unwrap_or(0.0),
+                    "avg300" => parsed.avg300 = value.parse().unwrap_or(0.0),
+                    "total" => parsed.total = value.parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+            match kind {
+                Some("some") => pressure.some = Some(parsed),
+                Some("full") => pressure.full = Some(parsed),
+                _ => {}
+            }
+        }
+        pressure
+    }
+}
+
+/// `key value` lines, like `cpu.stat` and `memory.events`.
+fn parse_flat_keyed(contents: &str) -> Vec<(String, u64)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// `major:minor key=value ...` lines.
+fn parse_io_stat(contents: &str) -> Vec<IoStat> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?.to_string();
+            let values = fields
+                .filter_map(|field| {
+                    let (key, value) = field.split_once('=')?;
+                    Some((key.to_string(), value.parse().ok()?))
+                })
+                .collect();
+            Some(IoStat { device, values })
+        })
+        .collect()
+}
+
+fn format_usec(usec: u64) -> String {
+    format!("{:.2}s", usec as f64 / 1e6)
+}
+
+/// Formats a value of a flat keyed file by the unit its key implies.
+fn format_value(key: &str, value: u64) -> String {
+    if key.ends_with("_usec") {
+        format_usec(value)
+    } else if key.ends_with("bytes") {
+        format_bytes(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// PSI files, `irq.pressure` only exists with CONFIG_IRQ_TIME_ACCOUNTING.
+const PRESSURE_FILES: [(&str, &str); 4] = [
+    ("cpu", "cpu.pressure"),
+    ("memory", "memory.pressure"),
+    ("io", "io.pressure"),
+    ("irq", "irq.pressure"),
+];
+
+/// Everything the interface files of a single cgroup tell, `None` or empty if the controller
+/// isn't enabled for it.
+pub struct CgroupStats {
+    pub path: String,
+    pub procs: Result<Vec<u64>>,
+    pub memory_current: Option<u64>,
+    pub memory_max: Option<Limit>,
+    pub memory_events: Vec<(String, u64)>,
+    pub cpu_stat: Vec<(String, u64)>,
+    pub cpu_max: Option<CpuMax>,
+    pub io_stat: Vec<IoStat>,
+    pub pids_current: Option<u64>,
+    pub pids_max: Option<Limit>,
+    pub pressure: Vec<(&'static str, Pressure)>,
+    /// Files that exist but could not be read.
+    pub errors: Vec<Error>,
+}
+
+impl CgroupStats {
+    /// `path` as shown in the tree, e.g. `/system.slice`.
+    pub fn load(source: &dyn ProcSource, path: &str) -> Self {
+        puffin::profile_function!();
+
+        let dir = Path::new(path.trim_start_matches('/'));
+        let mut errors = Vec::new();
+        let mut read = |name: &str| match read_optional(source, &dir.join(name)) {
+            Ok(contents) => contents,
+            Err(err) => {
+                errors.push(err);
+                None
+            }
+        };
+
+        let memory_current = read("memory.current").and_then(|c| c.trim().parse().ok());
+        let memory_max = read("memory.max").and_then(|c| Limit::parse(&c));
+        let memory_events = read("memory.events")
+            .map(|c| parse_flat_keyed(&c))
+            .unwrap_or_default();
+        let cpu_stat = read("cpu.stat")
+            .map(|c| parse_flat_keyed(&c))
+            .unwrap_or_default();
+        let cpu_max = read("cpu.max").and_then(|c| CpuMax::parse(&c));
+        let io_stat = read("io.stat")
+            .map(|c| parse_io_stat(&c))
+            .unwrap_or_default();
+        let pids_current = read("pids.current").and_then(|c| c.trim().parse().ok());
+        let pids_max = read("pids.max").and_then(|c| Limit::parse(&c));
+        let pressure = PRESSURE_FILES
+            .iter()
+            .filter_map(|(name, file)| Some((*name, Pressure::parse(&read(file)?))))
+            .collect();
+
+        let procs_path = dir.join("cgroup.procs");
+        let procs = procfs::read_to_string(source, &procs_path)
+            .and_then(|contents| parse_procs(&contents, &procs_path));
+
+        Self {
+            path: path.to_string(),
+            procs,
+            memory_current,
+            memory_max,
+            memory_events,
+            cpu_stat,
+            cpu_max,
+            io_stat,
+            pids_current,
+            pids_max,
+            pressure,
+            errors,
+        }
+    }
+
+    /// Headings with `name value` pairs, empty sections are left out.
+    pub fn sections(&self) -> Vec<(&'static str, Vec<(String, String)>)> {
+        let mut sections = Vec::new();
+
+        let mut memory = Vec::new();
+        if let Some(current) = self.memory_current {
+            memory.push(("current".to_string(), format_bytes(current)));
+        }
+        if let Some(max) = self.memory_max {
+            memory.push(("max".to_string(), max.format(format_bytes)));
+        }
+        memory.extend(
+            self.memory_events
+                .iter()
+                .map(|(key, value)| (format!("events {}", key), value.to_string())),
+        );
+        sections.push(("Memory", memory));
+
+        let mut cpu = Vec::new();
+        if let Some(max) = self.cpu_max {
+            cpu.push(("max".to_string(), max.to_string()));
+        }
+        cpu.extend(
+            self.cpu_stat
+                .iter()
+                .map(|(key, value)| (key.clone(), format_value(key, *value))),
+        );
+        sections.push(("CPU", cpu));
+
+        let io = self
+            .io_stat
+            .iter()
+            .map(|stat| {
+                let values = stat
+                    .values
+                    .iter()
+                    .map(|(key, value)| format!("{} {}", key, format_value(key, *value)))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                (stat.device.clone(), values)
+            })
+            .collect();
+        sections.push(("I/O", io));
+
+        let mut pids = Vec::new();
+        if let Some(current) = self.pids_current {
+            pids.push(("current".to_string(), current.to_string()));
+        }
+        if let Some(max) = self.pids_max {
+            pids.push(("max".to_string(), max.format(|max| max.to_string())));
+        }
+        sections.push(("Pids", pids));
+
+        let pressure = self
+            .pressure
+            .iter()
+            .flat_map(|(name, pressure)| {
+                [("some", pressure.some), ("full", pressure.full)]
+                    .into_iter()
+                    .filter_map(move |(kind, line)| {
+                        Some((format!("{} {}", name, kind), line?.to_string()))
+                    })
+            })
+            .collect();
+        sections.push(("Pressure (avg10 avg60 avg300)", pressure));
+
+        sections.retain(|(_, fields)| !fields.is_empty());
+        sections
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn show(&self, ui: &mut Ui) {
+        ui.label(RichText::new(&self.path).color(Color32::WHITE).strong());
+        match &self.procs {
+            Ok(procs) => {
+                let pids: Vec<String> = procs.iter().map(u64::to_string).collect();
+                ui.label(
+                    RichText::new(format!("{} processes: {}", procs.len(), pids.join(" ")))
+                        .color(Color32::LIGHT_GRAY),
+                );
+            }
+            Err(err) => {
+                ui.label(RichText::new(err.to_string()).color(Color32::YELLOW));
+            }
+        }
+
+        for (heading, fields) in self.sections() {
+            ui.separator();
+            ui.label(RichText::new(heading).color(Color32::WHITE).strong());
+            egui::Grid::new(heading).striped(true).show(ui, |ui| {
+                for (name, value) in fields {
+                    ui.label(RichText::new(name).color(Color32::WHITE));
+                    ui.label(RichText::new(value).color(Color32::LIGHT_GRAY));
+                    ui.end_row();
+                }
+            });
+        }
+
+        for error in &self.errors {
+            ui.label(RichText::new(error.to_string()).color(Color32::YELLOW));
+        }
+    }
+}
+
+/// The hierarchy as collapsible rows, returns the path of the cgroup that was clicked.
+#[cfg(feature = "gui")]
+pub fn show_cgroup_tree(ui: &mut Ui, node: &CgroupNode, selected: Option<&str>) -> Option<String> {
+    let mut clicked = None;
+    show_node(ui, node, 0, selected, &mut clicked);
+    clicked
+}
+
+#[cfg(feature = "gui")]
+fn show_node(
+    ui: &mut Ui,
+    node: &CgroupNode,
+    depth: usize,
+    selected: Option<&str>,
+    clicked: &mut Option<String>,
+) {
+    let mut label = |ui: &mut Ui| {
+        let mut text = format!("{} ({})", node.name(), node.total_procs());
+        if let Some(current) = node.memory_current {
+            text.push_str(&format!(" {}", format_bytes(current)));
+        }
+        let color = if node.procs.is_err() {
+            Color32::YELLOW
+        } else {
+            Color32::WHITE
+        };
+        let response = ui
+            .selectable_label(
+                selected == Some(node.path.as_str()),
+                RichText::new(text).color(color),
+            )
+            .on_hover_text("Processes in this cgroup and below it");
+        if response.clicked() {
+            *clicked = Some(node.path.clone());
+        }
+    };
+
+    if node.children.is_empty() {
+        ui.horizontal(|ui| {
+            // Where the toggle of a collapsible row would be.
+            ui.add_space(ui.spacing().indent);
+            label(ui);
+        });
+        return;
+    }
+
+    let id = ui.make_persistent_id(("cgroup", &node.path));
+    CollapsingState::load_with_default_open(ui.ctx(), id, depth < 2)
+        .show_header(ui, label)
+        .body(|ui| {
+            for child in &node.children {
+                show_node(ui, child, depth + 1, selected, clicked);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::procfs::MemorySource;
+
+    #[test]
+    fn limits() {
+        assert_eq!(Limit::parse("max\n"), Some(Limit::Max));
+        assert_eq!(Limit::parse("1073741824\n"), Some(Limit::Value(1 << 30)));
+        assert_eq!(Limit::parse("lots"), None);
+
+        let unlimited = CpuMax::parse("max 100000\n").unwrap();
+        assert_eq!(
+            unlimited,
+            CpuMax {
+                quota: None,
+                period: 100000
+            }
+        );
+        assert_eq!(unlimited.to_string(), "max (period 100000us)");
+        let half = CpuMax::parse("50000 100000\n").unwrap();
+        assert_eq!(half.quota, Some(50000));
+        assert_eq!(half.to_string(), "0.50 CPUs (50000us per 100000us)");
+        assert_eq!(CpuMax::parse("max"), None);
+        assert_eq!(CpuMax::parse(""), None);
+    }
+
+    #[test]
+    fn pressure() {
+        // CPU pressure of older kernels has no full line.
+        let cpu = Pressure::parse("some avg10=1.50 avg60=0.20 avg300=0.05 total=123456\n");
+        let some = cpu.some.unwrap();
+        assert_eq!((some.avg10, some.avg60, some.avg300), (1.5, 0.2, 0.05));
+        assert_eq!(some.total, 123456);
+        assert!(cpu.full.is_none());
+        assert_eq!(some.to_string(), "1.50 0.20 0.05 total 0.12s");
+
+        let memory = Pressure::parse(
+            "some avg10=0.00 avg60=0.00 avg300=0.00 total=10\n\
+             full avg10=0.00 avg60=0.00 avg300=0.00 total=5\n",
+        );
+        assert_eq!(memory.some.unwrap().total, 10);
+        assert_eq!(memory.full.unwrap().total, 5);
+        assert!(Pressure::parse("").some.is_none());
+    }
+
+    #[test]
+    fn keyed_files() {
+        let stat = parse_flat_keyed("usage_usec 1500000\nuser_usec 1000000\nbroken\nnr x\n");
+        assert_eq!(
+            stat,
+            [
+                ("usage_usec".to_string(), 1500000),
+                ("user_usec".to_string(), 1000000)
+            ]
+        );
+        assert_eq!(format_value("usage_usec", 1500000), "1.50s");
+        assert_eq!(format_value("nr_periods", 3), "3");
+
+        let io = parse_io_stat("8:0 rbytes=1024 wbytes=0 rios=2 wios=bad\n253:1 rbytes=5\n\n");
+        assert_eq!(io.len(), 2);
+        assert_eq!(io[0].device, "8:0");
+        assert_eq!(
+            io[0].values,
+            [
+                ("rbytes".to_string(), 1024),
+                ("wbytes".to_string(), 0),
+                ("rios".to_string(), 2)
+            ]
+        );
+        assert_eq!(io[1].values, [("rbytes".to_string(), 5)]);
+    }
+
+    #[test]
+    fn tree() {
+        let source = MemorySource::new()
+            .with("cgroup.procs", "1\n")
+            .with("cgroup.controllers", "cpu memory\n")
+            .with("system.slice/cgroup.procs", "")
+            .with("system.slice/cron.service/cgroup.procs", "100\n101\n")
+            .with("system.slice/cron.service/memory.current", "4096\n")
+            .with("user.slice/cgroup.procs", "200\n")
+            .with("broken.scope/cgroup.procs", "abc\n");
+        let tree = CgroupTree::load(&source).unwrap();
+
+        let nodes: Vec<(&str, &str, usize, usize)> = tree
+            .root
+            .flatten()
+            .into_iter()
+            .map(|(node, depth)| (node.path.as_str(), node.name(), depth, node.total_procs()))
+            .collect();
+        assert_eq!(
+            nodes,
+            [
+                ("/", "/", 0, 4),
+                ("/broken.scope", "broken.scope", 1, 0),
+                ("/system.slice", "system.slice", 1, 2),
+                ("/system.slice/cron.service", "cron.service", 2, 2),
+                ("/user.slice", "user.slice", 1, 1),
+            ]
+        );
+        let cron = &tree.root.children[1].children[0];
+        assert_eq!(cron.memory_current, Some(4096));
+        assert_eq!(cron.procs.as_ref().unwrap(), &[100, 101]);
+        assert_eq!(tree.root.memory_current, None);
+        let err = tree.root.children[0].procs.as_ref().unwrap_err();
+        assert_eq!(err.path, Path::new("broken.scope/cgroup.procs"));
+
+        assert!(CgroupTree::load(&MemorySource::new()).is_err());
+    }
+
+    #[test]
+    fn stats() {
+        // Only the cpu controller is enabled, memory and pids files don't exist.
+        let source = MemorySource::new()
+            .with("app.slice/cgroup.procs", "7\n")
+            .with("app.slice/cpu.max", "max 100000\n")
+            .with("app.slice/cpu.stat", "usage_usec 20\n")
+            .with(
+                "app.slice/cpu.pressure",
+                "some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n",
+            )
+            .with_error("app.slice/io.stat", io::ErrorKind::PermissionDenied);
+        let stats = CgroupStats::load(&source, "/app.slice");
+
+        assert_eq!(stats.procs.as_ref().unwrap(), &[7]);
+        assert_eq!(
+            stats.cpu_max,
+            Some(CpuMax {
+                quota: None,
+                period: 100000
+            })
+        );
+        assert_eq!(stats.cpu_stat, [("usage_usec".to_string(), 20)]);
+        assert!(stats.memory_current.is_none() && stats.memory_max.is_none());
+        assert!(stats.memory_events.is_empty() && stats.io_stat.is_empty());
+        assert!(stats.pids_current.is_none() && stats.pids_max.is_none());
+        assert_eq!(stats.pressure.len(), 1);
+        assert_eq!(stats.pressure[0].0, "cpu");
+        assert!(stats.pressure[0].1.full.is_none());
+
+        // Missing files are fine, unreadable ones are not.
+        let [err] = &stats.errors[..] else {
+            panic!("expected one error, got {:?}", stats.errors);
+        };
+        assert_eq!(err.path, Path::new("app.slice/io.stat"));
+        assert_eq!(err.kind, ErrorKind::PermissionDenied);
+
+        let sections = stats.sections();
+        let cpu = &sections.iter().find(|(name, _)| *name == "CPU").unwrap().1;
+        assert_eq!(
+            cpu[0],
+            ("max".to_string(), "max (period 100000us)".to_string())
+        );
+    }
+}
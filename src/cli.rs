@@ -0,0 +1,372 @@
+//! Headless subcommands, e.g. `linux-explorer list --format csv --query 'state:D'`.
+
+use std::{
+    io::{self, Write},
+    thread,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    cpu::CpuTracker,
+    process::{parse_processes, Process, ScanOptions},
+    procfs::ProcSource,
+    query::Query,
+    table::Column,
+    users::{current_uid, group_name, user_name},
+};
+
+pub const USAGE: &str = "\
+usage: linux-explorer [--record FILE | --replay FILE]
+       linux-explorer tui
+       linux-explorer list [OPTIONS]
+
+Options for list:
+  -f, --format FORMAT    table (default), csv or json
+  -q, --query QUERY      only processes matching the search query
+  -m, --mine             only processes started by or running as the current user
+  -c, --columns COLUMNS  comma separated, e.g. pid,state,rss,command
+  -s, --sort COLUMN      sort by a column, busy and big first for numbers
+  -r, --reverse          reverse the sort order
+  -i, --interval SECS    measure CPU usage over this long, 0 (default) leaves it empty
+
+Columns: pid nspid user group state cpu rss pss uss swap threads started command
+
+Reads PROC_ROOT instead of /proc if it is set.";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+pub struct ListOptions {
+    pub format: Format,
+    pub query: Query,
+    pub columns: Vec<Column>,
+    pub sort: Option<Column>,
+    pub reverse: bool,
+    pub interval: Duration,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        Self {
+            format: Format::Table,
+            query: Query::default(),
+            columns: [
+                Column::Pid,
+                Column::User,
+                Column::State,
+                Column::Cpu,
+                Column::Rss,
+                Column::Threads,
+                Column::Command,
+            ]
+            .into(),
+            sort: None,
+            reverse: false,
+            interval: Duration::ZERO,
+        }
+    }
+}
+
+impl ListOptions {
+    /// Parses the arguments after `list`.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = ListOptions::default();
+        let mut mine = false;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+
+            match arg.as_str() {
+                "-f" | "--format" => {
+                    options.format = match value()?.as_str() {
+                        "table" => Format::Table,
+                        "csv" => Format::Csv,
+                        "json" => Format::Json,
+                        format => return Err(format!("unknown format: {}", format)),
+                    }
+                }
+                "-q" | "--query" => {
+                    options.query =
+                        Query::parse(value()?).map_err(|err| format!("query: {}", err))?;
+                }
+                "-c" | "--columns" => {
+                    options.columns = value()?
+                        .split(',')
+                        .map(|key| parse_column(key.trim()))
+                        .collect::<Result<_, _>>()?;
+                }
+                "-m" | "--mine" => mine = true,
+                "-s" | "--sort" => options.sort = Some(parse_column(value()?)?),
+                "-r" | "--reverse" => options.reverse = true,
+                "-i" | "--interval" => {
+                    options.interval = value()?
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                        .ok_or("--interval needs a number of seconds")?;
+                }
+                arg => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+
+        if mine {
+            options.query = options.query.owned_by(current_uid());
+        }
+        Ok(options)
+    }
+}
+
+fn parse_column(key: &str) -> Result<Column, String> {
+    match Column::from_key(key) {
+        Some(column) if !column.is_history() => Ok(column),
+        _ => Err(format!("unknown column: {}", key)),
+    }
+}
+
+/// Runs `linux-explorer list` and returns the exit code.
+pub fn list(source: &dyn ProcSource, args: &[String]) -> i32 {
+    let options = match ListOptions::parse(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return 2;
+        }
+    };
+
+    let scan_options = ScanOptions {
+        rollup: options.query.needs_rollup()
+            || options.sort.is_some_and(|column| column.needs_rollup())
+            || options.columns.iter().any(Column::needs_rollup),
+        rollup_pid: None,
+    };
+    let processes = match scan(source, &scan_options, options.interval) {
+        Ok(processes) => processes,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+
+    let mut processes: Vec<Process> = processes
+        .into_iter()
+        .filter(|process| options.query.matches(process))
+        .collect();
+    if let Some(column) = options.sort {
+        processes.sort_by(|a, b| {
+            let ordering = column.compare(a, b);
+            if column.default_ascending() {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+    if options.reverse {
+        processes.reverse();
+    }
+
+    let mut out = io::stdout().lock();
+    let result = match options.format {
+        Format::Table => write_table(&mut out, &processes, &options.columns),
+        Format::Csv => write_csv(&mut out, &processes, &options.columns),
+        Format::Json => write_json(&mut out, &processes, &options.columns),
+    };
+
+    match result {
+        Ok(()) => 0,
+        // Piped into head.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+/// Scans twice if CPU usage is wanted, like `top -b -n 2`.
+fn scan(
+    source: &dyn ProcSource,
+    options: &ScanOptions,
+    interval: Duration,
+) -> crate::error::Result<Vec<Process>> {
+    let mut scan = parse_processes(source, options)?;
+    if !interval.is_zero() {
+        let mut cpu = CpuTracker::default();
+        cpu.update(source, &mut scan.processes);
+        thread::sleep(interval);
+        scan = parse_processes(source, options)?;
+        cpu.update(source, &mut scan.processes);
+    }
+    Ok(scan.processes)
+}
+
+/// Raw values for machines, bytes instead of `1.5G` and seconds since the epoch for times.
+fn value(column: Column, process: &Process) -> Value {
+    match column {
+        Column::Pid => json!(process.pid),
+        Column::NsPid => json!(process.ns_pid()),
+        Column::User => json!(process.uid().map(user_name)),
+        Column::Group => json!(process.gid().map(group_name)),
+        Column::State => json!((process.stats.state.code() as char).to_string()),
+        Column::Cpu => json!(process.cpu_usage),
+        Column::Rss => json!(process.stats.rss_bytes()),
+        Column::Pss => json!(process.memory.pss()),
+        Column::Uss => json!(process.memory.uss()),
+        Column::Swap => json!(process.memory.swap()),
+        Column::Threads => json!(process.stats.num_threads),
+        Column::StartTime => json!(process
+            .start_time
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_secs())),
+        Column::Command => json!(process.command()),
+        Column::CpuHistory | Column::RssHistory => Value::Null,
+    }
+}
+
+pub fn write_json(
+    out: &mut dyn Write,
+    processes: &[Process],
+    columns: &[Column],
+) -> io::Result<()> {
+    let rows: Vec<Value> = processes
+        .iter()
+        .map(|process| {
+            let row: Map<String, Value> = columns
+                .iter()
+                .map(|column| (column.key().to_string(), value(*column, process)))
+                .collect();
+            Value::Object(row)
+        })
+        .collect();
+
+    serde_json::to_writer_pretty(&mut *out, &rows)?;
+    writeln!(out)
+}
+
+pub fn write_csv(out: &mut dyn Write, processes: &[Process], columns: &[Column]) -> io::Result<()> {
+    let header: Vec<&str> = columns.iter().map(Column::key).collect();
+    writeln!(out, "{}", header.join(","))?;
+
+    for process in processes {
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|column| match value(*column, process) {
+                Value::Null => String::new(),
+                Value::String(text) => csv_field(&text),
+                value => value.to_string(),
+            })
+            .collect();
+        writeln!(out, "{}", fields.join(","))?;
+    }
+
+    Ok(())
+}
+
+/// Quotes fields with separators, quotes or line breaks as RFC 4180 wants.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Aligned columns like `ps`, the last one isn't padded.
+pub fn write_table(
+    out: &mut dyn Write,
+    processes: &[Process],
+    columns: &[Column],
+) -> io::Result<()> {
+    let mut rows = vec![columns
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect::<Vec<_>>()];
+    rows.extend(processes.iter().map(|process| {
+        columns
+            .iter()
+            // Keep one process per line, arguments may contain anything.
+            .map(|column| column.text(process).replace(char::is_control, " "))
+            .collect::<Vec<_>>()
+    }));
+
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for row in rows {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            if i + 1 == row.len() {
+                line.push_str(cell);
+            } else {
+                line.push_str(&format!("{:width$}  ", cell, width = widths[i]));
+            }
+        }
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<ListOptions, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        ListOptions::parse(&args)
+    }
+
+    #[test]
+    fn interval() {
+        let options = parse(&["--interval", "0.5"]).unwrap();
+        assert_eq!(options.interval, Duration::from_millis(500));
+        assert_eq!(parse(&["-i", "0"]).unwrap().interval, Duration::ZERO);
+
+        for secs in ["1e20", "-1", "NaN", "inf", "1s", ""] {
+            assert_eq!(
+                parse(&["-i", secs]).err().as_deref(),
+                Some("--interval needs a number of seconds"),
+                "{}",
+                secs
+            );
+        }
+        assert_eq!(
+            parse(&["--interval"]).err().as_deref(),
+            Some("--interval needs a value")
+        );
+    }
+
+    #[test]
+    fn arguments() {
+        let options = parse(&["-f", "json", "-c", "pid, rss", "-s", "rss", "-r"]).unwrap();
+        assert!(options.format == Format::Json);
+        assert_eq!(options.columns, [Column::Pid, Column::Rss]);
+        assert_eq!(options.sort, Some(Column::Rss));
+        assert!(options.reverse);
+
+        assert_eq!(
+            parse(&["-f", "xml"]).err().as_deref(),
+            Some("unknown format: xml")
+        );
+        assert_eq!(
+            parse(&["--frobnicate"]).err().as_deref(),
+            Some("unknown argument: --frobnicate")
+        );
+        assert!(parse(&["-q", "foo:bar"])
+            .err()
+            .is_some_and(|err| err.starts_with("query: ")));
+    }
+}
@@ -0,0 +1,111 @@
+use std::{collections::HashMap, time::Instant};
+
+use crate::{
+    process::{Process, ProcessStats},
+    procfs::ProcSource,
+    system::KernelStat,
+};
+
+/// Clock ticks per second, the unit of all times in `/proc/[pid]/stat`.
+pub fn clock_ticks() -> u64 {
+    // SAFETY: sysconf has no preconditions.
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 {
+        ticks as u64
+    } else {
+        100
+    }
+}
+
+/// Remembers CPU times between samples to turn them into usage percentages.
+pub struct CpuTracker {
+    clock_ticks: u64,
+    /// `utime + stime` by pid and start time, the latter guards against reused pids.
+    previous: HashMap<(u64, u64), u64>,
+    /// Same for threads, kept apart because the main thread shares the process' key.
+    previous_threads: HashMap<(u64, u64), u64>,
+    /// [`crate::system::CpuStat::total`] of all CPUs.
+    previous_total: Option<u64>,
+    previous_instant: Option<Instant>,
+}
+
+impl Default for CpuTracker {
+    fn default() -> Self {
+        Self {
+            clock_ticks: clock_ticks(),
+            previous: HashMap::new(),
+            previous_threads: HashMap::new(),
+            previous_total: None,
+            previous_instant: None,
+        }
+    }
+}
+
+impl CpuTracker {
+    /// Sets [`Process::cpu_usage`] and the usage of all threads based on the time since the last
+    /// update.
+    ///
+    /// 100% means one fully used core, so multi-threaded processes can go above that.
+    pub fn update(&mut self, source: &dyn ProcSource, processes: &mut [Process]) {
+        let now = Instant::now();
+        let stat = KernelStat::parse(source).ok();
+        let total = stat.as_ref().map(|stat| stat.total.total());
+
+        // Fall back to wall clock time if /proc/stat is unreadable.
+        let elapsed_ticks = match (&stat, total, self.previous_total) {
+            (Some(stat), Some(total), Some(previous)) if total > previous => {
+                Some((total - previous) as f64 / stat.cpus.len().max(1) as f64)
+            }
+            _ => self
+                .previous_instant
+                .map(|previous| (now - previous).as_secs_f64() * self.clock_ticks as f64),
+        };
+
+        let mut current = HashMap::with_capacity(processes.len());
+        let mut current_threads = HashMap::with_capacity(self.previous_threads.len());
+        for process in processes.iter_mut() {
+            process.cpu_usage = usage(
+                &self.previous,
+                &mut current,
+                process.pid,
+                &process.stats,
+                elapsed_ticks,
+            );
+
+            for thread in &mut process.threads {
+                thread.cpu_usage = usage(
+                    &self.previous_threads,
+                    &mut current_threads,
+                    thread.tid,
+                    &thread.stats,
+                    elapsed_ticks,
+                );
+            }
+        }
+
+        self.previous = current;
+        self.previous_threads = current_threads;
+        self.previous_total = total;
+        self.previous_instant = Some(now);
+    }
+}
+
+/// Remembers the ticks of `stats` in `current` and compares them to `previous`.
+fn usage(
+    previous: &HashMap<(u64, u64), u64>,
+    current: &mut HashMap<(u64, u64), u64>,
+    id: u64,
+    stats: &ProcessStats,
+    elapsed_ticks: Option<f64>,
+) -> Option<f64> {
+    let key = (id, stats.starttime);
+    let ticks = stats.utime + stats.stime;
+    current.insert(key, ticks);
+
+    match (previous.get(&key), elapsed_ticks) {
+        (Some(&previous), Some(elapsed)) if elapsed > 0.0 => {
+            Some(ticks.saturating_sub(previous) as f64 / elapsed * 100.0)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,202 @@
+#[cfg(feature = "gui")]
+use std::time::SystemTime;
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Ui};
+
+use crate::{
+    error::Result,
+    fd::{parse_fds, Fd},
+    maps::{parse_maps, Mapping, MapsSettings},
+    net::{parse_process_sockets, ProcessSockets},
+    procfs::ProcSource,
+    sched::parse_cpus_allowed,
+};
+#[cfg(feature = "gui")]
+use crate::{
+    fd::show_fds,
+    history::{show_chart, ProcessHistory, Unit},
+    maps::show_maps,
+    net::show_process_sockets,
+    process::Process,
+    sched::{show_scheduling, SchedControls},
+};
+
+/// Tabs of the details panel.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum DetailsTab {
+    #[default]
+    Overview,
+    History,
+    Files,
+    Maps,
+    Sockets,
+    Scheduling,
+}
+
+impl DetailsTab {
+    pub const ALL: [DetailsTab; 6] = [
+        DetailsTab::Overview,
+        DetailsTab::History,
+        DetailsTab::Files,
+        DetailsTab::Maps,
+        DetailsTab::Sockets,
+        DetailsTab::Scheduling,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DetailsTab::Overview => "Overview",
+            DetailsTab::History => "History",
+            DetailsTab::Files => "Files",
+            DetailsTab::Maps => "Maps",
+            DetailsTab::Sockets => "Sockets",
+            DetailsTab::Scheduling => "Scheduling",
+        }
+    }
+
+    /// What the tab shows if a recording lacks it, recordings only hold what the process list
+    /// reads.
+    pub fn not_recorded(&self) -> Option<&'static str> {
+        match self {
+            DetailsTab::Files => Some("open files"),
+            DetailsTab::Maps => Some("memory maps"),
+            DetailsTab::Sockets => Some("sockets"),
+            _ => None,
+        }
+    }
+}
+
+/// What the details panel shows, kept while switching between processes.
+#[derive(Default)]
+pub struct DetailsView {
+    pub tab: DetailsTab,
+    pub maps: MapsSettings,
+    #[cfg(feature = "gui")]
+    pub scheduling: SchedControls,
+}
+
+/// Things that are too expensive to read for every process in every sample, so they are only
+/// read for the selected one, and only for the tab that is shown.
+///
+/// Each field is `None` until its tab was shown, the details are dropped with every new sample.
+pub struct ProcessDetails {
+    pub pid: u64,
+    pub fds: Option<Result<Vec<Fd>>>,
+    pub maps: Option<Result<Vec<Mapping>>>,
+    pub sockets: Option<Result<ProcessSockets>>,
+    pub cpus_allowed: Option<Result<Vec<usize>>>,
+}
+
+impl ProcessDetails {
+    pub fn new(pid: u64) -> Self {
+        Self {
+            pid,
+            fds: None,
+            maps: None,
+            sockets: None,
+            cpus_allowed: None,
+        }
+    }
+
+    /// Reads what `tab` shows unless that was read already.
+    pub fn load(&mut self, source: &dyn ProcSource, tab: DetailsTab) {
+        puffin::profile_function!();
+
+        let pid = self.pid;
+        match tab {
+            DetailsTab::Overview | DetailsTab::History => {}
+            DetailsTab::Files => {
+                self.fds.get_or_insert_with(|| parse_fds(source, pid));
+            }
+            DetailsTab::Maps => {
+                self.maps.get_or_insert_with(|| parse_maps(source, pid));
+            }
+            DetailsTab::Sockets => {
+                let fds = self.fds.get_or_insert_with(|| parse_fds(source, pid));
+                if self.sockets.is_none() {
+                    self.sockets = Some(
+                        fds.as_ref()
+                            .map_err(Clone::clone)
+                            .and_then(|fds| parse_process_sockets(source, pid, fds)),
+                    );
+                }
+            }
+            DetailsTab::Scheduling => {
+                self.cpus_allowed
+                    .get_or_insert_with(|| parse_cpus_allowed(source, pid));
+            }
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        source: &dyn ProcSource,
+        process: &Process,
+        history: Option<&ProcessHistory>,
+        view: &mut DetailsView,
+        // When the frame was taken if the process is part of a recording, `None` while live.
+        recorded_at: Option<SystemTime>,
+    ) {
+        ui.horizontal(|ui| {
+            for tab in DetailsTab::ALL {
+                ui.selectable_value(&mut view.tab, tab, tab.name());
+            }
+        });
+        ui.separator();
+
+        if let (Some(_), Some(missing)) = (recorded_at, view.tab.not_recorded()) {
+            show_error(ui, &format!("Recordings don't include {}", missing));
+            return;
+        }
+        self.load(source, view.tab);
+        let now = recorded_at.unwrap_or_else(SystemTime::now);
+        egui::ScrollArea::both()
+            .auto_shrink(false)
+            .show(ui, |ui| match view.tab {
+                DetailsTab::Overview => process.show_details(ui),
+                DetailsTab::History => match history {
+                    Some(history) => {
+                        let cpu = [("CPU%", &history.cpu)];
+                        show_chart(ui, "cpu_history", Unit::Percent, now, &cpu);
+                        show_chart(
+                            ui,
+                            "rss_history",
+                            Unit::Bytes,
+                            now,
+                            &[("RSS", &history.rss)],
+                        );
+                    }
+                    None => show_error(ui, &"No history yet"),
+                },
+                DetailsTab::Files => match &self.fds {
+                    Some(Ok(fds)) => show_fds(ui, fds),
+                    Some(Err(err)) => show_error(ui, err),
+                    None => {}
+                },
+                DetailsTab::Maps => match &self.maps {
+                    Some(Ok(maps)) => show_maps(ui, maps, &mut view.maps),
+                    Some(Err(err)) => show_error(ui, err),
+                    None => {}
+                },
+                DetailsTab::Sockets => match &self.sockets {
+                    Some(Ok(sockets)) => show_process_sockets(ui, sockets),
+                    Some(Err(err)) => show_error(ui, err),
+                    None => {}
+                },
+                DetailsTab::Scheduling => {
+                    if let Some(cpus_allowed) = &self.cpus_allowed {
+                        let live = recorded_at.is_none();
+                        show_scheduling(ui, process, cpus_allowed, &mut view.scheduling, live)
+                    }
+                }
+            });
+    }
+}
+
+#[cfg(feature = "gui")]
+fn show_error(ui: &mut Ui, err: &impl std::fmt::Display) {
+    ui.label(RichText::new(err.to_string()).color(Color32::YELLOW));
+}
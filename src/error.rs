@@ -0,0 +1,72 @@
+use std::{fmt::Display, io, path::PathBuf};
+
+/// Failure to read or parse a single procfs file.
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub path: PathBuf,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The file is gone, usually because the process exited mid-scan.
+    NotFound,
+    PermissionDenied,
+    /// The process exited while the file was being read.
+    NoSuchProcess,
+    Io(io::ErrorKind),
+    Malformed(String),
+}
+
+impl ErrorKind {
+    /// Kind of the last failed syscall that took a pid.
+    pub fn last_os_error() -> Self {
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::EPERM) => ErrorKind::PermissionDenied,
+            Some(libc::ESRCH) => ErrorKind::NoSuchProcess,
+            _ => ErrorKind::Io(err.kind()),
+        }
+    }
+}
+
+impl Error {
+    pub fn io(path: impl Into<PathBuf>, err: io::Error) -> Self {
+        let kind = match err.kind() {
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            _ if err.raw_os_error() == Some(libc::ESRCH) => ErrorKind::NoSuchProcess,
+            kind => ErrorKind::Io(kind),
+        };
+
+        Self {
+            path: path.into(),
+            kind,
+        }
+    }
+
+    pub fn malformed(path: impl Into<PathBuf>, reason: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            kind: ErrorKind::Malformed(reason.into()),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::NotFound => write!(f, "{}: not found", self.path.display()),
+            ErrorKind::PermissionDenied => {
+                write!(f, "{}: permission denied", self.path.display())
+            }
+            ErrorKind::NoSuchProcess => write!(f, "{}: no such process", self.path.display()),
+            ErrorKind::Io(kind) => write!(f, "{}: {}", self.path.display(), kind),
+            ErrorKind::Malformed(reason) => write!(f, "{}: {}", self.path.display(), reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
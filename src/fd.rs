@@ -0,0 +1,305 @@
+use std::{fmt::Display, path::Path};
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Ui};
+
+use crate::{
+    error::Result,
+    procfs::{self, KeyValues, ProcSource},
+};
+
+/// An open file descriptor from `/proc/[pid]/fd` and `/proc/[pid]/fdinfo`.
+#[derive(Clone)]
+pub struct Fd {
+    pub fd: u32,
+    /// What the fd symlink points to.
+    pub target: String,
+    pub kind: FdKind,
+    /// The file was unlinked after it was opened.
+    pub deleted: bool,
+    pub info: Option<FdInfo>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FdKind {
+    File,
+    Directory,
+    Pipe,
+    Socket,
+    Eventfd,
+    Epoll,
+    Timerfd,
+    Signalfd,
+    Bpf,
+    /// Any other `anon_inode:`, e.g. inotify or io_uring.
+    AnonInode,
+    Memfd,
+    Device,
+}
+
+impl FdKind {
+    pub const ALL: [FdKind; 12] = [
+        FdKind::File,
+        FdKind::Directory,
+        FdKind::Pipe,
+        FdKind::Socket,
+        FdKind::Eventfd,
+        FdKind::Epoll,
+        FdKind::Timerfd,
+        FdKind::Signalfd,
+        FdKind::Bpf,
+        FdKind::AnonInode,
+        FdKind::Memfd,
+        FdKind::Device,
+    ];
+
+    /// `target` without ` (deleted)`, `link` is the fd symlink whose file type is checked for
+    /// paths, since e.g. `/dev/shm` holds regular files.
+    fn classify(source: &dyn ProcSource, target: &str, link: &Path) -> Self {
+        if target.starts_with("socket:") {
+            FdKind::Socket
+        } else if target.starts_with("pipe:") {
+            FdKind::Pipe
+        } else if let Some(anon) = target.strip_prefix("anon_inode:") {
+            match anon.trim_matches(|c| c == '[' || c == ']') {
+                "eventfd" => FdKind::Eventfd,
+                "eventpoll" => FdKind::Epoll,
+                "timerfd" => FdKind::Timerfd,
+                "signalfd" => FdKind::Signalfd,
+                anon if anon.starts_with("bpf") => FdKind::Bpf,
+                _ => FdKind::AnonInode,
+            }
+        } else if target.starts_with("/memfd:") {
+            FdKind::Memfd
+        } else if source.is_device(link).unwrap_or(false) {
+            FdKind::Device
+        } else if source.is_dir(link).unwrap_or(false) {
+            FdKind::Directory
+        } else {
+            FdKind::File
+        }
+    }
+}
+
+impl Display for FdKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FdKind::File => write!(f, "File"),
+            FdKind::Directory => write!(f, "Directory"),
+            FdKind::Pipe => write!(f, "Pipe"),
+            FdKind::Socket => write!(f, "Socket"),
+            FdKind::Eventfd => write!(f, "eventfd"),
+            FdKind::Epoll => write!(f, "epoll"),
+            FdKind::Timerfd => write!(f, "timerfd"),
+            FdKind::Signalfd => write!(f, "signalfd"),
+            FdKind::Bpf => write!(f, "bpf"),
+            FdKind::AnonInode => write!(f, "anon_inode"),
+            FdKind::Memfd => write!(f, "memfd"),
+            FdKind::Device => write!(f, "Device"),
+        }
+    }
+}
+
+/// `/proc/[pid]/fdinfo/[fd]`.
+#[derive(Clone, Copy)]
+pub struct FdInfo {
+    pub pos: u64,
+    /// The `O_*` flags the file was opened with.
+    pub flags: i32,
+    /// Since 3.15.
+    pub mnt_id: Option<u64>,
+}
+
+impl FdInfo {
+    /// Access mode and the flags that are most interesting when hunting down an fd.
+    pub fn describe_flags(&self) -> String {
+        let mut flags = vec![match self.flags & libc::O_ACCMODE {
+            libc::O_RDONLY => "O_RDONLY",
+            libc::O_WRONLY => "O_WRONLY",
+            _ => "O_RDWR",
+        }];
+
+        for (flag, name) in [
+            (libc::O_APPEND, "O_APPEND"),
+            (libc::O_NONBLOCK, "O_NONBLOCK"),
+            (libc::O_CLOEXEC, "O_CLOEXEC"),
+            (libc::O_DIRECT, "O_DIRECT"),
+            (libc::O_SYNC, "O_SYNC"),
+            (libc::O_PATH, "O_PATH"),
+        ] {
+            if self.flags & flag == flag {
+                flags.push(name);
+            }
+        }
+
+        flags.join("|")
+    }
+}
+
+/// Fds closed while we read them are skipped.
+pub fn parse_fds(source: &dyn ProcSource, pid: u64) -> Result<Vec<Fd>> {
+    let dir = Path::new(&pid.to_string()).to_path_buf();
+    let fd_dir = dir.join("fd");
+
+    let mut fds = Vec::new();
+    for name in procfs::read_dir(source, &fd_dir)? {
+        let Some(fd) = name.to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+
+        let link = fd_dir.join(&name);
+        let Ok(target) = procfs::read_link(source, &link) else {
+            continue;
+        };
+        let target = target.to_string_lossy().to_string();
+
+        let (path, deleted) = match target.strip_suffix(" (deleted)") {
+            Some(path) => (path, true),
+            None => (target.as_str(), false),
+        };
+        let kind = FdKind::classify(source, path, &link);
+        // memfds have no name in any directory, so the kernel always calls them deleted.
+        let deleted = deleted && kind != FdKind::Memfd;
+
+        let info = KeyValues::read(source, &dir.join("fdinfo").join(&name))
+            .ok()
+            .and_then(|info| {
+                Some(FdInfo {
+                    pos: info.get("pos")?.parse().ok()?,
+                    flags: i32::from_str_radix(info.get("flags")?, 8).ok()?,
+                    mnt_id: info.get("mnt_id").and_then(|id| id.parse().ok()),
+                })
+            });
+
+        fds.push(Fd {
+            fd,
+            target,
+            kind,
+            deleted,
+            info,
+        });
+    }
+
+    fds.sort_by_key(|fd| fd.fd);
+    Ok(fds)
+}
+
+#[cfg(feature = "gui")]
+pub fn show_fds(ui: &mut Ui, fds: &[Fd]) {
+    puffin::profile_function!();
+
+    ui.horizontal_wrapped(|ui| {
+        for kind in FdKind::ALL {
+            let count = fds.iter().filter(|fd| fd.kind == kind).count();
+            if count > 0 {
+                ui.label(RichText::new(format!("{} {}", count, kind)).color(Color32::WHITE));
+                ui.separator();
+            }
+        }
+
+        let deleted = fds.iter().filter(|fd| fd.deleted).count();
+        if deleted > 0 {
+            ui.label(RichText::new(format!("{} deleted", deleted)).color(Color32::RED));
+        }
+    });
+
+    egui::Grid::new("fds").striped(true).show(ui, |ui| {
+        for header in ["FD", "Type", "Target", "Pos", "Flags", "Mount"] {
+            ui.label(RichText::new(header).color(Color32::WHITE));
+        }
+        ui.end_row();
+
+        for fd in fds {
+            let color = if fd.deleted {
+                Color32::RED
+            } else {
+                Color32::LIGHT_GRAY
+            };
+
+            ui.label(RichText::new(fd.fd.to_string()).color(color));
+            ui.label(RichText::new(fd.kind.to_string()).color(color));
+            let target = ui.label(RichText::new(&fd.target).color(color));
+            if fd.deleted {
+                target.on_hover_text("The file was deleted but is still held open");
+            }
+
+            match &fd.info {
+                Some(info) => {
+                    ui.label(RichText::new(info.pos.to_string()).color(color));
+                    ui.label(RichText::new(format!("{:o}", info.flags)).color(color))
+                        .on_hover_text(info.describe_flags());
+                    let mnt_id = info.mnt_id.map(|id| id.to_string()).unwrap_or_default();
+                    ui.label(RichText::new(mnt_id).color(color));
+                }
+                None => {
+                    ui.label("");
+                    ui.label("");
+                    ui.label("");
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs::MemorySource;
+
+    #[test]
+    fn kinds() {
+        let source = MemorySource::new()
+            .with_link("7/fd/0", "/dev/pts/3")
+            .with_device("7/fd/0")
+            .with_link("7/fd/1", "pipe:[1234]")
+            .with_link("7/fd/2", "socket:[5678]")
+            .with_link("7/fd/3", "/memfd:wayland-shm (deleted)")
+            .with_link("7/fd/4", "/var/log/app.log (deleted)")
+            .with_link("7/fd/5", "/dev/shm/pulse-shm-42")
+            .with_link("7/fd/6", "anon_inode:[eventpoll]")
+            .with_link("7/fd/7", "anon_inode:bpf-map")
+            .with_link("7/fd/8", "anon_inode:inotify")
+            .with_link("7/fd/9", "/home/user")
+            .with("7/fd/9/.bashrc", "")
+            .with_link("7/fd/10", "/dev/shm/gone (deleted)")
+            .with("7/fdinfo/4", "pos:\t42\nflags:\t02102001\nmnt_id:\t29\n");
+
+        let fds = parse_fds(&source, 7).unwrap();
+        let kinds: Vec<(u32, FdKind, bool)> =
+            fds.iter().map(|fd| (fd.fd, fd.kind, fd.deleted)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (0, FdKind::Device, false),
+                (1, FdKind::Pipe, false),
+                (2, FdKind::Socket, false),
+                (3, FdKind::Memfd, false),
+                (4, FdKind::File, true),
+                (5, FdKind::File, false),
+                (6, FdKind::Epoll, false),
+                (7, FdKind::Bpf, false),
+                (8, FdKind::AnonInode, false),
+                (9, FdKind::Directory, false),
+                (10, FdKind::File, true),
+            ]
+        );
+        assert_eq!(fds[3].target, "/memfd:wayland-shm (deleted)");
+
+        let info = fds[4].info.unwrap();
+        assert_eq!((info.pos, info.mnt_id), (42, Some(29)));
+        assert_eq!(info.describe_flags(), "O_WRONLY|O_APPEND|O_CLOEXEC");
+        assert!(fds[0].info.is_none());
+    }
+
+    #[test]
+    fn closed_while_reading() {
+        let source = MemorySource::new()
+            .with_link("7/fd/1", "pipe:[1]")
+            .with_error("7/fd/2", std::io::ErrorKind::NotFound)
+            .with("7/fd/not-a-number", "");
+        let fds = parse_fds(&source, 7).unwrap();
+        assert_eq!(fds.len(), 1);
+        assert!(parse_fds(&source, 8).is_err());
+    }
+}
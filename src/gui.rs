@@ -0,0 +1,1098 @@
+//! The egui frontend.
+
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use eframe::NativeOptions;
+use egui::{
+    CentralPanel, Color32, DragValue, FontFamily, FontId, RichText, SidePanel, TextEdit, TextStyle,
+    TopBottomPanel, Widget,
+};
+
+use crate::{
+    cgroup::{group_by_workload, show_workload_groups, GroupSort},
+    cgroupfs::{find_root, show_cgroup_tree, CgroupStats, CgroupTree},
+    cpu::CpuTracker,
+    details::{DetailsView, ProcessDetails},
+    error::{Error, Result},
+    history::History,
+    namespace::{list_namespaces, show_namespaces, NamespaceInfo, NamespaceKind, Owners},
+    net::{show_connections, Connections, ConnectionsSettings},
+    process::{parse_processes, Scan, ScanOptions},
+    procfs::{DirSource, ProcSource},
+    query::{self, Query, QueryError},
+    recording::{Recorder, Replay},
+    sampler::Sampler,
+    signal::{describe_target, target_warning, SignalReport, SignalRequest},
+    system::{parse_system, show_system, SystemInfo, SystemTracker},
+    table::{ColumnSettings, ProcessRows, ProcessTable},
+    time,
+    users::{current_uid, user_name},
+};
+
+/// Opens the window, `record` starts a recording right away.
+pub fn run(record: Option<PathBuf>, replay: Option<Replay>) -> eframe::Result<()> {
+    eframe::run_native(
+        "Linux Explorer",
+        NativeOptions::default(),
+        Box::new(|cc| {
+            let mut app = App::new(cc, Arc::new(DirSource::from_env()), replay);
+            if let Some(path) = record {
+                app.record_path = path.display().to_string();
+                app.start_recording();
+            }
+            Box::new(app)
+        }),
+    )
+}
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+/// The system counters are cheap to read, so they are sampled more often than processes.
+const SYSTEM_INTERVAL: Duration = Duration::from_secs(1);
+const COLUMNS_KEY: &str = "columns";
+
+/// What the central panel shows.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    Processes,
+    Connections,
+    Cgroups,
+    Namespaces,
+    System,
+}
+
+/// Playback state of a recording opened with `--replay`.
+struct Timeline {
+    replay: Replay,
+    playing: bool,
+    speed: f64,
+    last_step: Instant,
+}
+
+struct App {
+    /// Procfs while live, the current frame while replaying.
+    source: Arc<dyn ProcSource>,
+    /// Only the process sampler reads through this, so every frame is a single sample.
+    recorder: Arc<Recorder>,
+    record_path: String,
+    record_compressed: bool,
+    record_error: Option<Error>,
+    timeline: Option<Timeline>,
+    sampler: Sampler<Result<Scan>>,
+    /// What the sampler reads, depending on what is shown.
+    scan_options: Arc<Mutex<ScanOptions>>,
+    scan: Scan,
+    /// Set if the last scan failed as a whole, e.g. because the procfs root is missing.
+    scan_error: Option<Error>,
+    last_sample: Option<SystemTime>,
+    profiling: bool,
+    search_text: String,
+    /// Last query that parsed, kept while the search text has a syntax error.
+    query: Query,
+    query_error: Option<QueryError>,
+    columns: ColumnSettings,
+    tree_mode: bool,
+    /// Pids whose children are hidden in tree mode.
+    collapsed: HashSet<u64>,
+    /// Pids whose threads are shown below them.
+    expanded_threads: HashSet<u64>,
+    /// Show every thread as its own row instead of processes, like `top -H`.
+    threads_as_rows: bool,
+    /// Only show processes started by or running as the current user.
+    mine_only: bool,
+    /// Show containers, pods and systemd units with their combined usage instead of processes.
+    group_mode: bool,
+    group_sort: GroupSort,
+    /// Pid of the process shown in the details panel.
+    selected: Option<u64>,
+    details: Option<ProcessDetails>,
+    details_view: DetailsView,
+    view: View,
+    /// Loaded when the connections view is open, dropped with every new sample.
+    connections: Option<Result<Connections>>,
+    connections_settings: ConnectionsSettings,
+    /// Where cgroup2 is mounted, `None` if it isn't.
+    cgroup_root: Option<PathBuf>,
+    /// Loaded when the cgroups view is open, dropped with every new sample like the connections.
+    cgroup_tree: Option<Result<CgroupTree>>,
+    selected_cgroup: Option<String>,
+    cgroup_stats: Option<CgroupStats>,
+    /// Built when the namespaces view is open, dropped with every new sample.
+    namespaces: Option<Vec<NamespaceInfo>>,
+    namespace_owners: Owners,
+    /// Only namespaces of this kind are listed.
+    namespace_kind: Option<NamespaceKind>,
+    system_sampler: Sampler<Result<SystemInfo>>,
+    system: Option<Result<SystemInfo>>,
+    history: History,
+    /// Pids picked with Ctrl+click.
+    marked: HashSet<u64>,
+    /// Picked from the context menu, waiting for confirmation.
+    signal_request: Option<SignalRequest>,
+    signal_report: Option<SignalReport>,
+    /// The report window is open, only if sending failed somewhere.
+    show_signal_errors: bool,
+}
+
+impl App {
+    fn new(
+        cc: &eframe::CreationContext,
+        source: Arc<dyn ProcSource>,
+        replay: Option<Replay>,
+    ) -> Self {
+        let recorder = Arc::new(Recorder::new(source.clone()));
+
+        let ctx = cc.egui_ctx.clone();
+        let mut cpu = CpuTracker::default();
+        let sampler_recorder = recorder.clone();
+        let scan_options = Arc::new(Mutex::new(ScanOptions::default()));
+        let sampler_options = scan_options.clone();
+        let mut sampler = Sampler::spawn(
+            DEFAULT_INTERVAL,
+            move || {
+                let options = sampler_options.lock().unwrap().clone();
+                let mut scan = parse_processes(sampler_recorder.as_ref(), &options)?;
+                cpu.update(sampler_recorder.as_ref(), &mut scan.processes);
+                // The system panel of a replay is parsed from the same frame, the system sampler
+                // runs on its own schedule.
+                if sampler_recorder.recording().is_some() {
+                    let _ = parse_system(sampler_recorder.as_ref());
+                }
+                sampler_recorder.finish_frame(SystemTime::now());
+                Ok(scan)
+            },
+            move || ctx.request_repaint(),
+        );
+
+        let ctx = cc.egui_ctx.clone();
+        let mut tracker = SystemTracker::default();
+        let system_source = source.clone();
+        let mut system_sampler = Sampler::spawn(
+            SYSTEM_INTERVAL,
+            move || {
+                let mut system = parse_system(system_source.as_ref())?;
+                tracker.update(&mut system);
+                Ok(system)
+            },
+            move || ctx.request_repaint(),
+        );
+
+        if replay.is_some() {
+            sampler.pause();
+            system_sampler.pause();
+        }
+
+        let record_path = format!(
+            "linux-explorer-{}.lxrec",
+            time::format_local_date_time(SystemTime::now()).replace([' ', ':'], "-")
+        );
+
+        let mut app = Self {
+            source,
+            recorder,
+            record_path,
+            record_compressed: true,
+            record_error: None,
+            timeline: replay.map(|replay| Timeline {
+                replay,
+                playing: false,
+                speed: 1.0,
+                last_step: Instant::now(),
+            }),
+            sampler,
+            scan_options,
+            scan: Scan::default(),
+            scan_error: None,
+            last_sample: None,
+            profiling: std::env::var("PROFILING").is_ok(),
+            search_text: "".to_string(),
+            query: Query::default(),
+            query_error: None,
+            columns: load_columns(cc.storage),
+            tree_mode: false,
+            collapsed: HashSet::new(),
+            expanded_threads: HashSet::new(),
+            threads_as_rows: false,
+            mine_only: false,
+            group_mode: false,
+            group_sort: GroupSort::default(),
+            selected: None,
+            details: None,
+            details_view: DetailsView::default(),
+            view: View::Processes,
+            connections: None,
+            connections_settings: ConnectionsSettings::default(),
+            cgroup_root: find_root(),
+            cgroup_tree: None,
+            selected_cgroup: None,
+            cgroup_stats: None,
+            namespaces: None,
+            namespace_owners: Owners::default(),
+            namespace_kind: None,
+            system_sampler,
+            system: None,
+            history: History::default(),
+            marked: HashSet::new(),
+            signal_request: None,
+            signal_report: None,
+            show_signal_errors: false,
+        };
+
+        if app.timeline.is_some() {
+            app.seek(0);
+        }
+        app
+    }
+
+    fn start_recording(&mut self) {
+        let path = PathBuf::from(&self.record_path);
+        self.record_error = self.recorder.start(&path, self.record_compressed).err();
+    }
+
+    fn stop_recording(&mut self) {
+        self.record_error = self.recorder.stop().err();
+    }
+
+    /// Where the charts end, the frame shown while replaying.
+    fn chart_end(&self) -> SystemTime {
+        match (&self.timeline, self.last_sample) {
+            (Some(_), Some(taken_at)) => taken_at,
+            _ => SystemTime::now(),
+        }
+    }
+
+    /// Loads a frame of the recording as if it was a new sample.
+    fn seek(&mut self, index: usize) {
+        let Some(timeline) = &mut self.timeline else {
+            return;
+        };
+        let frame = timeline.replay.load(index);
+        timeline.last_step = Instant::now();
+
+        if !frame.sequential {
+            self.history.clear();
+        }
+        match frame.scan {
+            Ok(scan) => {
+                self.history
+                    .record_processes(&scan.processes, frame.taken_at);
+                self.scan = scan;
+                self.scan_error = None;
+            }
+            Err(err) => self.scan_error = Some(err),
+        }
+        if let Ok(system) = &frame.system {
+            self.history.record_system(system, frame.taken_at);
+        }
+        self.system = Some(frame.system);
+
+        self.source = frame.source;
+        self.last_sample = Some(frame.taken_at);
+        self.details = None;
+        self.connections = None;
+        self.namespaces = None;
+    }
+
+    /// Steps through the recording at the pace it was recorded at.
+    fn play(&mut self, ctx: &egui::Context) {
+        let Some(timeline) = &mut self.timeline else {
+            return;
+        };
+        let Some(position) = timeline.replay.position() else {
+            return;
+        };
+        if !timeline.playing {
+            return;
+        }
+        if position + 1 >= timeline.replay.len() {
+            timeline.playing = false;
+            return;
+        }
+
+        let gap = timeline
+            .replay
+            .taken_at(position + 1)
+            .duration_since(timeline.replay.taken_at(position))
+            .unwrap_or_default()
+            .div_f64(timeline.speed);
+        let elapsed = timeline.last_step.elapsed();
+        if elapsed >= gap {
+            self.seek(position + 1);
+            ctx.request_repaint();
+        } else {
+            ctx.request_repaint_after(gap - elapsed);
+        }
+    }
+
+    fn show_timeline(&mut self, ui: &mut egui::Ui) {
+        let Some(timeline) = &mut self.timeline else {
+            return;
+        };
+        let last = timeline.replay.len() - 1;
+        let position = timeline.replay.position().unwrap_or(0);
+        let mut target = position;
+
+        ui.horizontal(|ui| {
+            if ui.button("⏮").clicked() {
+                target = 0;
+            }
+            if ui
+                .add_enabled(position > 0, egui::Button::new("◀"))
+                .clicked()
+            {
+                target = position - 1;
+            }
+            let play = if timeline.playing { "⏸" } else { "▶" };
+            if ui.button(play).clicked() {
+                timeline.playing = !timeline.playing;
+                timeline.last_step = Instant::now();
+            }
+            if ui
+                .add_enabled(position < last, egui::Button::new("▶|"))
+                .clicked()
+            {
+                target = position + 1;
+            }
+            if ui.button("⏭").clicked() {
+                target = last;
+            }
+
+            egui::ComboBox::from_id_source("replay_speed")
+                .selected_text(format!("{}x", timeline.speed))
+                .width(60.0)
+                .show_ui(ui, |ui| {
+                    for speed in [0.5, 1.0, 2.0, 4.0, 8.0, 16.0] {
+                        ui.selectable_value(&mut timeline.speed, speed, format!("{}x", speed));
+                    }
+                });
+
+            ui.label(
+                RichText::new(format!(
+                    "{} / {}  {}",
+                    position + 1,
+                    last + 1,
+                    time::format_local_date_time(timeline.replay.taken_at(position))
+                ))
+                .color(Color32::WHITE),
+            );
+
+            ui.spacing_mut().slider_width = ui.available_width();
+            ui.add(egui::Slider::new(&mut target, 0..=last).show_value(false));
+        });
+
+        if target != position {
+            self.seek(target);
+        }
+    }
+
+    /// Sampler and recording controls while live. Replays have the timeline instead.
+    fn show_live_controls(&mut self, ui: &mut egui::Ui, system: bool) {
+        if self.timeline.is_some() {
+            return;
+        }
+        if system {
+            show_sampler_controls(ui, &mut self.system_sampler);
+            return;
+        }
+
+        show_sampler_controls(ui, &mut self.sampler);
+        ui.separator();
+        if self.recorder.recording().is_some() {
+            if ui.button("Stop recording").clicked() {
+                self.stop_recording();
+            }
+            return;
+        }
+        ui.menu_button("Record", |ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("File").color(Color32::WHITE));
+                TextEdit::singleline(&mut self.record_path)
+                    .desired_width(300.0)
+                    .ui(ui);
+            });
+            ui.checkbox(&mut self.record_compressed, "Compress");
+            if ui.button("Start").clicked() {
+                self.start_recording();
+                ui.close_menu();
+            }
+        });
+    }
+
+    /// `smaps_rollup` only for what shows or filters by PSS, USS or swap, sampling right away
+    /// once it is needed.
+    fn update_scan_options(&mut self) {
+        let options = ScanOptions {
+            rollup: self.columns.needs_rollup() || self.query.needs_rollup() || self.group_mode,
+            rollup_pid: self.selected,
+        };
+        let mut current = self.scan_options.lock().unwrap();
+        if *current != options {
+            let more = options.reads_more_than(&current);
+            *current = options;
+            if more && !self.sampler.is_paused() {
+                self.sampler.sample_now();
+            }
+        }
+    }
+
+    fn receive_samples(&mut self) {
+        if self.timeline.is_some() {
+            return;
+        }
+
+        if let Some(sample) = self.sampler.latest() {
+            match sample.value {
+                Ok(scan) => {
+                    self.history
+                        .record_processes(&scan.processes, sample.taken_at);
+                    self.scan = scan;
+                    self.scan_error = None;
+                }
+                Err(err) => self.scan_error = Some(err),
+            }
+            self.last_sample = Some(sample.taken_at);
+
+            let scan = &self.scan;
+            self.marked.retain(|&pid| scan.find(pid).is_some());
+
+            // Keep the details as fresh as the process list.
+            self.details = None;
+            self.connections = None;
+            CgroupTree::expire(&mut self.cgroup_tree);
+            self.cgroup_stats = None;
+            self.namespaces = None;
+        }
+
+        if let Some(sample) = self.system_sampler.latest() {
+            if let Ok(system) = &sample.value {
+                self.history.record_system(system, sample.taken_at);
+            }
+            self.system = Some(sample.value);
+        }
+    }
+
+    fn show_view_controls(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(
+            &mut self.mine_only,
+            RichText::new("Mine").color(Color32::WHITE),
+        )
+        .on_hover_text(format!(
+            "Only processes started by or running as {}",
+            user_name(current_uid())
+        ));
+
+        ui.checkbox(
+            &mut self.threads_as_rows,
+            RichText::new("Threads").color(Color32::WHITE),
+        )
+        .on_hover_text("Show every thread as its own row");
+
+        ui.add_enabled(
+            !self.threads_as_rows,
+            egui::Checkbox::new(
+                &mut self.tree_mode,
+                RichText::new("Tree").color(Color32::WHITE),
+            ),
+        );
+
+        ui.checkbox(
+            &mut self.group_mode,
+            RichText::new("Group").color(Color32::WHITE),
+        )
+        .on_hover_text("Group processes by container, pod or systemd unit");
+
+        if self.tree_mode && !self.threads_as_rows && !self.group_mode {
+            if ui.button("Expand all").clicked() {
+                self.collapsed.clear();
+            }
+            if ui.button("Collapse all").clicked() {
+                self.collapsed = self.scan.processes.iter().map(|p| p.pid).collect();
+            }
+        }
+
+        ui.menu_button("Columns", |ui| self.columns.show_menu(ui));
+        ui.menu_button("History", |ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Keep exited for").color(Color32::WHITE));
+                let mut retention = self.history.retention.as_secs_f64();
+                let changed = DragValue::new(&mut retention)
+                    .clamp_range(0.0..=3600.0)
+                    .suffix("s")
+                    .ui(ui)
+                    .changed();
+                if changed {
+                    self.history.retention = Duration::from_secs_f64(retention);
+                }
+            });
+        });
+    }
+
+    fn show_search(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Search").color(Color32::WHITE))
+                .on_hover_text(query::HELP);
+            let text_color = if self.query_error.is_some() {
+                Color32::RED
+            } else {
+                Color32::BLACK
+            };
+            let search = TextEdit::singleline(&mut self.search_text)
+                .text_color(text_color)
+                .desired_width(400.0)
+                .ui(ui);
+            if search.changed() {
+                self.parse_search();
+            }
+
+            ui.separator();
+            self.show_view_controls(ui);
+            ui.separator();
+            self.show_live_controls(ui, false);
+        });
+
+        if let Some(err) = &self.query_error {
+            ui.label(RichText::new(format!("Syntax error at {}", err)).color(Color32::RED));
+        }
+    }
+
+    fn parse_search(&mut self) {
+        match Query::parse(&self.search_text) {
+            Ok(query) => {
+                self.query = query;
+                self.query_error = None;
+            }
+            Err(err) => self.query_error = Some(err),
+        }
+    }
+
+    fn show_connections(&mut self, ui: &mut egui::Ui) {
+        if self.timeline.is_some() {
+            ui.label(RichText::new("Recordings don't include sockets").color(Color32::YELLOW));
+            return;
+        }
+        let connections = self
+            .connections
+            .get_or_insert_with(|| Connections::load(self.source.as_ref(), &self.scan.processes));
+
+        match connections {
+            Ok(connections) => show_connections(
+                ui,
+                connections,
+                &self.scan.processes,
+                &mut self.connections_settings,
+                &mut self.selected,
+            ),
+            Err(err) => {
+                ui.label(RichText::new(err.to_string()).color(Color32::RED));
+            }
+        }
+    }
+
+    /// The hierarchy with the cgroup picked in it, clicking a cgroup filters the processes.
+    fn show_cgroups(&mut self, ui: &mut egui::Ui) {
+        if self.timeline.is_some() {
+            ui.label(RichText::new("Recordings don't include cgroups").color(Color32::YELLOW));
+            return;
+        }
+        let Some(root) = &self.cgroup_root else {
+            ui.label(RichText::new("cgroup2 is not mounted").color(Color32::YELLOW));
+            return;
+        };
+        let source = DirSource::new(root);
+
+        if ui
+            .button("Reload")
+            .on_hover_text("The tree is only read again every 30 seconds by itself")
+            .clicked()
+        {
+            self.cgroup_tree = None;
+        }
+        let tree = self
+            .cgroup_tree
+            .get_or_insert_with(|| CgroupTree::load(&source));
+        let clicked = match tree {
+            Ok(tree) => {
+                egui::ScrollArea::vertical()
+                    .id_source("cgroup_tree")
+                    .max_height(ui.available_height() / 2.0)
+                    .auto_shrink(false)
+                    .show(ui, |ui| {
+                        show_cgroup_tree(ui, &tree.root, self.selected_cgroup.as_deref())
+                    })
+                    .inner
+            }
+            Err(err) => {
+                ui.label(RichText::new(err.to_string()).color(Color32::RED));
+                None
+            }
+        };
+        if let Some(path) = clicked {
+            self.search_text = query::within("cgroup2", &path);
+            self.parse_search();
+            self.selected_cgroup = Some(path);
+            self.cgroup_stats = None;
+        }
+
+        if let Some(path) = &self.selected_cgroup {
+            ui.separator();
+            let stats = self
+                .cgroup_stats
+                .get_or_insert_with(|| CgroupStats::load(&source, path));
+            egui::ScrollArea::vertical()
+                .id_source("cgroup_stats")
+                .auto_shrink(false)
+                .show(ui, |ui| stats.show(ui));
+        }
+    }
+
+    fn show_namespaces(&mut self, ui: &mut egui::Ui) {
+        let namespaces = self.namespaces.get_or_insert_with(|| {
+            list_namespaces(
+                &self.scan.processes,
+                self.source.as_ref(),
+                &mut self.namespace_owners,
+            )
+        });
+        if let Some(namespace) = show_namespaces(ui, namespaces, &mut self.namespace_kind) {
+            self.search_text = namespace.search();
+            self.parse_search();
+            self.view = View::Processes;
+        }
+    }
+
+    fn show_system(&mut self, ui: &mut egui::Ui) {
+        match &self.system {
+            Some(Ok(system)) => {
+                egui::ScrollArea::vertical()
+                    .auto_shrink(false)
+                    .show(ui, |ui| {
+                        show_system(ui, system, &self.history.system, self.chart_end())
+                    });
+            }
+            Some(Err(err)) => {
+                ui.label(RichText::new(err.to_string()).color(Color32::RED));
+            }
+            None => {
+                ui.label(RichText::new("No sample yet").color(Color32::WHITE));
+            }
+        }
+    }
+
+    fn show_processes(&mut self, ui: &mut egui::Ui) {
+        let mine;
+        let query = if self.mine_only {
+            mine = self.query.clone().owned_by(current_uid());
+            &mine
+        } else {
+            &self.query
+        };
+
+        if self.group_mode {
+            let groups = group_by_workload(&self.scan.processes, query, self.group_sort);
+            if let Some(workload) = show_workload_groups(ui, &groups, &mut self.group_sort) {
+                self.search_text = workload.search();
+                self.parse_search();
+                self.group_mode = false;
+            }
+            return;
+        }
+
+        let ProcessRows {
+            processes,
+            tree,
+            rows,
+        } = ProcessRows::build(
+            &self.scan.processes,
+            query,
+            &self.columns,
+            self.tree_mode,
+            self.threads_as_rows,
+            &self.collapsed,
+            &self.expanded_threads,
+        );
+
+        ProcessTable {
+            processes: &processes,
+            rows: &rows,
+            tree: tree.as_ref(),
+            settings: &mut self.columns,
+            collapsed: &mut self.collapsed,
+            expanded_threads: &mut self.expanded_threads,
+            selected: &mut self.selected,
+            marked: &mut self.marked,
+            // The processes of a recording are long gone, or worse, the pids were reused.
+            signal: self.timeline.is_none().then_some(&mut self.signal_request),
+            history: &self.history,
+        }
+        .show(ui);
+    }
+
+    fn show_details(&mut self, ui: &mut egui::Ui) {
+        let Some(pid) = self.selected else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.selected = None;
+            }
+            ui.heading(RichText::new(pid.to_string()).color(Color32::WHITE));
+        });
+        ui.separator();
+
+        let Some(process) = self.scan.find(pid) else {
+            ui.label(RichText::new("Process exited").color(Color32::YELLOW));
+            return;
+        };
+
+        let mut details = match self.details.take() {
+            Some(details) if details.pid == process.pid => details,
+            _ => ProcessDetails::new(process.pid),
+        };
+        details.show(
+            ui,
+            self.source.as_ref(),
+            process,
+            self.history.process(process.pid),
+            &mut self.details_view,
+            self.timeline.as_ref().and(self.last_sample),
+        );
+        self.details = Some(details);
+
+        if self.details_view.scheduling.changed {
+            self.details_view.scheduling.changed = false;
+            // Show the effect right away.
+            self.sampler.sample_now();
+        }
+    }
+
+    /// Confirmation for a signal picked from the context menu, and the errors if sending failed.
+    fn show_signal_dialog(&mut self, ctx: &egui::Context) {
+        if let Some(request) = &self.signal_request {
+            let mut send = false;
+            let mut cancel = false;
+            egui::Window::new("Send signal")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    let targets = match request.pids.len() {
+                        1 => "this process".to_string(),
+                        n => format!("these {} processes", n),
+                    };
+                    ui.label(
+                        RichText::new(format!("Send {} to {}?", request.signal, targets))
+                            .color(Color32::WHITE),
+                    );
+                    let description = request.signal.description();
+                    if !description.is_empty() {
+                        ui.label(RichText::new(description).color(Color32::LIGHT_GRAY));
+                    }
+                    ui.separator();
+
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("signal_targets").show(ui, |ui| {
+                                for &pid in &request.pids {
+                                    ui.label(RichText::new(pid.to_string()).color(Color32::WHITE));
+                                    ui.label(
+                                        RichText::new(describe_target(&self.scan, pid))
+                                            .color(Color32::LIGHT_GRAY),
+                                    );
+                                    if let Some(warning) = target_warning(pid) {
+                                        ui.label(RichText::new(warning).color(Color32::YELLOW));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        send = ui.button("Send").clicked();
+                        cancel = ui.button("Cancel").clicked();
+                    });
+                });
+
+            if send {
+                let report = request.send();
+                self.show_signal_errors = !report.errors.is_empty();
+                self.signal_report = Some(report);
+                self.signal_request = None;
+                self.marked.clear();
+                // Show the effect right away.
+                self.sampler.sample_now();
+            } else if cancel {
+                self.signal_request = None;
+            }
+        }
+
+        if let (true, Some(report)) = (self.show_signal_errors, &self.signal_report) {
+            egui::Window::new("Sending failed")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(RichText::new(report.to_string()).color(Color32::WHITE));
+                    ui.separator();
+                    for err in &report.errors {
+                        ui.label(RichText::new(err.to_string()).color(Color32::RED));
+                    }
+                    ui.separator();
+                    if ui.button("OK").clicked() {
+                        self.show_signal_errors = false;
+                    }
+                });
+        }
+    }
+
+    fn show_status_bar(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let last_sample = match self.last_sample {
+                Some(time) => format!("Last sample {}", time::format_local(time)),
+                None => "No sample yet".to_string(),
+            };
+            ui.label(RichText::new(last_sample).color(Color32::WHITE));
+            if let Some(timeline) = &self.timeline {
+                ui.label(
+                    RichText::new(format!("(replaying {})", timeline.replay.path.display()))
+                        .color(Color32::YELLOW),
+                );
+            } else if self.sampler.is_paused() {
+                ui.label(RichText::new("(paused)").color(Color32::YELLOW));
+            }
+            ui.separator();
+
+            if let Some(report) = &self.signal_report {
+                let color = if report.errors.is_empty() {
+                    Color32::WHITE
+                } else {
+                    Color32::RED
+                };
+                let label = ui.label(RichText::new(report.to_string()).color(color));
+                if !report.errors.is_empty() {
+                    label.on_hover_ui(|ui| {
+                        for err in &report.errors {
+                            ui.label(err.to_string());
+                        }
+                    });
+                }
+                ui.separator();
+            }
+            if !self.marked.is_empty() {
+                ui.label(
+                    RichText::new(format!("{} marked", self.marked.len())).color(Color32::WHITE),
+                );
+                ui.separator();
+            }
+
+            if let Some((path, frames)) = self.recorder.recording() {
+                ui.label(
+                    RichText::new(format!(
+                        "● Recording to {} ({} frames)",
+                        path.display(),
+                        frames
+                    ))
+                    .color(Color32::RED),
+                );
+                ui.separator();
+            }
+            let record_error = self
+                .record_error
+                .as_ref()
+                .map(ToString::to_string)
+                .or_else(|| self.recorder.error());
+            if let Some(err) = record_error {
+                ui.label(RichText::new(format!("Recording failed: {}", err)).color(Color32::RED));
+                ui.separator();
+            }
+
+            if let Some(err) = &self.scan_error {
+                ui.label(RichText::new(err.to_string()).color(Color32::RED));
+                ui.separator();
+            }
+
+            ui.label(
+                RichText::new(format!("{} processes", self.scan.processes.len()))
+                    .color(Color32::WHITE),
+            );
+            ui.separator();
+
+            let partial = self.scan.partial();
+            ui.label(
+                RichText::new(format!("{} partial", partial)).color(if partial == 0 {
+                    Color32::LIGHT_GRAY
+                } else {
+                    Color32::YELLOW
+                }),
+            );
+            ui.separator();
+
+            let skipped = ui.label(
+                RichText::new(format!(
+                    "{} skipped ({} permission denied)",
+                    self.scan.skipped.len(),
+                    self.scan.permission_denied()
+                ))
+                .color(if self.scan.skipped.is_empty() {
+                    Color32::LIGHT_GRAY
+                } else {
+                    Color32::YELLOW
+                }),
+            );
+
+            if !self.scan.skipped.is_empty() {
+                skipped.on_hover_ui(|ui| {
+                    for skipped in &self.scan.skipped {
+                        ui.label(format!("{}: {}", skipped.pid, skipped.error));
+                    }
+                });
+            }
+        });
+    }
+}
+
+impl eframe::App for App {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, COLUMNS_KEY, &self.columns);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        puffin::profile_function!();
+        puffin::GlobalProfiler::lock().new_frame();
+
+        self.update_scan_options();
+        self.receive_samples();
+        self.play(ctx);
+
+        if self.profiling {
+            puffin_egui::profiler_window(ctx);
+        }
+
+        let mut style = (*ctx.style()).clone();
+
+        style.visuals.panel_fill = Color32::BLACK;
+        style.visuals.extreme_bg_color = Color32::WHITE;
+        style.visuals.text_cursor.color = Color32::BLACK;
+
+        style.text_styles = [
+            (TextStyle::Heading, FontId::new(25.0, FontFamily::Monospace)),
+            (TextStyle::Body, FontId::new(14.0, FontFamily::Monospace)),
+            (TextStyle::Button, FontId::new(14.0, FontFamily::Monospace)),
+            (
+                TextStyle::Monospace,
+                FontId::new(14.0, FontFamily::Monospace),
+            ),
+        ]
+        .into();
+
+        ctx.set_style(style);
+
+        self.show_signal_dialog(ctx);
+
+        if self.selected.is_some() {
+            SidePanel::right("details")
+                .resizable(true)
+                .default_width(500.0)
+                .show(ctx, |ui| self.show_details(ui));
+        }
+
+        TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            self.show_status_bar(ui);
+        });
+
+        if self.timeline.is_some() {
+            TopBottomPanel::bottom("timeline").show(ctx, |ui| self.show_timeline(ui));
+        }
+
+        CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for (view, name) in [
+                    (View::Processes, "Processes"),
+                    (View::Connections, "Connections"),
+                    (View::Cgroups, "Cgroups"),
+                    (View::Namespaces, "Namespaces"),
+                    (View::System, "System"),
+                ] {
+                    ui.selectable_value(
+                        &mut self.view,
+                        view,
+                        RichText::new(name).heading().color(Color32::WHITE),
+                    );
+                }
+            });
+
+            match self.view {
+                View::Processes => {
+                    self.show_search(ui);
+                    ui.separator();
+                    self.show_processes(ui);
+                }
+                View::Connections => {
+                    ui.horizontal(|ui| self.show_live_controls(ui, false));
+                    ui.separator();
+                    self.show_connections(ui);
+                }
+                View::Cgroups => {
+                    self.show_search(ui);
+                    ui.separator();
+                    SidePanel::left("cgroups")
+                        .resizable(true)
+                        .default_width(450.0)
+                        .show_inside(ui, |ui| self.show_cgroups(ui));
+                    self.show_processes(ui);
+                }
+                View::Namespaces => {
+                    ui.horizontal(|ui| self.show_live_controls(ui, false));
+                    ui.separator();
+                    self.show_namespaces(ui);
+                }
+                View::System => {
+                    ui.horizontal(|ui| self.show_live_controls(ui, true));
+                    ui.separator();
+                    self.show_system(ui);
+                }
+            }
+        });
+    }
+}
+
+fn load_columns(storage: Option<&dyn eframe::Storage>) -> ColumnSettings {
+    let mut columns: ColumnSettings = storage
+        .and_then(|storage| eframe::get_value(storage, COLUMNS_KEY))
+        .unwrap_or_default();
+    columns.normalize();
+    columns
+}
+
+/// Pause, refresh and interval controls for a sampler.
+fn show_sampler_controls<T: Send + 'static>(ui: &mut egui::Ui, sampler: &mut Sampler<T>) {
+    if sampler.is_paused() {
+        if ui.button("Resume").clicked() {
+            sampler.resume();
+        }
+    } else if ui.button("Pause").clicked() {
+        sampler.pause();
+    }
+
+    if ui.button("Refresh").clicked() {
+        sampler.sample_now();
+    }
+
+    ui.label(RichText::new("Interval").color(Color32::WHITE));
+    let mut interval = sampler.interval().as_secs_f64();
+    let changed = DragValue::new(&mut interval)
+        .clamp_range(0.1..=60.0)
+        .speed(0.1)
+        .suffix("s")
+        .ui(ui)
+        .changed();
+    if changed {
+        sampler.set_interval(Duration::from_secs_f64(interval));
+    }
+}
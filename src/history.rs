@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, SystemTime},
+};
+
+#[cfg(feature = "gui")]
+use egui::{pos2, Color32, Sense, Stroke, Ui, Vec2};
+#[cfg(feature = "gui")]
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+
+use crate::{process::Process, system::SystemInfo, units::format_bytes};
+
+/// Samples kept per series, ten minutes of processes at the default interval.
+pub const DEFAULT_CAPACITY: usize = 300;
+/// How long the history of an exited process is kept around.
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(60);
+
+/// A queue that drops its oldest value once it is full.
+#[derive(Clone)]
+pub struct RingBuffer<T> {
+    values: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.values.back()
+    }
+
+    /// Oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.values.iter()
+    }
+}
+
+/// Values over time.
+pub type Series = RingBuffer<(SystemTime, f64)>;
+
+impl Series {
+    pub fn max(&self) -> f64 {
+        self.iter().map(|(_, value)| *value).fold(0.0, f64::max)
+    }
+
+    /// Points for a plot, with x in seconds relative to `now`.
+    #[cfg(feature = "gui")]
+    fn points(&self, now: SystemTime) -> PlotPoints {
+        self.iter()
+            .map(|(time, value)| {
+                let ago = now
+                    .duration_since(*time)
+                    .unwrap_or(Duration::ZERO)
+                    .as_secs_f64();
+                [-ago, *value]
+            })
+            .collect()
+    }
+}
+
+pub struct ProcessHistory {
+    /// Tells a reused pid apart from the process that had it before.
+    pub starttime: u64,
+    pub cpu: Series,
+    /// Bytes.
+    pub rss: Series,
+    pub last_seen: SystemTime,
+}
+
+pub struct SystemHistory {
+    pub cpu: Series,
+    /// Bytes, as [`MemInfo::used`](crate::system::MemInfo::used) counts them.
+    pub memory: Series,
+    pub swap: Series,
+    /// One minute load average.
+    pub load: Series,
+}
+
+/// Recent samples of every process and of the whole system.
+///
+/// Filled from complete scans, so the history of a process keeps growing while it is hidden by
+/// a search.
+pub struct History {
+    processes: HashMap<u64, ProcessHistory>,
+    pub system: SystemHistory,
+    capacity: usize,
+    /// How long the history of an exited process is kept.
+    pub retention: Duration,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY, DEFAULT_RETENTION)
+    }
+}
+
+impl History {
+    pub fn new(capacity: usize, retention: Duration) -> Self {
+        Self {
+            processes: HashMap::new(),
+            system: SystemHistory {
+                cpu: Series::new(capacity),
+                memory: Series::new(capacity),
+                swap: Series::new(capacity),
+                load: Series::new(capacity),
+            },
+            capacity,
+            retention,
+        }
+    }
+
+    /// Forgets everything, keeping the settings.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.capacity, self.retention);
+    }
+
+    pub fn process(&self, pid: u64) -> Option<&ProcessHistory> {
+        self.processes.get(&pid)
+    }
+
+    /// Also forgets processes that have been gone for longer than the retention window.
+    pub fn record_processes(&mut self, processes: &[Process], taken_at: SystemTime) {
+        puffin::profile_function!();
+
+        for process in processes {
+            let history = self
+                .processes
+                .entry(process.pid)
+                .or_insert_with(|| ProcessHistory {
+                    starttime: process.stats.starttime,
+                    cpu: Series::new(self.capacity),
+                    rss: Series::new(self.capacity),
+                    last_seen: taken_at,
+                });
+            if history.starttime != process.stats.starttime {
+                *history = ProcessHistory {
+                    starttime: process.stats.starttime,
+                    cpu: Series::new(self.capacity),
+                    rss: Series::new(self.capacity),
+                    last_seen: taken_at,
+                };
+            }
+
+            if let Some(cpu_usage) = process.cpu_usage {
+                history.cpu.push((taken_at, cpu_usage));
+            }
+            history
+                .rss
+                .push((taken_at, process.stats.rss_bytes() as f64));
+            history.last_seen = taken_at;
+        }
+
+        let retention = self.retention;
+        self.processes.retain(|_, history| {
+            taken_at
+                .duration_since(history.last_seen)
+                .map_or(true, |gone| gone <= retention)
+        });
+    }
+
+    pub fn record_system(&mut self, info: &SystemInfo, taken_at: SystemTime) {
+        let system = &mut self.system;
+        if let Some(rates) = &info.rates {
+            system.cpu.push((taken_at, rates.cpu_usage));
+        }
+        system.memory.push((taken_at, info.meminfo.used() as f64));
+        system
+            .swap
+            .push((taken_at, info.meminfo.swap_used() as f64));
+        system.load.push((taken_at, info.loadavg.one));
+    }
+}
+
+/// A tiny line chart without axes, scaled so that `min_max` still fits.
+#[cfg(feature = "gui")]
+pub fn show_sparkline(ui: &mut Ui, series: &Series, min_max: f64, color: Color32) {
+    let (rect, _) = ui.allocate_exact_size(
+        Vec2::new(ui.available_width(), ui.available_height()),
+        Sense::hover(),
+    );
+    if series.len() < 2 {
+        return;
+    }
+
+    let max = series.max().max(min_max).max(f64::EPSILON);
+    let step = rect.width() / (series.len() - 1) as f32;
+    let points = series
+        .iter()
+        .enumerate()
+        .map(|(i, (_, value))| {
+            pos2(
+                rect.left() + i as f32 * step,
+                rect.bottom() - (*value / max) as f32 * rect.height(),
+            )
+        })
+        .collect();
+
+    ui.painter_at(rect)
+        .add(egui::Shape::line(points, Stroke::new(1.0, color)));
+}
+
+#[derive(Clone, Copy)]
+pub enum Unit {
+    Percent,
+    Bytes,
+    Plain,
+}
+
+impl Unit {
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            Unit::Percent => format!("{:.0}%", value),
+            Unit::Bytes => format_bytes(value.max(0.0) as u64),
+            Unit::Plain => format!("{:.2}", value),
+        }
+    }
+}
+
+/// A line chart of `lines` over the last samples, x is in seconds before `now`, which is when the
+/// frame was taken while replaying.
+#[cfg(feature = "gui")]
+pub fn show_chart(ui: &mut Ui, id: &str, unit: Unit, now: SystemTime, lines: &[(&str, &Series)]) {
+    puffin::profile_function!();
+
+    Plot::new(id)
+        .height(140.0)
+        .allow_scroll(false)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .include_y(0.0)
+        .legend(Legend::default())
+        .x_axis_formatter(|mark, _, _| format!("{:.0}s", mark.value))
+        .y_axis_formatter(move |mark, _, _| unit.format(mark.value))
+        .label_formatter(move |name, point| {
+            format!("{}\n{:.0}s: {}", name, point.x, unit.format(point.y))
+        })
+        .show(ui, |plot| {
+            for (name, series) in lines {
+                plot.line(Line::new(series.points(now)).name(*name));
+            }
+        });
+}
@@ -0,0 +1,30 @@
+pub mod cgroup;
+pub mod cgroupfs;
+pub mod cli;
+pub mod cpu;
+pub mod details;
+pub mod error;
+pub mod fd;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod history;
+pub mod maps;
+pub mod memory;
+pub mod namespace;
+pub mod net;
+pub mod process;
+pub mod procfs;
+pub mod query;
+pub mod recording;
+pub mod sampler;
+pub mod sched;
+pub mod signal;
+pub mod system;
+pub mod table;
+pub mod thread;
+pub mod time;
+pub mod tree;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod units;
+pub mod users;
@@ -0,0 +1,714 @@
+#[cfg(feature = "gui")]
+use std::cmp::Ordering;
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+#[cfg(feature = "gui")]
+use egui::{Color32, Rect, RichText, Sense, Ui, Vec2};
+
+#[cfg(feature = "gui")]
+use crate::units::format_bytes;
+use crate::{
+    error::{Error, Result},
+    procfs::{self, ProcSource},
+};
+
+/// A mapping from `/proc/[pid]/smaps`, which is `/proc/[pid]/maps` with statistics below every
+/// line.
+#[derive(Clone)]
+pub struct Mapping {
+    pub start: u64,
+    pub end: u64,
+    pub perms: Perms,
+    pub offset: u64,
+    /// `major:minor` in hex.
+    pub dev: String,
+    pub inode: u64,
+    /// Empty for anonymous mappings, `[heap]`, `[stack]` etc. for special ones.
+    pub path: String,
+    pub stats: MappingStats,
+}
+
+/// The per-mapping values from `smaps`, in bytes.
+#[derive(Clone, Default)]
+pub struct MappingStats {
+    pub rss: u64,
+    pub pss: u64,
+    pub private_dirty: u64,
+    pub swap: u64,
+    /// Transparent huge pages, `AnonHugePages`.
+    pub thp: u64,
+    /// Two letter codes like `rd` or `ex`, see proc(5).
+    pub vm_flags: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Perms {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+    /// `s` instead of `p`, i.e. not copy on write.
+    pub shared: bool,
+}
+
+impl Perms {
+    fn parse(perms: &str) -> Option<Self> {
+        let &[r, w, x, s] = perms.as_bytes() else {
+            return None;
+        };
+
+        Some(Self {
+            read: r == b'r',
+            write: w == b'w',
+            execute: x == b'x',
+            shared: s == b's',
+        })
+    }
+}
+
+impl Display for Perms {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flag = |set, c| if set { c } else { '-' };
+        write!(
+            f,
+            "{}{}{}{}",
+            flag(self.read, 'r'),
+            flag(self.write, 'w'),
+            flag(self.execute, 'x'),
+            if self.shared { 's' } else { 'p' }
+        )
+    }
+}
+
+/// What a mapping is used for, mostly to pick a color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MappingKind {
+    Code,
+    File,
+    Anonymous,
+    Heap,
+    Stack,
+    /// `[vdso]`, `[vvar]`, `[vsyscall]` and friends.
+    Special,
+}
+
+impl MappingKind {
+    pub const ALL: [MappingKind; 6] = [
+        MappingKind::Code,
+        MappingKind::File,
+        MappingKind::Anonymous,
+        MappingKind::Heap,
+        MappingKind::Stack,
+        MappingKind::Special,
+    ];
+
+    #[cfg(feature = "gui")]
+    pub fn color(&self) -> Color32 {
+        match self {
+            MappingKind::Code => Color32::from_rgb(0x4e, 0x9a, 0xe0),
+            MappingKind::File => Color32::from_rgb(0x2e, 0x6a, 0xa0),
+            MappingKind::Anonymous => Color32::from_rgb(0xc0, 0x80, 0x30),
+            MappingKind::Heap => Color32::from_rgb(0xe0, 0x50, 0x40),
+            MappingKind::Stack => Color32::from_rgb(0x50, 0xb0, 0x50),
+            MappingKind::Special => Color32::GRAY,
+        }
+    }
+}
+
+impl Display for MappingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MappingKind::Code => write!(f, "Code"),
+            MappingKind::File => write!(f, "File"),
+            MappingKind::Anonymous => write!(f, "Anonymous"),
+            MappingKind::Heap => write!(f, "Heap"),
+            MappingKind::Stack => write!(f, "Stack"),
+            MappingKind::Special => write!(f, "Special"),
+        }
+    }
+}
+
+impl Mapping {
+    pub fn size(&self) -> u64 {
+        self.end - self.start
+    }
+
+    pub fn kind(&self) -> MappingKind {
+        if self.path.is_empty() {
+            MappingKind::Anonymous
+        } else if self.path == "[heap]" {
+            MappingKind::Heap
+        } else if self.path.starts_with("[stack") {
+            MappingKind::Stack
+        } else if self.path.starts_with('[') {
+            MappingKind::Special
+        } else if self.perms.execute {
+            MappingKind::Code
+        } else {
+            MappingKind::File
+        }
+    }
+
+    /// What the mapping is grouped under, the backing file for file mappings.
+    pub fn group(&self) -> &str {
+        if self.path.is_empty() {
+            "[anon]"
+        } else {
+            &self.path
+        }
+    }
+
+    /// Parses a `maps` line like `7f00-7f10 r-xp 00002000 fe:00 317783   /usr/bin/head`.
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(6, ' ');
+        let (start, end) = fields.next()?.split_once('-')?;
+        let perms = Perms::parse(fields.next()?)?;
+        let offset = u64::from_str_radix(fields.next()?, 16).ok()?;
+        let dev = fields.next()?.to_string();
+        let inode = fields.next()?.parse().ok()?;
+        let path = fields.next().unwrap_or("").trim_start().to_string();
+
+        Some(Self {
+            start: u64::from_str_radix(start, 16).ok()?,
+            end: u64::from_str_radix(end, 16).ok()?,
+            perms,
+            offset,
+            dev,
+            inode,
+            path,
+            stats: MappingStats::default(),
+        })
+    }
+}
+
+/// Kernel threads have no mappings and yield an empty list.
+pub fn parse_maps(source: &dyn ProcSource, pid: u64) -> Result<Vec<Mapping>> {
+    let path = Path::new(&pid.to_string()).join("smaps");
+    let contents = procfs::read_to_string(source, &path)?;
+
+    let mut mappings: Vec<Mapping> = Vec::new();
+    for line in contents.lines() {
+        // Headers contain a colon too, in the device, but not right after the first word.
+        let key_value = line
+            .split_once(':')
+            .filter(|(key, _)| !key.contains(char::is_whitespace));
+        let Some((key, value)) = key_value else {
+            let mapping = Mapping::parse(line)
+                .ok_or_else(|| Error::malformed(&path, format!("bad mapping: {}", line)))?;
+            mappings.push(mapping);
+            continue;
+        };
+
+        let Some(mapping) = mappings.last_mut() else {
+            return Err(Error::malformed(
+                &path,
+                "statistics before the first mapping",
+            ));
+        };
+
+        let kb = || {
+            let value = value.trim();
+            let kb = value.strip_suffix("kB").unwrap_or(value).trim();
+            kb.parse::<u64>().map(|kb| kb * 1024).unwrap_or(0)
+        };
+        let stats = &mut mapping.stats;
+        match key {
+            "Rss" => stats.rss = kb(),
+            "Pss" => stats.pss = kb(),
+            "Private_Dirty" => stats.private_dirty = kb(),
+            "Swap" => stats.swap = kb(),
+            "AnonHugePages" => stats.thp = kb(),
+            "VmFlags" => stats.vm_flags = value.split_whitespace().map(String::from).collect(),
+            _ => {}
+        }
+    }
+
+    Ok(mappings)
+}
+
+/// All mappings of one backing file, or of one special region.
+pub struct MappingGroup {
+    pub name: String,
+    pub kind: MappingKind,
+    /// Address of the lowest mapping.
+    pub start: u64,
+    pub mappings: usize,
+    pub size: u64,
+    pub rss: u64,
+    pub pss: u64,
+    pub private_dirty: u64,
+    pub swap: u64,
+}
+
+pub fn group_mappings(mappings: &[Mapping]) -> Vec<MappingGroup> {
+    let mut groups: Vec<MappingGroup> = Vec::new();
+    let mut index = HashMap::new();
+
+    for mapping in mappings {
+        let i = *index.entry(mapping.group()).or_insert_with(|| {
+            groups.push(MappingGroup {
+                name: mapping.group().to_string(),
+                kind: mapping.kind(),
+                start: mapping.start,
+                mappings: 0,
+                size: 0,
+                rss: 0,
+                pss: 0,
+                private_dirty: 0,
+                swap: 0,
+            });
+            groups.len() - 1
+        });
+
+        let group = &mut groups[i];
+        // A library's code decides the color of the whole library.
+        if mapping.kind() == MappingKind::Code {
+            group.kind = MappingKind::Code;
+        }
+        group.start = group.start.min(mapping.start);
+        group.mappings += 1;
+        group.size += mapping.size();
+        group.rss += mapping.stats.rss;
+        group.pss += mapping.stats.pss;
+        group.private_dirty += mapping.stats.private_dirty;
+        group.swap += mapping.stats.swap;
+    }
+
+    groups
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MapsColumn {
+    Address,
+    Size,
+    Rss,
+    Pss,
+    PrivateDirty,
+    Swap,
+    Path,
+}
+
+#[cfg(feature = "gui")]
+impl MapsColumn {
+    fn name(&self) -> &'static str {
+        match self {
+            MapsColumn::Address => "Address",
+            MapsColumn::Size => "Size",
+            MapsColumn::Rss => "RSS",
+            MapsColumn::Pss => "PSS",
+            MapsColumn::PrivateDirty => "Dirty",
+            MapsColumn::Swap => "Swap",
+            MapsColumn::Path => "Path",
+        }
+    }
+
+    fn compare(&self, a: &Mapping, b: &Mapping) -> Ordering {
+        match self {
+            MapsColumn::Address => a.start.cmp(&b.start),
+            MapsColumn::Size => a.size().cmp(&b.size()),
+            MapsColumn::Rss => a.stats.rss.cmp(&b.stats.rss),
+            MapsColumn::Pss => a.stats.pss.cmp(&b.stats.pss),
+            MapsColumn::PrivateDirty => a.stats.private_dirty.cmp(&b.stats.private_dirty),
+            MapsColumn::Swap => a.stats.swap.cmp(&b.stats.swap),
+            MapsColumn::Path => a.path.cmp(&b.path),
+        }
+    }
+
+    fn compare_groups(&self, a: &MappingGroup, b: &MappingGroup) -> Ordering {
+        match self {
+            MapsColumn::Address => a.start.cmp(&b.start),
+            MapsColumn::Size => a.size.cmp(&b.size),
+            MapsColumn::Rss => a.rss.cmp(&b.rss),
+            MapsColumn::Pss => a.pss.cmp(&b.pss),
+            MapsColumn::PrivateDirty => a.private_dirty.cmp(&b.private_dirty),
+            MapsColumn::Swap => a.swap.cmp(&b.swap),
+            MapsColumn::Path => a.name.cmp(&b.name),
+        }
+    }
+}
+
+/// How the memory map tab is laid out, kept while switching between processes.
+pub struct MapsSettings {
+    pub sort_column: MapsColumn,
+    pub sort_ascending: bool,
+    pub grouped: bool,
+    /// Size the address-space bar by RSS instead of by virtual size.
+    pub by_rss: bool,
+}
+
+impl Default for MapsSettings {
+    fn default() -> Self {
+        Self {
+            sort_column: MapsColumn::Address,
+            sort_ascending: true,
+            grouped: false,
+            by_rss: false,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl MapsSettings {
+    fn sort_by(&mut self, column: MapsColumn) {
+        if self.sort_column == column {
+            self.sort_ascending = !self.sort_ascending;
+        } else {
+            self.sort_column = column;
+            self.sort_ascending = matches!(column, MapsColumn::Address | MapsColumn::Path);
+        }
+    }
+
+    fn order(&self, ordering: Ordering) -> Ordering {
+        if self.sort_ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    }
+
+    fn header(&mut self, ui: &mut Ui, column: MapsColumn) {
+        let arrow = match (self.sort_column == column, self.sort_ascending) {
+            (false, _) => "",
+            (true, true) => " ⏶",
+            (true, false) => " ⏷",
+        };
+
+        let text = RichText::new(format!("{}{}", column.name(), arrow)).color(Color32::WHITE);
+        if ui
+            .add(egui::Label::new(text).sense(Sense::click()))
+            .clicked()
+        {
+            self.sort_by(column);
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn show_maps(ui: &mut Ui, mappings: &[Mapping], settings: &mut MapsSettings) {
+    puffin::profile_function!();
+
+    if mappings.is_empty() {
+        ui.label(RichText::new("No mappings, probably a kernel thread").color(Color32::YELLOW));
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut settings.grouped, "Group by file");
+        ui.checkbox(&mut settings.by_rss, "Bar by RSS");
+    });
+    show_address_space(ui, mappings, settings.by_rss);
+    ui.horizontal_wrapped(|ui| {
+        for kind in MappingKind::ALL {
+            ui.label(RichText::new("■").color(kind.color()));
+            ui.label(RichText::new(kind.to_string()).color(Color32::LIGHT_GRAY));
+        }
+    });
+    ui.separator();
+
+    if settings.grouped {
+        show_groups(ui, mappings, settings);
+    } else {
+        show_mappings(ui, mappings, settings);
+    }
+}
+
+/// The mappings in address order, each as wide as its share of the total. Gaps between mappings
+/// are left out, they would dwarf everything else.
+#[cfg(feature = "gui")]
+fn show_address_space(ui: &mut Ui, mappings: &[Mapping], by_rss: bool) {
+    let weight = |mapping: &Mapping| {
+        if by_rss {
+            mapping.stats.rss
+        } else {
+            mapping.size()
+        }
+    };
+    let total = mappings.iter().map(weight).sum::<u64>().max(1) as f32;
+
+    let (rect, response) =
+        ui.allocate_exact_size(Vec2::new(ui.available_width(), 24.0), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::from_gray(30));
+
+    let mut x = rect.left();
+    let mut hovered = None;
+    for mapping in mappings {
+        let width = weight(mapping) as f32 / total * rect.width();
+        if width <= 0.0 {
+            continue;
+        }
+
+        let segment = Rect::from_min_max(
+            egui::pos2(x, rect.top()),
+            egui::pos2(x + width, rect.bottom()),
+        );
+        painter.rect_filled(segment, 0.0, mapping.kind().color());
+        if response
+            .hover_pos()
+            .is_some_and(|pos| segment.contains(pos))
+        {
+            hovered = Some(mapping);
+        }
+        x += width;
+    }
+
+    if let Some(mapping) = hovered {
+        response.on_hover_text_at_pointer(format!(
+            "{:x}-{:x} {}\n{}\nSize {}  RSS {}",
+            mapping.start,
+            mapping.end,
+            mapping.perms,
+            mapping.group(),
+            format_bytes(mapping.size()),
+            format_bytes(mapping.stats.rss),
+        ));
+    }
+}
+
+#[cfg(feature = "gui")]
+fn show_mappings(ui: &mut Ui, mappings: &[Mapping], settings: &mut MapsSettings) {
+    let mut sorted: Vec<&Mapping> = mappings.iter().collect();
+    sorted.sort_by(|a, b| settings.order(settings.sort_column.compare(a, b)));
+
+    egui::Grid::new("maps").striped(true).show(ui, |ui| {
+        settings.header(ui, MapsColumn::Address);
+        ui.label(RichText::new("Perms").color(Color32::WHITE));
+        ui.label(RichText::new("Offset").color(Color32::WHITE));
+        ui.label(RichText::new("Dev").color(Color32::WHITE));
+        ui.label(RichText::new("Inode").color(Color32::WHITE));
+        settings.header(ui, MapsColumn::Size);
+        settings.header(ui, MapsColumn::Rss);
+        settings.header(ui, MapsColumn::Pss);
+        settings.header(ui, MapsColumn::PrivateDirty);
+        settings.header(ui, MapsColumn::Swap);
+        ui.label(RichText::new("THP").color(Color32::WHITE));
+        settings.header(ui, MapsColumn::Path);
+        ui.end_row();
+
+        for mapping in sorted {
+            let value = |ui: &mut Ui, text: String| {
+                ui.label(RichText::new(text).color(Color32::LIGHT_GRAY));
+            };
+
+            ui.label(
+                RichText::new(format!("{:012x}-{:012x}", mapping.start, mapping.end))
+                    .color(mapping.kind().color()),
+            );
+            ui.label(RichText::new(mapping.perms.to_string()).color(Color32::LIGHT_GRAY))
+                .on_hover_text(format!("VmFlags: {}", mapping.stats.vm_flags.join(" ")));
+            value(ui, format!("{:x}", mapping.offset));
+            value(ui, mapping.dev.clone());
+            value(ui, mapping.inode.to_string());
+            value(ui, format_bytes(mapping.size()));
+            value(ui, format_bytes(mapping.stats.rss));
+            value(ui, format_bytes(mapping.stats.pss));
+            value(ui, format_bytes(mapping.stats.private_dirty));
+            value(ui, format_bytes(mapping.stats.swap));
+            value(ui, format_bytes(mapping.stats.thp));
+            value(ui, mapping.path.clone());
+            ui.end_row();
+        }
+    });
+}
+
+#[cfg(feature = "gui")]
+fn show_groups(ui: &mut Ui, mappings: &[Mapping], settings: &mut MapsSettings) {
+    let mut groups = group_mappings(mappings);
+    groups.sort_by(|a, b| settings.order(settings.sort_column.compare_groups(a, b)));
+
+    egui::Grid::new("map_groups").striped(true).show(ui, |ui| {
+        settings.header(ui, MapsColumn::Path);
+        ui.label(RichText::new("Mappings").color(Color32::WHITE));
+        settings.header(ui, MapsColumn::Size);
+        settings.header(ui, MapsColumn::Rss);
+        settings.header(ui, MapsColumn::Pss);
+        settings.header(ui, MapsColumn::PrivateDirty);
+        settings.header(ui, MapsColumn::Swap);
+        ui.end_row();
+
+        for group in groups {
+            ui.label(RichText::new(&group.name).color(group.kind.color()));
+            for value in [
+                group.mappings.to_string(),
+                format_bytes(group.size),
+                format_bytes(group.rss),
+                format_bytes(group.pss),
+                format_bytes(group.private_dirty),
+                format_bytes(group.swap),
+            ] {
+                ui.label(RichText::new(value).color(Color32::LIGHT_GRAY));
+            }
+            ui.end_row();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs::MemorySource;
+
+    fn smaps(headers: &[&str]) -> MemorySource {
+        let contents: String = headers
+            .iter()
+            .map(|header| {
+                format!(
+                    "{}\nRss:                   8 kB\nPss:                   4 kB\n",
+                    header
+                )
+            })
+            .collect();
+        MemorySource::new().with("7/smaps", contents)
+    }
+
+    #[test]
+    fn lines() {
+        let source = smaps(&[
+            "55d0c0a00000-55d0c0a02000 r--p 00000000 fe:01 1311027                    /usr/bin/cat",
+            "55d0c0a02000-55d0c0a07000 r-xp 00002000 fe:01 1311027                    /usr/bin/cat",
+            "55d0c1e4f000-55d0c1e70000 rw-p 00000000 00:00 0                          [heap]",
+            "7f1c5e200000-7f1c5e400000 rw-p 00000000 00:00 0 ",
+            "7f1c5e600000-7f1c5e628000 r-xp 00028000 fe:01 1316234                    /usr/lib/libc.so.6 (deleted)",
+            "7f1c5e800000-7f1c5e801000 rw-s 00000000 00:01 2048                       /memfd:pulseaudio (deleted)",
+            "7f1c5ea00000-7f1c5ea01000 r--p 00000000 fe:01 1400000                    /opt/My App/data file.bin",
+            "7ffd4a1c5000-7ffd4a1e6000 rw-p 00000000 00:00 0                          [stack]",
+            "7ffd4a1f2000-7ffd4a1f4000 r-xp 00000000 00:00 0                          [vdso]",
+            "ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0                  [vsyscall]",
+        ]);
+        let mappings = parse_maps(&source, 7).unwrap();
+
+        let parsed: Vec<(&str, MappingKind, String)> = mappings
+            .iter()
+            .map(|m| (m.path.as_str(), m.kind(), m.perms.to_string()))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                ("/usr/bin/cat", MappingKind::File, "r--p".to_string()),
+                ("/usr/bin/cat", MappingKind::Code, "r-xp".to_string()),
+                ("[heap]", MappingKind::Heap, "rw-p".to_string()),
+                ("", MappingKind::Anonymous, "rw-p".to_string()),
+                (
+                    "/usr/lib/libc.so.6 (deleted)",
+                    MappingKind::Code,
+                    "r-xp".to_string()
+                ),
+                (
+                    "/memfd:pulseaudio (deleted)",
+                    MappingKind::File,
+                    "rw-s".to_string()
+                ),
+                (
+                    "/opt/My App/data file.bin",
+                    MappingKind::File,
+                    "r--p".to_string()
+                ),
+                ("[stack]", MappingKind::Stack, "rw-p".to_string()),
+                ("[vdso]", MappingKind::Special, "r-xp".to_string()),
+                ("[vsyscall]", MappingKind::Special, "--xp".to_string()),
+            ]
+        );
+
+        let code = &mappings[1];
+        assert_eq!((code.start, code.end), (0x55d0c0a02000, 0x55d0c0a07000));
+        assert_eq!(code.size(), 0x5000);
+        assert_eq!(code.offset, 0x2000);
+        assert_eq!(code.dev, "fe:01");
+        assert_eq!(code.inode, 1311027);
+        assert_eq!((code.stats.rss, code.stats.pss), (8192, 4096));
+        assert!(mappings[5].perms.shared);
+        assert_eq!(mappings[3].group(), "[anon]");
+        assert_eq!(mappings[9].start, 0xffffffffff600000);
+    }
+
+    #[test]
+    fn stats() {
+        let source = MemorySource::new().with(
+            "7/smaps",
+            "7f00-7f10 rw-p 00000000 00:00 0 \n\
+             Size:                 64 kB\n\
+             Rss:                  12 kB\n\
+             Pss:                   6 kB\n\
+             Private_Dirty:         4 kB\n\
+             Swap:                  2 kB\n\
+             AnonHugePages:         0 kB\n\
+             VmFlags: rd wr mr mw me ac\n",
+        );
+        let [mapping] = &parse_maps(&source, 7).unwrap()[..] else {
+            panic!("expected one mapping");
+        };
+        let stats = &mapping.stats;
+        assert_eq!(
+            (stats.rss, stats.pss, stats.private_dirty, stats.swap),
+            (12 << 10, 6 << 10, 4 << 10, 2 << 10)
+        );
+        assert_eq!(stats.vm_flags, ["rd", "wr", "mr", "mw", "me", "ac"]);
+
+        // Kernel threads have no mappings.
+        let empty = MemorySource::new().with("2/smaps", "");
+        assert!(parse_maps(&empty, 2).unwrap().is_empty());
+
+        for contents in [
+            "Rss: 4 kB\n",
+            "7f00-7f10 rw 00000000 00:00 0\n",
+            "garbage\n",
+        ] {
+            let source = MemorySource::new().with("7/smaps", contents);
+            let Err(err) = parse_maps(&source, 7) else {
+                panic!("{} parsed", contents);
+            };
+            assert_eq!(err.path, Path::new("7/smaps"), "{}", contents);
+        }
+    }
+
+    #[test]
+    fn groups() {
+        let source = smaps(&[
+            "1000-2000 r--p 00000000 fe:01 10                         /usr/lib/libc.so.6",
+            "2000-5000 r-xp 00001000 fe:01 10                         /usr/lib/libc.so.6",
+            "5000-6000 rw-p 00004000 fe:01 10                         /usr/lib/libc.so.6",
+            "6000-7000 rw-p 00000000 00:00 0 ",
+            "9000-a000 r-xp 00000000 fe:01 10                         /usr/lib/libc.so.6 (deleted)",
+            "a000-c000 rw-p 00000000 00:00 0 ",
+            "c000-d000 rw-p 00000000 00:00 0                          [heap]",
+        ]);
+        let groups = group_mappings(&parse_maps(&source, 7).unwrap());
+
+        let summary: Vec<(&str, MappingKind, u64, usize, u64, u64)> = groups
+            .iter()
+            .map(|g| (g.name.as_str(), g.kind, g.start, g.mappings, g.size, g.rss))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                // Colored as code since part of it is.
+                (
+                    "/usr/lib/libc.so.6",
+                    MappingKind::Code,
+                    0x1000,
+                    3,
+                    0x5000,
+                    3 * 8192
+                ),
+                (
+                    "[anon]",
+                    MappingKind::Anonymous,
+                    0x6000,
+                    2,
+                    0x3000,
+                    2 * 8192
+                ),
+                // The replaced library is still mapped, apart from its new version.
+                (
+                    "/usr/lib/libc.so.6 (deleted)",
+                    MappingKind::Code,
+                    0x9000,
+                    1,
+                    0x1000,
+                    8192
+                ),
+                ("[heap]", MappingKind::Heap, 0xc000, 1, 0x1000, 8192),
+            ]
+        );
+        assert_eq!(groups[0].pss, 3 * 4096);
+    }
+}
@@ -0,0 +1,211 @@
+use std::path::Path;
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Ui};
+
+#[cfg(feature = "gui")]
+use crate::units::format_bytes;
+use crate::{
+    error::{Error, ErrorKind, Result},
+    process::page_size,
+    procfs::{self, KeyValues, ProcSource},
+};
+
+/// Memory usage of a process from `status`, `statm` and `smaps_rollup`, all in bytes.
+#[derive(Clone, Default)]
+pub struct MemoryInfo {
+    pub vm_rss: Option<u64>,
+    /// Peak RSS.
+    pub vm_hwm: Option<u64>,
+    pub vm_swap: Option<u64>,
+    pub rss_anon: Option<u64>,
+    pub rss_file: Option<u64>,
+    pub rss_shmem: Option<u64>,
+    pub statm: Option<Statm>,
+    /// Only readable for processes we may ptrace, and empty for kernel threads.
+    pub rollup: Option<SmapsRollup>,
+}
+
+/// `/proc/[pid]/statm`, converted from pages to bytes.
+#[derive(Clone, Copy)]
+pub struct Statm {
+    pub size: u64,
+    pub resident: u64,
+    pub shared: u64,
+    pub text: u64,
+    pub data: u64,
+}
+
+/// `/proc/[pid]/smaps_rollup`, the sum over all mappings in `smaps`.
+#[derive(Clone, Copy, Default)]
+pub struct SmapsRollup {
+    pub rss: u64,
+    /// Proportional set size, shared pages are split evenly between the processes using them.
+    pub pss: u64,
+    pub pss_anon: u64,
+    pub pss_file: u64,
+    pub pss_shmem: u64,
+    pub shared_clean: u64,
+    pub shared_dirty: u64,
+    pub private_clean: u64,
+    pub private_dirty: u64,
+    pub swap: u64,
+    pub swap_pss: u64,
+}
+
+impl SmapsRollup {
+    /// Unique set size, the memory that would be freed if the process exited.
+    pub fn uss(&self) -> u64 {
+        self.private_clean + self.private_dirty
+    }
+}
+
+impl MemoryInfo {
+    pub fn pss(&self) -> Option<u64> {
+        self.rollup.map(|rollup| rollup.pss)
+    }
+
+    pub fn uss(&self) -> Option<u64> {
+        self.rollup.map(|rollup| rollup.uss())
+    }
+
+    pub fn swap(&self) -> Option<u64> {
+        self.vm_swap.or(self.rollup.map(|rollup| rollup.swap))
+    }
+
+    /// Every value from `status`, `statm` and `smaps_rollup` in bytes, `None` if unknown.
+    pub fn fields(&self) -> [(&'static str, Option<u64>); 22] {
+        [
+            ("VmRSS", self.vm_rss),
+            ("VmHWM", self.vm_hwm),
+            ("VmSwap", self.vm_swap),
+            ("RssAnon", self.rss_anon),
+            ("RssFile", self.rss_file),
+            ("RssShmem", self.rss_shmem),
+            ("statm size", self.statm.map(|s| s.size)),
+            ("statm resident", self.statm.map(|s| s.resident)),
+            ("statm shared", self.statm.map(|s| s.shared)),
+            ("statm text", self.statm.map(|s| s.text)),
+            ("statm data", self.statm.map(|s| s.data)),
+            ("Pss", self.pss()),
+            ("Pss_Anon", self.rollup.map(|r| r.pss_anon)),
+            ("Pss_File", self.rollup.map(|r| r.pss_file)),
+            ("Pss_Shmem", self.rollup.map(|r| r.pss_shmem)),
+            ("Uss", self.uss()),
+            ("Shared_Clean", self.rollup.map(|r| r.shared_clean)),
+            ("Shared_Dirty", self.rollup.map(|r| r.shared_dirty)),
+            ("Private_Clean", self.rollup.map(|r| r.private_clean)),
+            ("Private_Dirty", self.rollup.map(|r| r.private_dirty)),
+            ("Swap", self.rollup.map(|r| r.swap)),
+            ("SwapPss", self.rollup.map(|r| r.swap_pss)),
+        ]
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn show(&self, ui: &mut Ui) {
+        puffin::profile_function!();
+
+        let fields = self.fields();
+        egui::Grid::new("memory").striped(true).show(ui, |ui| {
+            for row in fields.chunks(3) {
+                for (name, value) in row {
+                    ui.label(RichText::new(*name).color(Color32::WHITE));
+                    let value = match value {
+                        Some(value) => format_bytes(*value),
+                        None => "-".to_string(),
+                    };
+                    ui.label(RichText::new(value).color(Color32::LIGHT_GRAY));
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
+
+/// Failures are added to `errors`, except for `smaps_rollup` being off limits, which is
+/// normal for kernel threads and processes of other users. `smaps_rollup` is only read with
+/// `rollup`.
+pub fn parse_memory(
+    source: &dyn ProcSource,
+    dir: &Path,
+    status: Option<&KeyValues>,
+    rollup: bool,
+    errors: &mut Vec<Error>,
+) -> MemoryInfo {
+    let mut memory = MemoryInfo::default();
+
+    if let Some(status) = status {
+        memory.vm_rss = status.kb("VmRSS");
+        memory.vm_hwm = status.kb("VmHWM");
+        memory.vm_swap = status.kb("VmSwap");
+        memory.rss_anon = status.kb("RssAnon");
+        memory.rss_file = status.kb("RssFile");
+        memory.rss_shmem = status.kb("RssShmem");
+    }
+
+    match parse_statm(source, &dir.join("statm")) {
+        Ok(statm) => memory.statm = Some(statm),
+        Err(err) => errors.push(err),
+    }
+
+    if !rollup {
+        return memory;
+    }
+    match parse_smaps_rollup(source, &dir.join("smaps_rollup")) {
+        Ok(rollup) => memory.rollup = rollup,
+        // Kernel threads report ESRCH. Recordings lack it where it wasn't needed.
+        Err(err)
+            if matches!(
+                err.kind,
+                ErrorKind::PermissionDenied | ErrorKind::NoSuchProcess | ErrorKind::NotFound
+            ) => {}
+        Err(err) => errors.push(err),
+    }
+
+    memory
+}
+
+fn parse_statm(source: &dyn ProcSource, path: &Path) -> Result<Statm> {
+    let contents = procfs::read_to_string(source, path)?;
+    let pages = contents
+        .split_ascii_whitespace()
+        .map(|field| field.parse::<u64>())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|err| Error::malformed(path, err.to_string()))?;
+
+    let [size, resident, shared, text, _lib, data, ..] = pages[..] else {
+        return Err(Error::malformed(path, "expected 7 fields"));
+    };
+
+    let page_size = page_size();
+    Ok(Statm {
+        size: size * page_size,
+        resident: resident * page_size,
+        shared: shared * page_size,
+        text: text * page_size,
+        data: data * page_size,
+    })
+}
+
+/// `None` for kernel threads, which have no mappings.
+fn parse_smaps_rollup(source: &dyn ProcSource, path: &Path) -> Result<Option<SmapsRollup>> {
+    let rollup = KeyValues::read(source, path)?;
+    let Some(rss) = rollup.kb("Rss") else {
+        return Ok(None);
+    };
+
+    let kb = |key| rollup.kb(key).unwrap_or(0);
+    Ok(Some(SmapsRollup {
+        rss,
+        pss: kb("Pss"),
+        pss_anon: kb("Pss_Anon"),
+        pss_file: kb("Pss_File"),
+        pss_shmem: kb("Pss_Shmem"),
+        shared_clean: kb("Shared_Clean"),
+        shared_dirty: kb("Shared_Dirty"),
+        private_clean: kb("Private_Clean"),
+        private_dirty: kb("Private_Dirty"),
+        swap: kb("Swap"),
+        swap_pss: kb("SwapPss"),
+    }))
+}
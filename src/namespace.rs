@@ -0,0 +1,466 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::File,
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd},
+        unix::fs::MetadataExt,
+    },
+    path::Path,
+};
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Sense, Ui};
+
+use crate::{
+    error::{ErrorKind, Result},
+    process::{command_summary, CommandCounts, Process},
+    procfs::{self, ProcSource},
+};
+
+/// See namespaces(7).
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum NamespaceKind {
+    Cgroup,
+    Ipc,
+    Mnt,
+    Net,
+    Pid,
+    Time,
+    User,
+    Uts,
+}
+
+impl NamespaceKind {
+    pub const ALL: [NamespaceKind; 8] = [
+        NamespaceKind::Cgroup,
+        NamespaceKind::Ipc,
+        NamespaceKind::Mnt,
+        NamespaceKind::Net,
+        NamespaceKind::Pid,
+        NamespaceKind::Time,
+        NamespaceKind::User,
+        NamespaceKind::Uts,
+    ];
+
+    /// The name of the link in `/proc/[pid]/ns`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NamespaceKind::Cgroup => "cgroup",
+            NamespaceKind::Ipc => "ipc",
+            NamespaceKind::Mnt => "mnt",
+            NamespaceKind::Net => "net",
+            NamespaceKind::Pid => "pid",
+            NamespaceKind::Time => "time",
+            NamespaceKind::User => "user",
+            NamespaceKind::Uts => "uts",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            NamespaceKind::Cgroup => "Cgroup root directory",
+            NamespaceKind::Ipc => "System V IPC, POSIX message queues",
+            NamespaceKind::Mnt => "Mount points",
+            NamespaceKind::Net => "Network devices, stacks, ports",
+            NamespaceKind::Pid => "Process ids",
+            NamespaceKind::Time => "Boot and monotonic clocks",
+            NamespaceKind::User => "User and group ids",
+            NamespaceKind::Uts => "Hostname and NIS domain name",
+        }
+    }
+}
+
+/// A namespace is identified by the inode of its `nsfs` file.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Namespace {
+    pub kind: NamespaceKind,
+    pub inode: u64,
+}
+
+impl Namespace {
+    /// Link targets like `net:[4026531840]`.
+    fn parse(kind: NamespaceKind, target: &str) -> Option<Self> {
+        let inode = target
+            .strip_prefix(kind.name())?
+            .strip_prefix(":[")?
+            .strip_suffix(']')?
+            .parse()
+            .ok()?;
+        Some(Self { kind, inode })
+    }
+}
+
+impl Display for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:[{}]", self.kind.name(), self.inode)
+    }
+}
+
+impl Namespace {
+    /// A search that finds the members, inodes are unique across kinds.
+    pub fn search(&self) -> String {
+        format!("ns:{}", self.inode)
+    }
+}
+
+/// The namespaces of a process, only the kinds the kernel supports.
+#[derive(Clone, Debug, Default)]
+pub struct Namespaces {
+    pub entries: Vec<Namespace>,
+}
+
+impl Namespaces {
+    pub fn get(&self, kind: NamespaceKind) -> Option<Namespace> {
+        self.entries.iter().find(|ns| ns.kind == kind).copied()
+    }
+
+    /// All of them like `net:[4026531840] pid:[4026531836]`, for searching.
+    pub fn text(&self) -> String {
+        self.entries
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Reading the links takes the same permission as ptrace, so other users' processes usually fail
+/// with permission denied.
+pub fn parse_namespaces(source: &dyn ProcSource, dir: &Path) -> Result<Namespaces> {
+    let mut entries = Vec::new();
+    for kind in NamespaceKind::ALL {
+        let path = dir.join("ns").join(kind.name());
+        match procfs::read_link(source, &path) {
+            Ok(target) => {
+                if let Some(ns) = Namespace::parse(kind, &target.to_string_lossy()) {
+                    entries.push(ns);
+                }
+            }
+            // Older kernels don't have the cgroup and time namespaces.
+            Err(err) if err.kind == ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Namespaces { entries })
+}
+
+/// `NSpid` from `/proc/[pid]/status`, the pid in every pid namespace the process is in.
+pub fn parse_ns_pids(value: &str) -> Vec<u64> {
+    value
+        .split_whitespace()
+        .filter_map(|pid| pid.parse().ok())
+        .collect()
+}
+
+/// ioctl_ns(2) requests.
+const NS_GET_USERNS: libc::c_ulong = 0xb701;
+const NS_GET_PARENT: libc::c_ulong = 0xb702;
+
+/// Inode of the user namespace that owns `ns`, or the parent of a user namespace, asked through a
+/// member. Only works on the host's procfs, and fails with EPERM for the initial user namespace.
+pub fn owner(source: &dyn ProcSource, pid: u64, ns: Namespace) -> io::Result<u64> {
+    let path = Path::new(&pid.to_string()).join("ns").join(ns.kind.name());
+    let path = source.host_path(&path).ok_or(io::ErrorKind::Unsupported)?;
+    let file = File::open(path)?;
+    let request = match ns.kind {
+        NamespaceKind::User => NS_GET_PARENT,
+        _ => NS_GET_USERNS,
+    };
+    // SAFETY: both requests take no argument and return a new file descriptor or -1.
+    let fd = unsafe { libc::ioctl(file.as_raw_fd(), request) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the descriptor is new and owned by nobody else.
+    let owner = unsafe { File::from_raw_fd(fd) };
+    Ok(owner.metadata()?.ino())
+}
+
+/// Owners already looked up, they never change while the namespace exists.
+#[derive(Default)]
+pub struct Owners {
+    owners: HashMap<Namespace, Option<u64>>,
+}
+
+impl Owners {
+    fn get(
+        &mut self,
+        source: &dyn ProcSource,
+        namespace: Namespace,
+        members: &[&Process],
+    ) -> Option<u64> {
+        if let Some(owner) = self.owners.get(&namespace) {
+            return *owner;
+        }
+
+        let mut found = None;
+        for process in members {
+            match owner(source, process.pid, namespace) {
+                Ok(inode) => {
+                    found = Some(inode);
+                    break;
+                }
+                // The same for every member, like for the initial user namespace.
+                Err(err) if matches!(err.raw_os_error(), Some(libc::EPERM | libc::EINVAL)) => break,
+                // The member exited or belongs to another user, another one might work.
+                Err(_) => {}
+            }
+        }
+        self.owners.insert(namespace, found);
+        found
+    }
+}
+
+/// A namespace with the processes in it.
+pub struct NamespaceInfo {
+    pub namespace: Namespace,
+    pub pids: Vec<u64>,
+    /// Most common command names first.
+    pub commands: Vec<(String, usize)>,
+    /// Shared with init, i.e. the namespace of the host.
+    pub host: bool,
+    /// The owning user namespace, the parent for user namespaces.
+    pub owner: Option<u64>,
+}
+
+impl NamespaceInfo {
+    pub fn command_summary(&self) -> String {
+        command_summary(&self.commands)
+    }
+}
+
+/// Every namespace any of `processes` is in, by kind and host namespaces first. Owners are only
+/// looked up on the host's procfs, since a recording can't be asked.
+pub fn list_namespaces(
+    processes: &[Process],
+    source: &dyn ProcSource,
+    owners: &mut Owners,
+) -> Vec<NamespaceInfo> {
+    puffin::profile_function!();
+
+    let live = source.host_path(Path::new("")).is_some();
+
+    // kthreadd stands in for init if that can't be read, kernel threads never leave the initial
+    // namespaces.
+    let host = [1, 2].into_iter().find_map(|pid| {
+        processes
+            .iter()
+            .find(|p| p.pid == pid)
+            .and_then(|p| p.namespaces.as_ref())
+    });
+
+    let mut members: HashMap<Namespace, Vec<&Process>> = HashMap::new();
+    for process in processes {
+        for ns in process.namespaces.iter().flat_map(|n| &n.entries) {
+            members.entry(*ns).or_default().push(process);
+        }
+    }
+
+    if live {
+        owners
+            .owners
+            .retain(|namespace, _| members.contains_key(namespace));
+    }
+
+    let mut namespaces: Vec<NamespaceInfo> = members
+        .into_iter()
+        .map(|(namespace, processes)| {
+            let mut commands = CommandCounts::default();
+            for process in &processes {
+                commands.add(process);
+            }
+
+            let owner = live
+                .then(|| owners.get(source, namespace, &processes))
+                .flatten();
+
+            NamespaceInfo {
+                namespace,
+                pids: processes.iter().map(|p| p.pid).collect(),
+                commands: commands.sorted(),
+                host: host.and_then(|h| h.get(namespace.kind)) == Some(namespace),
+                owner,
+            }
+        })
+        .collect();
+    namespaces.sort_by(|a, b| {
+        a.namespace
+            .kind
+            .cmp(&b.namespace.kind)
+            .then_with(|| b.host.cmp(&a.host))
+            .then_with(|| b.pids.len().cmp(&a.pids.len()))
+            .then_with(|| a.namespace.inode.cmp(&b.namespace.inode))
+    });
+    namespaces
+}
+
+/// The namespace list, returns the namespace that was clicked.
+#[cfg(feature = "gui")]
+pub fn show_namespaces(
+    ui: &mut Ui,
+    namespaces: &[NamespaceInfo],
+    kind: &mut Option<NamespaceKind>,
+) -> Option<Namespace> {
+    puffin::profile_function!();
+
+    ui.horizontal(|ui| {
+        ui.selectable_value(kind, None, "all");
+        for k in NamespaceKind::ALL {
+            ui.selectable_value(kind, Some(k), k.name())
+                .on_hover_text(k.description());
+        }
+    });
+    ui.separator();
+
+    let mut clicked = None;
+    egui::ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+        egui::Grid::new("namespaces").striped(true).show(ui, |ui| {
+            for name in ["Kind", "Inode", "", "Processes", "Owner", "Commands"] {
+                ui.label(RichText::new(name).color(Color32::WHITE).strong());
+            }
+            ui.end_row();
+
+            for info in namespaces
+                .iter()
+                .filter(|info| kind.is_none_or(|k| k == info.namespace.kind))
+            {
+                let ns = info.namespace;
+                ui.label(RichText::new(ns.kind.name()).color(Color32::LIGHT_GRAY))
+                    .on_hover_text(ns.kind.description());
+                let inode = ui.add(
+                    egui::Label::new(RichText::new(ns.inode.to_string()).color(Color32::WHITE))
+                        .sense(Sense::click()),
+                );
+                if inode.on_hover_text("Show the processes").clicked() {
+                    clicked = Some(ns);
+                }
+                ui.label(
+                    RichText::new(if info.host { "host" } else { "" }).color(Color32::LIGHT_GRAY),
+                );
+                ui.label(RichText::new(info.pids.len().to_string()).color(Color32::LIGHT_GRAY));
+                let owner = match (info.owner, ns.kind) {
+                    (Some(owner), NamespaceKind::User) => format!("parent user:[{}]", owner),
+                    (Some(owner), _) => format!("user:[{}]", owner),
+                    (None, _) => "-".to_string(),
+                };
+                ui.label(RichText::new(owner).color(Color32::LIGHT_GRAY));
+                ui.label(RichText::new(info.command_summary()).color(Color32::LIGHT_GRAY));
+                ui.end_row();
+            }
+        });
+    });
+    clicked
+}
+
+/// The namespaces of a single process with its pid in each pid namespace.
+#[cfg(feature = "gui")]
+pub fn show_process_namespaces(ui: &mut Ui, namespaces: &Namespaces, ns_pids: &[u64]) {
+    egui::Grid::new("process_namespaces")
+        .striped(true)
+        .show(ui, |ui| {
+            for ns in &namespaces.entries {
+                ui.label(RichText::new(ns.kind.name()).color(Color32::WHITE))
+                    .on_hover_text(ns.kind.description());
+                ui.label(RichText::new(ns.inode.to_string()).color(Color32::LIGHT_GRAY));
+                ui.end_row();
+            }
+            if ns_pids.len() > 1 {
+                ui.label(RichText::new("NSpid").color(Color32::WHITE))
+                    .on_hover_text("The pid in each pid namespace, outermost first");
+                let pids: Vec<String> = ns_pids.iter().map(u64::to_string).collect();
+                ui.label(RichText::new(pids.join(" ")).color(Color32::LIGHT_GRAY));
+                ui.end_row();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::OsString,
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::procfs::{DirSource, MemorySource};
+
+    /// The host's procfs, counting how often a file would be opened for an ioctl.
+    #[derive(Default)]
+    struct Counting {
+        inner: DirSource,
+        opened: AtomicUsize,
+    }
+
+    impl ProcSource for Counting {
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.inner.read(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+            self.inner.read_dir(path)
+        }
+
+        fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+            self.inner.read_link(path)
+        }
+
+        fn is_dir(&self, path: &Path) -> io::Result<bool> {
+            self.inner.is_dir(path)
+        }
+
+        fn is_device(&self, path: &Path) -> io::Result<bool> {
+            self.inner.is_device(path)
+        }
+
+        fn host_path(&self, path: &Path) -> Option<PathBuf> {
+            if !path.as_os_str().is_empty() {
+                self.opened.fetch_add(1, Ordering::Relaxed);
+            }
+            self.inner.host_path(path)
+        }
+    }
+
+    #[test]
+    fn host_paths() {
+        assert_eq!(
+            DirSource::default().host_path(Path::new("1/ns/net")),
+            Some(PathBuf::from("/proc/1/ns/net"))
+        );
+        assert_eq!(DirSource::new("/tmp/proc").host_path(Path::new("1")), None);
+        assert_eq!(MemorySource::new().host_path(Path::new("1")), None);
+
+        let ns = Namespace {
+            kind: NamespaceKind::Net,
+            inode: 4026531840,
+        };
+        let err = owner(&DirSource::new("/tmp/proc"), 1, ns).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn owners_are_cached() {
+        let source = Counting::default();
+        let pid = u64::from(std::process::id());
+        let mut scan = crate::process::parse_processes(&source, &Default::default()).unwrap();
+        scan.processes.retain(|p| p.pid == pid);
+        let Some(namespaces) = scan.processes[0].namespaces.clone() else {
+            return;
+        };
+        // Every member gives the same answer, so the first one decides.
+        let processes = vec![scan.processes[0].clone(); 5];
+
+        let mut owners = Owners::default();
+        let first = list_namespaces(&processes, &source, &mut owners);
+        assert_eq!(
+            source.opened.swap(0, Ordering::Relaxed),
+            namespaces.entries.len()
+        );
+        let second = list_namespaces(&processes, &source, &mut owners);
+        assert_eq!(source.opened.load(Ordering::Relaxed), 0);
+        let owners = |list: &[NamespaceInfo]| list.iter().map(|i| i.owner).collect::<Vec<_>>();
+        assert_eq!(owners(&first), owners(&second));
+    }
+}
@@ -0,0 +1,705 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::Path,
+};
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Sense, TextStyle, Ui};
+#[cfg(feature = "gui")]
+use egui_extras::TableBuilder;
+
+use crate::{
+    error::{Error, ErrorKind, Result},
+    fd::Fd,
+    process::Process,
+    procfs::{self, ProcSource},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Protocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+    Raw,
+    Raw6,
+    Unix,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 7] = [
+        Protocol::Tcp,
+        Protocol::Tcp6,
+        Protocol::Udp,
+        Protocol::Udp6,
+        Protocol::Raw,
+        Protocol::Raw6,
+        Protocol::Unix,
+    ];
+
+    /// Name of the file in `/proc/net`.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Tcp6 => "tcp6",
+            Protocol::Udp => "udp",
+            Protocol::Udp6 => "udp6",
+            Protocol::Raw => "raw",
+            Protocol::Raw6 => "raw6",
+            Protocol::Unix => "unix",
+        }
+    }
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file_name())
+    }
+}
+
+/// Connection state, named like `ss` does.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SocketState {
+    Established,
+    SynSent,
+    SynRecv,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    Close,
+    CloseWait,
+    LastAck,
+    Listen,
+    Closing,
+    NewSynRecv,
+    /// UDP and raw sockets without a peer, and unix sockets that aren't connected.
+    Unconnected,
+    Connecting,
+    Connected,
+    Disconnecting,
+    Unknown(u8),
+}
+
+impl SocketState {
+    /// The `st` column, whose meaning depends on the protocol.
+    fn parse(protocol: Protocol, st: u8, unix_flags: u32) -> Self {
+        const SO_ACCEPTCON: u32 = 1 << 16;
+
+        match protocol {
+            Protocol::Tcp | Protocol::Tcp6 => match st {
+                1 => SocketState::Established,
+                2 => SocketState::SynSent,
+                3 => SocketState::SynRecv,
+                4 => SocketState::FinWait1,
+                5 => SocketState::FinWait2,
+                6 => SocketState::TimeWait,
+                7 => SocketState::Close,
+                8 => SocketState::CloseWait,
+                9 => SocketState::LastAck,
+                10 => SocketState::Listen,
+                11 => SocketState::Closing,
+                12 => SocketState::NewSynRecv,
+                st => SocketState::Unknown(st),
+            },
+            Protocol::Udp | Protocol::Udp6 | Protocol::Raw | Protocol::Raw6 => match st {
+                1 => SocketState::Established,
+                7 => SocketState::Unconnected,
+                st => SocketState::Unknown(st),
+            },
+            Protocol::Unix if unix_flags & SO_ACCEPTCON != 0 => SocketState::Listen,
+            Protocol::Unix => match st {
+                1 => SocketState::Unconnected,
+                2 => SocketState::Connecting,
+                3 => SocketState::Connected,
+                4 => SocketState::Disconnecting,
+                st => SocketState::Unknown(st),
+            },
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn color(&self) -> Color32 {
+        match self {
+            SocketState::Established | SocketState::Connected => Color32::GREEN,
+            SocketState::Listen => Color32::LIGHT_BLUE,
+            SocketState::Unconnected => Color32::LIGHT_GRAY,
+            SocketState::Unknown(_) => Color32::RED,
+            _ => Color32::YELLOW,
+        }
+    }
+}
+
+impl Display for SocketState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SocketState::Established => write!(f, "ESTAB"),
+            SocketState::SynSent => write!(f, "SYN-SENT"),
+            SocketState::SynRecv => write!(f, "SYN-RECV"),
+            SocketState::FinWait1 => write!(f, "FIN-WAIT-1"),
+            SocketState::FinWait2 => write!(f, "FIN-WAIT-2"),
+            SocketState::TimeWait => write!(f, "TIME-WAIT"),
+            SocketState::Close => write!(f, "CLOSE"),
+            SocketState::CloseWait => write!(f, "CLOSE-WAIT"),
+            SocketState::LastAck => write!(f, "LAST-ACK"),
+            SocketState::Listen => write!(f, "LISTEN"),
+            SocketState::Closing => write!(f, "CLOSING"),
+            SocketState::NewSynRecv => write!(f, "NEW-SYN-RECV"),
+            SocketState::Unconnected => write!(f, "UNCONN"),
+            SocketState::Connecting => write!(f, "CONNECTING"),
+            SocketState::Connected => write!(f, "CONNECTED"),
+            SocketState::Disconnecting => write!(f, "DISCONNECTING"),
+            SocketState::Unknown(st) => write!(f, "UNKNOWN({:02X})", st),
+        }
+    }
+}
+
+/// A line from one of the socket tables in `/proc/net`.
+#[derive(Clone)]
+pub struct Socket {
+    pub protocol: Protocol,
+    /// Not set for unix sockets.
+    pub local: Option<SocketAddr>,
+    pub remote: Option<SocketAddr>,
+    /// Bound path of a unix socket, `@` for abstract ones.
+    pub path: Option<String>,
+    pub state: SocketState,
+    pub tx_queue: u64,
+    pub rx_queue: u64,
+    /// Not reported for unix sockets.
+    pub uid: Option<u32>,
+    /// Matches the `socket:[inode]` link in the owner's `fd` directory.
+    pub inode: u64,
+}
+
+impl Socket {
+    pub fn local_address(&self) -> String {
+        match (&self.local, &self.path) {
+            (Some(local), _) => local.to_string(),
+            (None, Some(path)) => path.clone(),
+            (None, None) => "*".to_string(),
+        }
+    }
+
+    pub fn remote_address(&self) -> String {
+        match &self.remote {
+            Some(remote) if !remote.ip().is_unspecified() || remote.port() != 0 => {
+                remote.to_string()
+            }
+            Some(_) => "*".to_string(),
+            None => "".to_string(),
+        }
+    }
+}
+
+/// Reads every socket table below `net_dir`, e.g. `net` for the namespace we are in or
+/// `[pid]/net` for the one of a process. Tables the kernel doesn't have, like `tcp6` without
+/// IPv6, are skipped, lines that can't be parsed are added to `errors`.
+pub fn parse_sockets(
+    source: &dyn ProcSource,
+    net_dir: &Path,
+    errors: &mut Vec<Error>,
+) -> Result<Vec<Socket>> {
+    let mut sockets = Vec::new();
+    for protocol in Protocol::ALL {
+        let path = net_dir.join(protocol.file_name());
+        let contents = match procfs::read_to_string(source, &path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+
+        for line in contents.lines().skip(1) {
+            let socket = if protocol == Protocol::Unix {
+                parse_unix(line)
+            } else {
+                parse_inet(protocol, line)
+            };
+            match socket {
+                Some(socket) => sockets.push(socket),
+                None => errors.push(Error::malformed(
+                    &path,
+                    format!("bad socket: {}", line.trim()),
+                )),
+            }
+        }
+    }
+
+    Ok(sockets)
+}
+
+/// `0: 0100007F:0035 00000000:0000 0A 00000000:00000000 00:00000000 00000000 101 0 12345 ...`
+fn parse_inet(protocol: Protocol, line: &str) -> Option<Socket> {
+    let mut fields = line.split_whitespace().skip(1);
+    let local = parse_address(fields.next()?)?;
+    let remote = parse_address(fields.next()?)?;
+    let st = u8::from_str_radix(fields.next()?, 16).ok()?;
+    let (tx_queue, rx_queue) = fields.next()?.split_once(':')?;
+    let mut fields = fields.skip(2);
+    let uid = fields.next()?.parse().ok()?;
+    let inode = fields.nth(1)?.parse().ok()?;
+
+    Some(Socket {
+        protocol,
+        local: Some(local),
+        remote: Some(remote),
+        path: None,
+        state: SocketState::parse(protocol, st, 0),
+        tx_queue: u64::from_str_radix(tx_queue, 16).ok()?,
+        rx_queue: u64::from_str_radix(rx_queue, 16).ok()?,
+        uid: Some(uid),
+        inode,
+    })
+}
+
+/// The kernel prints the address as 32 bit words in host byte order, and the port in hex.
+fn parse_address(address: &str) -> Option<SocketAddr> {
+    let (ip, port) = address.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let mut bytes = Vec::with_capacity(16);
+    for i in (0..ip.len()).step_by(8) {
+        let word = u32::from_str_radix(ip.get(i..i + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => {
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?);
+            match ip.to_ipv4_mapped() {
+                Some(ip) => IpAddr::V4(ip),
+                None => IpAddr::V6(ip),
+            }
+        }
+        _ => return None,
+    };
+
+    Some(SocketAddr::new(ip, port))
+}
+
+/// `0000000025418794: 00000003 00000000 00000000 0001 03   926 /run/foo.sock`, the path is the
+/// rest of the line and may contain spaces.
+fn parse_unix(line: &str) -> Option<Socket> {
+    let mut rest = line;
+    for _ in 0..3 {
+        next_field(&mut rest)?;
+    }
+    let flags = u32::from_str_radix(next_field(&mut rest)?, 16).ok()?;
+    let _kind = next_field(&mut rest)?;
+    let st = u8::from_str_radix(next_field(&mut rest)?, 16).ok()?;
+    let inode = next_field(&mut rest)?.parse().ok()?;
+    let path = rest
+        .strip_prefix(' ')
+        .filter(|path| !path.is_empty())
+        .map(String::from);
+
+    Some(Socket {
+        protocol: Protocol::Unix,
+        local: None,
+        remote: None,
+        path,
+        state: SocketState::parse(Protocol::Unix, st, flags),
+        tx_queue: 0,
+        rx_queue: 0,
+        uid: None,
+        inode,
+    })
+}
+
+/// Splits off the next whitespace separated field, leaving the separator in `rest`.
+fn next_field<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let trimmed = rest.trim_start();
+    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    let (field, remainder) = trimmed.split_at(end);
+    *rest = remainder;
+    (!field.is_empty()).then_some(field)
+}
+
+/// Inode of a fd target like `socket:[12345]`.
+pub fn socket_inode(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// A process holding a socket open.
+#[derive(Clone, Copy)]
+pub struct SocketOwner {
+    pub pid: u64,
+    pub fd: u32,
+}
+
+/// Maps socket inodes to the processes that have them open. Processes whose `fd` directory we
+/// may not read are left out.
+pub fn socket_owners(
+    source: &dyn ProcSource,
+    processes: &[Process],
+) -> HashMap<u64, Vec<SocketOwner>> {
+    puffin::profile_function!();
+
+    let mut owners: HashMap<u64, Vec<SocketOwner>> = HashMap::new();
+    for process in processes {
+        let fd_dir = Path::new(&process.pid.to_string()).join("fd");
+        let Ok(names) = source.read_dir(&fd_dir) else {
+            continue;
+        };
+
+        for name in names {
+            let Some(fd) = name.to_str().and_then(|name| name.parse().ok()) else {
+                continue;
+            };
+            let Ok(target) = source.read_link(&fd_dir.join(&name)) else {
+                continue;
+            };
+
+            if let Some(inode) = target.to_str().and_then(socket_inode) {
+                owners.entry(inode).or_default().push(SocketOwner {
+                    pid: process.pid,
+                    fd,
+                });
+            }
+        }
+    }
+
+    owners
+}
+
+/// The system-wide socket list with the owning processes, like `ss -anp`.
+pub struct Connections {
+    pub sockets: Vec<Socket>,
+    pub owners: HashMap<u64, Vec<SocketOwner>>,
+    /// Lines of the socket tables that were skipped.
+    pub errors: Vec<Error>,
+}
+
+impl Connections {
+    /// Only sees sockets in our own network namespace.
+    pub fn load(source: &dyn ProcSource, processes: &[Process]) -> Result<Self> {
+        puffin::profile_function!();
+
+        let mut errors = Vec::new();
+        let mut sockets = parse_sockets(source, Path::new("net"), &mut errors)?;
+        sockets.sort_by(|a, b| (a.protocol, a.local, &a.path).cmp(&(b.protocol, b.local, &b.path)));
+
+        Ok(Self {
+            sockets,
+            owners: socket_owners(source, processes),
+            errors,
+        })
+    }
+}
+
+/// Which sockets the connections panel shows.
+pub struct ConnectionsSettings {
+    pub protocols: HashSet<Protocol>,
+    pub listening_only: bool,
+}
+
+impl Default for ConnectionsSettings {
+    fn default() -> Self {
+        Self {
+            protocols: Protocol::ALL.into_iter().collect(),
+            listening_only: false,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ConnectionsSettings {
+    fn matches(&self, socket: &Socket) -> bool {
+        self.protocols.contains(&socket.protocol)
+            && (!self.listening_only || socket.state == SocketState::Listen)
+    }
+
+    fn show(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            for protocol in Protocol::ALL {
+                let mut shown = self.protocols.contains(&protocol);
+                if ui.checkbox(&mut shown, protocol.file_name()).changed() {
+                    if shown {
+                        self.protocols.insert(protocol);
+                    } else {
+                        self.protocols.remove(&protocol);
+                    }
+                }
+            }
+            ui.separator();
+            ui.checkbox(&mut self.listening_only, "Listening only");
+        });
+    }
+}
+
+/// Clicking a row selects the owning process.
+#[cfg(feature = "gui")]
+pub fn show_connections(
+    ui: &mut Ui,
+    connections: &Connections,
+    processes: &[Process],
+    settings: &mut ConnectionsSettings,
+    selected: &mut Option<u64>,
+) {
+    puffin::profile_function!();
+
+    settings.show(ui);
+    ui.separator();
+    for error in &connections.errors {
+        ui.label(RichText::new(error.to_string()).color(Color32::YELLOW));
+    }
+
+    let sockets: Vec<&Socket> = connections
+        .sockets
+        .iter()
+        .filter(|socket| settings.matches(socket))
+        .collect();
+    let row_height = ui.text_style_height(&TextStyle::Body) + 4.0;
+
+    TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .sense(Sense::click())
+        .auto_shrink(false)
+        .column(egui_extras::Column::initial(60.0))
+        .column(egui_extras::Column::initial(260.0).clip(true))
+        .column(egui_extras::Column::initial(260.0).clip(true))
+        .column(egui_extras::Column::initial(110.0))
+        .column(egui_extras::Column::initial(70.0))
+        .column(egui_extras::Column::initial(70.0))
+        .column(egui_extras::Column::initial(70.0))
+        .column(egui_extras::Column::initial(90.0))
+        .column(egui_extras::Column::remainder().clip(true))
+        .header(row_height, |mut header| {
+            for name in [
+                "Proto", "Local", "Remote", "State", "Recv-Q", "Send-Q", "UID", "Inode", "Process",
+            ] {
+                header.col(|ui| {
+                    ui.label(RichText::new(name).color(Color32::WHITE).strong());
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, sockets.len(), |mut row| {
+                let socket = sockets[row.index()];
+                let owners = connections
+                    .owners
+                    .get(&socket.inode)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                row.set_selected(owners.iter().any(|owner| Some(owner.pid) == *selected));
+
+                let value = |text: String| RichText::new(text).color(Color32::LIGHT_GRAY);
+                row.col(|ui| {
+                    ui.label(value(socket.protocol.to_string()));
+                });
+                row.col(|ui| {
+                    ui.label(value(socket.local_address()));
+                });
+                row.col(|ui| {
+                    ui.label(value(socket.remote_address()));
+                });
+                row.col(|ui| {
+                    ui.label(RichText::new(socket.state.to_string()).color(socket.state.color()));
+                });
+                row.col(|ui| {
+                    ui.label(value(socket.rx_queue.to_string()));
+                });
+                row.col(|ui| {
+                    ui.label(value(socket.tx_queue.to_string()));
+                });
+                row.col(|ui| {
+                    ui.label(value(
+                        socket.uid.map(|uid| uid.to_string()).unwrap_or_default(),
+                    ));
+                });
+                row.col(|ui| {
+                    ui.label(value(socket.inode.to_string()));
+                });
+                row.col(|ui| {
+                    let owner_text = owners
+                        .iter()
+                        .map(|owner| {
+                            let command = processes
+                                .iter()
+                                .find(|p| p.pid == owner.pid)
+                                .map(|p| p.stats.tcomm.as_str())
+                                .unwrap_or("?");
+                            format!("{}({}) fd {}", command, owner.pid, owner.fd)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    ui.label(RichText::new(owner_text).color(Color32::WHITE));
+                });
+
+                if row.response().clicked() {
+                    if let Some(owner) = owners.first() {
+                        *selected = Some(owner.pid);
+                    }
+                }
+            });
+        });
+}
+
+/// The sockets among `fds`, looked up in the network namespace of `pid`.
+pub struct ProcessSockets {
+    pub sockets: Vec<(u32, Socket)>,
+    /// Sockets of families without a table in `/proc/net`, like netlink or packet sockets.
+    pub unknown: usize,
+    /// Lines of the socket tables that were skipped.
+    pub errors: Vec<Error>,
+}
+
+pub fn parse_process_sockets(
+    source: &dyn ProcSource,
+    pid: u64,
+    fds: &[Fd],
+) -> Result<ProcessSockets> {
+    let inodes: Vec<(u32, u64)> = fds
+        .iter()
+        .filter_map(|fd| Some((fd.fd, socket_inode(&fd.target)?)))
+        .collect();
+    if inodes.is_empty() {
+        return Ok(ProcessSockets {
+            sockets: Vec::new(),
+            unknown: 0,
+            errors: Vec::new(),
+        });
+    }
+
+    let mut errors = Vec::new();
+    let all = parse_sockets(
+        source,
+        &Path::new(&pid.to_string()).join("net"),
+        &mut errors,
+    )?;
+    let by_inode: HashMap<u64, &Socket> = all.iter().map(|s| (s.inode, s)).collect();
+
+    let mut sockets = Vec::new();
+    let mut unknown = 0;
+    for (fd, inode) in inodes {
+        match by_inode.get(&inode) {
+            Some(socket) => sockets.push((fd, (*socket).clone())),
+            None => unknown += 1,
+        }
+    }
+
+    Ok(ProcessSockets {
+        sockets,
+        unknown,
+        errors,
+    })
+}
+
+#[cfg(feature = "gui")]
+pub fn show_process_sockets(ui: &mut Ui, sockets: &ProcessSockets) {
+    puffin::profile_function!();
+
+    if sockets.unknown > 0 {
+        ui.label(
+            RichText::new(format!(
+                "{} sockets of other families (netlink, packet, ...)",
+                sockets.unknown
+            ))
+            .color(Color32::GRAY),
+        );
+    }
+    for error in &sockets.errors {
+        ui.label(RichText::new(error.to_string()).color(Color32::YELLOW));
+    }
+
+    egui::Grid::new("sockets").striped(true).show(ui, |ui| {
+        for header in [
+            "FD", "Proto", "Local", "Remote", "State", "Recv-Q", "Send-Q",
+        ] {
+            ui.label(RichText::new(header).color(Color32::WHITE));
+        }
+        ui.end_row();
+
+        for (fd, socket) in &sockets.sockets {
+            for text in [
+                fd.to_string(),
+                socket.protocol.to_string(),
+                socket.local_address(),
+                socket.remote_address(),
+            ] {
+                ui.label(RichText::new(text).color(Color32::LIGHT_GRAY));
+            }
+            ui.label(RichText::new(socket.state.to_string()).color(socket.state.color()));
+            ui.label(RichText::new(socket.rx_queue.to_string()).color(Color32::LIGHT_GRAY));
+            ui.label(RichText::new(socket.tx_queue.to_string()).color(Color32::LIGHT_GRAY));
+            ui.end_row();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs::MemorySource;
+
+    const UNIX: &str = "\
+Num       RefCount Protocol Flags    Type St Inode Path
+0000000025418794: 00000003 00000000 00000000 0001 03   926 /run/my app/app.sock
+0000000025418795: 00000002 00000000 00010000 0001 01   927 @abstract
+0000000025418796: 00000002 00000000 00000000 0002 01   928
+garbage
+0000000025418797: 00000002 00000000 00000000 0001 01   929 /run/two  spaces
+";
+
+    #[test]
+    fn unix_paths_with_spaces() {
+        let source = MemorySource::new().with("net/unix", UNIX);
+        let mut errors = Vec::new();
+        let sockets = parse_sockets(&source, Path::new("net"), &mut errors).unwrap();
+
+        let paths: Vec<(u64, Option<&str>)> = sockets
+            .iter()
+            .map(|socket| (socket.inode, socket.path.as_deref()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                (926, Some("/run/my app/app.sock")),
+                (927, Some("@abstract")),
+                (928, None),
+                (929, Some("/run/two  spaces")),
+            ]
+        );
+        assert_eq!(sockets[0].state, SocketState::Connected);
+        assert_eq!(sockets[1].state, SocketState::Listen);
+
+        let [error] = &errors[..] else {
+            panic!("expected one error, got {:?}", errors);
+        };
+        assert_eq!(error.path, Path::new("net/unix"));
+        assert_eq!(
+            error.kind,
+            ErrorKind::Malformed("bad socket: garbage".to_string())
+        );
+    }
+
+    #[test]
+    fn inet() {
+        let source = MemorySource::new().with(
+            "net/tcp",
+            "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   \
+             0: 0100007F:0035 00000000:0000 0A 00000000:00000002 00:00000000 00000000   101        0 12345 1 0000000000000000 100 0 0 10 0\n   \
+             1: nonsense\n",
+        );
+        let mut errors = Vec::new();
+        let sockets = parse_sockets(&source, Path::new("net"), &mut errors).unwrap();
+
+        let [socket] = &sockets[..] else {
+            panic!("expected one socket, got {}", sockets.len());
+        };
+        assert_eq!(socket.local_address(), "127.0.0.1:53");
+        assert_eq!(socket.remote_address(), "*");
+        assert_eq!(socket.state, SocketState::Listen);
+        assert_eq!(socket.rx_queue, 2);
+        assert_eq!(socket.uid, Some(101));
+        assert_eq!(socket.inode, 12345);
+        assert_eq!(errors.len(), 1);
+    }
+}
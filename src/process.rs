@@ -0,0 +1,1224 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Ui};
+
+use crate::{
+    cgroup::{parse_cgroups, Cgroups, Workload},
+    cpu::clock_ticks,
+    error::{Error, ErrorKind, Result},
+    memory::{parse_memory, MemoryInfo},
+    namespace::{parse_namespaces, parse_ns_pids, Namespaces},
+    procfs::{self, KeyValues, ProcSource},
+    system::parse_boot_time,
+    thread::{parse_threads, Thread},
+    users::{group_name, user_name},
+};
+#[cfg(feature = "gui")]
+use crate::{namespace::show_process_namespaces, thread::show_threads};
+
+/// https://docs.kernel.org/filesystems/proc.html
+#[derive(Clone)]
+pub struct Process {
+    pub pid: u64,
+    pub cmdline: String,
+
+    pub stats: ProcessStats,
+    pub credentials: Option<Credentials>,
+    pub cgroups: Option<Cgroups>,
+    /// `None` if the links can't be read, which needs the same permission as ptrace.
+    pub namespaces: Option<Namespaces>,
+    /// The pid in each pid namespace the process is in, outermost first.
+    pub ns_pids: Vec<u64>,
+    pub start_time: Option<SystemTime>,
+    pub memory: MemoryInfo,
+    pub threads: Vec<Thread>,
+    /// Percentage of a single core used since the previous sample, see [`crate::cpu::CpuTracker`].
+    pub cpu_usage: Option<f64>,
+    /// Things that could not be read, the process is only partially shown if this is not empty.
+    pub errors: Vec<Error>,
+}
+
+impl Process {
+    /// What to show as the command, kernel threads have no cmdline.
+    pub fn command(&self) -> String {
+        if self.cmdline.is_empty() {
+            format!("[{}]", self.stats.tcomm)
+        } else {
+            self.cmdline.clone()
+        }
+    }
+
+    /// Effective uid, the one that counts for permissions.
+    pub fn uid(&self) -> Option<u32> {
+        self.credentials.as_ref().map(|c| c.uid.effective)
+    }
+
+    /// Effective gid.
+    pub fn gid(&self) -> Option<u32> {
+        self.credentials.as_ref().map(|c| c.gid.effective)
+    }
+
+    /// The cgroup path that says the most about the process, see [`Cgroups::path`].
+    pub fn cgroup(&self) -> Option<&str> {
+        self.cgroups.as_ref().map(Cgroups::path)
+    }
+
+    pub fn workload(&self) -> Option<Workload> {
+        self.cgroup().map(Workload::from_path)
+    }
+
+    /// The pid as seen inside the innermost pid namespace, e.g. in a container.
+    pub fn ns_pid(&self) -> u64 {
+        self.ns_pids.last().copied().unwrap_or(self.pid)
+    }
+
+    /// Whether the process runs with other ids than the ones of the user who started it.
+    pub fn is_setuid(&self) -> bool {
+        self.credentials
+            .as_ref()
+            .is_some_and(|c| c.is_setuid() || c.is_setgid())
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn show_details(&self, ui: &mut Ui) {
+        puffin::profile_function!();
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("State").color(Color32::WHITE));
+            self.stats.state.show(ui);
+        });
+        ui.label(RichText::new(&self.cmdline).color(Color32::LIGHT_GRAY));
+        ui.separator();
+
+        if let Some(credentials) = &self.credentials {
+            ui.label(RichText::new("Credentials").color(Color32::WHITE).strong());
+            credentials.show(ui);
+            ui.separator();
+        }
+
+        if let Some(cgroups) = &self.cgroups {
+            ui.label(RichText::new("Cgroups").color(Color32::WHITE).strong());
+            cgroups.show(ui);
+            ui.separator();
+        }
+
+        if let Some(namespaces) = &self.namespaces {
+            ui.label(RichText::new("Namespaces").color(Color32::WHITE).strong());
+            show_process_namespaces(ui, namespaces, &self.ns_pids);
+            ui.separator();
+        }
+
+        ui.label(RichText::new("Memory").color(Color32::WHITE).strong());
+        self.memory.show(ui);
+        ui.separator();
+
+        ui.label(RichText::new("Threads").color(Color32::WHITE).strong());
+        show_threads(ui, &self.threads);
+        ui.separator();
+
+        ui.label(RichText::new("Stat").color(Color32::WHITE).strong());
+        self.stats.show(ui);
+
+        for error in &self.errors {
+            ui.label(RichText::new(error.to_string()).color(Color32::YELLOW));
+        }
+    }
+
+    pub fn contains(&self, search_text: &str) -> bool {
+        self.pid.to_string().contains(search_text)
+            || self.cmdline.contains(search_text)
+            || self.stats.contains(search_text)
+    }
+}
+
+/// Result of a single pass over procfs.
+#[derive(Clone, Default)]
+pub struct Scan {
+    pub processes: Vec<Process>,
+    /// Processes that could not be read at all.
+    pub skipped: Vec<SkippedProcess>,
+}
+
+impl Scan {
+    /// The process with `pid`, or the process `pid` is a thread of.
+    pub fn find(&self, pid: u64) -> Option<&Process> {
+        self.processes.iter().find(|p| p.pid == pid).or_else(|| {
+            self.processes
+                .iter()
+                .find(|p| p.threads.iter().any(|t| t.tid == pid))
+        })
+    }
+
+    pub fn permission_denied(&self) -> usize {
+        self.skipped
+            .iter()
+            .filter(|s| s.error.kind == ErrorKind::PermissionDenied)
+            .count()
+    }
+
+    pub fn partial(&self) -> usize {
+        self.processes
+            .iter()
+            .filter(|p| !p.errors.is_empty())
+            .count()
+    }
+}
+
+#[derive(Clone)]
+pub struct SkippedProcess {
+    pub pid: u64,
+    pub error: Error,
+}
+
+/// How many processes of a group, like a workload or namespace, run each command.
+#[derive(Default)]
+pub struct CommandCounts<'a> {
+    counts: HashMap<&'a str, usize>,
+}
+
+impl<'a> CommandCounts<'a> {
+    pub fn add(&mut self, process: &'a Process) {
+        *self.counts.entry(&process.stats.tcomm).or_default() += 1;
+    }
+
+    /// Most common first, then by name.
+    pub fn sorted(self) -> Vec<(String, usize)> {
+        let mut commands: Vec<(String, usize)> = self
+            .counts
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+        commands.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        commands
+    }
+}
+
+/// Counted commands like `nginx ×4, bash`.
+pub fn command_summary(commands: &[(String, usize)]) -> String {
+    commands
+        .iter()
+        .map(|(command, count)| match count {
+            1 => command.clone(),
+            count => format!("{} ×{}", command, count),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// What a scan reads beyond the essentials.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// `smaps_rollup` of every process, for PSS, USS and swap. The kernel walks every mapping
+    /// under the mmap lock for it, the slowest part of a scan on large hosts.
+    pub rollup: bool,
+    /// A process to read `smaps_rollup` of anyway, like the one shown in the details.
+    pub rollup_pid: Option<u64>,
+}
+
+impl ScanOptions {
+    /// Everything, for replays, which only contain what was read while recording.
+    pub fn all() -> Self {
+        Self {
+            rollup: true,
+            rollup_pid: None,
+        }
+    }
+
+    /// Whether a scan with these options fills in something `other` leaves out.
+    pub fn reads_more_than(&self, other: &ScanOptions) -> bool {
+        !other.rollup
+            && (self.rollup || self.rollup_pid.is_some() && self.rollup_pid != other.rollup_pid)
+    }
+}
+
+/// Things that are the same for every process of a scan.
+struct ScanContext<'a> {
+    options: &'a ScanOptions,
+    kernel: Option<KernelVersion>,
+    boot_time: Option<SystemTime>,
+    clock_ticks: u64,
+}
+
+pub fn parse_processes(source: &dyn ProcSource, options: &ScanOptions) -> Result<Scan> {
+    let mut scan = Scan::default();
+    let context = ScanContext {
+        options,
+        kernel: KernelVersion::parse(source),
+        boot_time: parse_boot_time(source).ok(),
+        clock_ticks: clock_ticks(),
+    };
+
+    for name in procfs::read_dir(source, Path::new(""))? {
+        let Some(pid) = name.to_str().and_then(|name| name.parse::<u64>().ok()) else {
+            continue;
+        };
+
+        match parse_process(source, pid, &context) {
+            Ok(process) => scan.processes.push(process),
+            Err(error) => scan.skipped.push(SkippedProcess { pid, error }),
+        }
+    }
+
+    Ok(scan)
+}
+
+/// Fails only if the essentials are missing, anything else ends up in [`Process::errors`].
+fn parse_process(source: &dyn ProcSource, pid: u64, context: &ScanContext) -> Result<Process> {
+    let dir = Path::new(&pid.to_string()).to_path_buf();
+    let stats = parse_stats(source, pid, context.kernel)?;
+
+    let mut errors = Vec::new();
+    let cmdline = match procfs::read(source, &dir.join("cmdline")) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes)
+            .trim_end_matches('\0')
+            .replace('\0', " "),
+        Err(err) => {
+            errors.push(err);
+            String::new()
+        }
+    };
+
+    let status_path = dir.join("status");
+    let status = match KeyValues::read(source, &status_path) {
+        Ok(status) => Some(status),
+        Err(err) => {
+            errors.push(err);
+            None
+        }
+    };
+
+    let credentials = match status
+        .as_ref()
+        .map(|status| parse_credentials(status, &status_path))
+    {
+        Some(Ok(credentials)) => Some(credentials),
+        Some(Err(err)) => {
+            errors.push(err);
+            None
+        }
+        None => None,
+    };
+
+    let cgroups = match parse_cgroups(source, &dir) {
+        Ok(cgroups) => Some(cgroups),
+        Err(err) => {
+            errors.push(err);
+            None
+        }
+    };
+
+    let namespaces = match parse_namespaces(source, &dir) {
+        Ok(namespaces) => Some(namespaces),
+        // Not worth marking every process of other users as partial.
+        Err(err) if err.kind == ErrorKind::PermissionDenied => None,
+        Err(err) => {
+            errors.push(err);
+            None
+        }
+    };
+    let ns_pids = status
+        .as_ref()
+        .and_then(|status| status.get("NSpid"))
+        .map(parse_ns_pids)
+        .unwrap_or_default();
+
+    let rollup = context.options.rollup || context.options.rollup_pid == Some(pid);
+    let memory = parse_memory(source, &dir, status.as_ref(), rollup, &mut errors);
+    let threads = parse_threads(source, &dir, context.kernel, ns_pids.len() > 1, &mut errors);
+
+    let start_time = context.boot_time.map(|boot_time| {
+        boot_time + Duration::from_secs_f64(stats.starttime as f64 / context.clock_ticks as f64)
+    });
+
+    Ok(Process {
+        pid,
+        cmdline,
+        stats,
+        credentials,
+        cgroups,
+        namespaces,
+        ns_pids,
+        start_time,
+        memory,
+        threads,
+        cpu_usage: None,
+        errors,
+    })
+}
+
+/// Real, effective, saved set and filesystem id, see credentials(7).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Ids {
+    pub real: u32,
+    pub effective: u32,
+    pub saved: u32,
+    pub fs: u32,
+}
+
+impl Ids {
+    /// The four numbers of a `Uid:` or `Gid:` line.
+    fn parse(line: &str) -> Option<Self> {
+        let mut ids = line.split_ascii_whitespace().map(|id| id.parse().ok());
+        Some(Self {
+            real: ids.next()??,
+            effective: ids.next()??,
+            saved: ids.next()??,
+            fs: ids.next()??,
+        })
+    }
+
+    pub fn all(&self) -> [(&'static str, u32); 4] {
+        [
+            ("real", self.real),
+            ("effective", self.effective),
+            ("saved", self.saved),
+            ("fs", self.fs),
+        ]
+    }
+}
+
+/// Who a process runs as, from `/proc/[pid]/status`.
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    pub uid: Ids,
+    pub gid: Ids,
+    /// Supplementary groups.
+    pub groups: Vec<u32>,
+}
+
+impl Credentials {
+    /// Runs as another user than the one who started it, usually because of a setuid binary.
+    pub fn is_setuid(&self) -> bool {
+        self.uid.effective != self.uid.real
+    }
+
+    pub fn is_setgid(&self) -> bool {
+        self.gid.effective != self.gid.real
+    }
+
+    /// Ids with their names, like `1000(alice)`.
+    pub fn user_fields(&self) -> Vec<(&'static str, String)> {
+        self.uid
+            .all()
+            .into_iter()
+            .map(|(name, uid)| (name, format!("{}({})", uid, user_name(uid))))
+            .collect()
+    }
+
+    pub fn group_fields(&self) -> Vec<(&'static str, String)> {
+        self.gid
+            .all()
+            .into_iter()
+            .map(|(name, gid)| (name, format!("{}({})", gid, group_name(gid))))
+            .collect()
+    }
+
+    pub fn group_names(&self) -> String {
+        self.groups
+            .iter()
+            .map(|&gid| format!("{}({})", gid, group_name(gid)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[cfg(feature = "gui")]
+    fn show(&self, ui: &mut Ui) {
+        egui::Grid::new("credentials").striped(true).show(ui, |ui| {
+            for (kind, fields, changed) in [
+                ("Uid", self.user_fields(), self.is_setuid()),
+                ("Gid", self.group_fields(), self.is_setgid()),
+            ] {
+                ui.label(RichText::new(kind).color(Color32::WHITE));
+                for (name, value) in fields {
+                    let color = if changed && name == "effective" {
+                        Color32::YELLOW
+                    } else {
+                        Color32::LIGHT_GRAY
+                    };
+                    ui.label(RichText::new(name).color(Color32::WHITE));
+                    ui.label(RichText::new(value).color(color));
+                }
+                ui.end_row();
+            }
+        });
+        if !self.groups.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(RichText::new("Groups").color(Color32::WHITE));
+                ui.label(RichText::new(self.group_names()).color(Color32::LIGHT_GRAY));
+            });
+        }
+    }
+}
+
+/// The `Uid:`, `Gid:` and `Groups:` lines of a status file.
+fn parse_credentials(status: &KeyValues, path: &Path) -> Result<Credentials> {
+    let ids = |key: &str| {
+        status
+            .get(key)
+            .and_then(Ids::parse)
+            .ok_or_else(|| Error::malformed(path, format!("missing or malformed {}", key)))
+    };
+    let groups = status
+        .get("Groups")
+        .unwrap_or_default()
+        .split_ascii_whitespace()
+        .map(|gid| {
+            gid.parse()
+                .map_err(|_| Error::malformed(path, format!("invalid group '{}'", gid)))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Credentials {
+        uid: ids("Uid")?,
+        gid: ids("Gid")?,
+        groups,
+    })
+}
+
+/// Contents of `/proc/[pid]/stat`, see proc(5) for details on each field.
+///
+/// Fields that were added in later kernel versions are zero when running on kernels that don't
+/// report them yet.
+#[derive(Clone)]
+pub struct ProcessStats {
+    pub pid: u64,
+    pub tcomm: String,
+    pub state: ProcessState,
+    pub ppid: i64,
+    pub pgrp: i64,
+    pub session: i64,
+    pub tty_nr: i64,
+    pub tpgid: i64,
+    pub flags: u64,
+    pub minflt: u64,
+    pub cminflt: u64,
+    pub majflt: u64,
+    pub cmajflt: u64,
+    /// In clock ticks.
+    pub utime: u64,
+    /// In clock ticks.
+    pub stime: u64,
+    /// In clock ticks.
+    pub cutime: i64,
+    /// In clock ticks.
+    pub cstime: i64,
+    pub priority: i64,
+    pub nice: i64,
+    pub num_threads: i64,
+    pub itrealvalue: i64,
+    /// In clock ticks after system boot.
+    pub starttime: u64,
+    /// In bytes.
+    pub vsize: u64,
+    /// In pages.
+    pub rss: i64,
+    /// In bytes.
+    pub rsslim: u64,
+    pub startcode: u64,
+    pub endcode: u64,
+    pub startstack: u64,
+    pub kstkesp: u64,
+    pub kstkeip: u64,
+    pub signal: u64,
+    pub blocked: u64,
+    pub sigignore: u64,
+    pub sigcatch: u64,
+    pub wchan: u64,
+    pub nswap: u64,
+    pub cnswap: u64,
+    pub exit_signal: i64,
+    pub processor: i64,
+    pub rt_priority: u64,
+    pub policy: u64,
+    /// In clock ticks.
+    pub delayacct_blkio_ticks: u64,
+    /// In clock ticks.
+    pub guest_time: u64,
+    /// In clock ticks.
+    pub cguest_time: i64,
+    pub start_data: u64,
+    pub end_data: u64,
+    pub start_brk: u64,
+    pub arg_start: u64,
+    pub arg_end: u64,
+    pub env_start: u64,
+    pub env_end: u64,
+    pub exit_code: i64,
+}
+
+impl ProcessStats {
+    pub fn rss_bytes(&self) -> u64 {
+        self.rss.max(0) as u64 * page_size()
+    }
+
+    fn contains(&self, search_text: &str) -> bool {
+        self.tcomm.contains(search_text)
+    }
+
+    /// Every field of `/proc/[pid]/stat` by its name in proc(5).
+    pub fn fields(&self) -> [(&'static str, String); 52] {
+        [
+            ("pid", self.pid.to_string()),
+            ("tcomm", self.tcomm.clone()),
+            ("state", self.state.to_string()),
+            ("ppid", self.ppid.to_string()),
+            ("pgrp", self.pgrp.to_string()),
+            ("session", self.session.to_string()),
+            ("tty_nr", self.tty_nr.to_string()),
+            ("tpgid", self.tpgid.to_string()),
+            ("flags", format!("{:#x}", self.flags)),
+            ("minflt", self.minflt.to_string()),
+            ("cminflt", self.cminflt.to_string()),
+            ("majflt", self.majflt.to_string()),
+            ("cmajflt", self.cmajflt.to_string()),
+            ("utime", self.utime.to_string()),
+            ("stime", self.stime.to_string()),
+            ("cutime", self.cutime.to_string()),
+            ("cstime", self.cstime.to_string()),
+            ("priority", self.priority.to_string()),
+            ("nice", self.nice.to_string()),
+            ("num_threads", self.num_threads.to_string()),
+            ("itrealvalue", self.itrealvalue.to_string()),
+            ("starttime", self.starttime.to_string()),
+            ("vsize", self.vsize.to_string()),
+            ("rss", self.rss.to_string()),
+            ("rsslim", self.rsslim.to_string()),
+            ("startcode", format!("{:#x}", self.startcode)),
+            ("endcode", format!("{:#x}", self.endcode)),
+            ("startstack", format!("{:#x}", self.startstack)),
+            ("kstkesp", format!("{:#x}", self.kstkesp)),
+            ("kstkeip", format!("{:#x}", self.kstkeip)),
+            ("signal", format!("{:#x}", self.signal)),
+            ("blocked", format!("{:#x}", self.blocked)),
+            ("sigignore", format!("{:#x}", self.sigignore)),
+            ("sigcatch", format!("{:#x}", self.sigcatch)),
+            ("wchan", format!("{:#x}", self.wchan)),
+            ("nswap", self.nswap.to_string()),
+            ("cnswap", self.cnswap.to_string()),
+            ("exit_signal", self.exit_signal.to_string()),
+            ("processor", self.processor.to_string()),
+            ("rt_priority", self.rt_priority.to_string()),
+            ("policy", self.policy.to_string()),
+            (
+                "delayacct_blkio_ticks",
+                self.delayacct_blkio_ticks.to_string(),
+            ),
+            ("guest_time", self.guest_time.to_string()),
+            ("cguest_time", self.cguest_time.to_string()),
+            ("start_data", format!("{:#x}", self.start_data)),
+            ("end_data", format!("{:#x}", self.end_data)),
+            ("start_brk", format!("{:#x}", self.start_brk)),
+            ("arg_start", format!("{:#x}", self.arg_start)),
+            ("arg_end", format!("{:#x}", self.arg_end)),
+            ("env_start", format!("{:#x}", self.env_start)),
+            ("env_end", format!("{:#x}", self.env_end)),
+            ("exit_code", self.exit_code.to_string()),
+        ]
+    }
+
+    #[cfg(feature = "gui")]
+    fn show(&self, ui: &mut Ui) {
+        puffin::profile_function!();
+
+        let fields = self.fields();
+        egui::Grid::new(("stats", self.pid))
+            .striped(true)
+            .show(ui, |ui| {
+                for row in fields.chunks(4) {
+                    for (name, value) in row {
+                        ui.label(RichText::new(*name).color(Color32::WHITE));
+                        ui.label(RichText::new(value).color(Color32::LIGHT_GRAY));
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// Size of a memory page in bytes, the unit of [`ProcessStats::rss`].
+pub fn page_size() -> u64 {
+    // SAFETY: sysconf has no preconditions.
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as u64
+    } else {
+        4096
+    }
+}
+
+/// Whitespace separated fields of a stat file.
+struct Fields<'a> {
+    path: &'a Path,
+    fields: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl<'a> Fields<'a> {
+    fn next<T: FromStr>(&mut self, name: &str) -> Result<T> {
+        let field = self
+            .fields
+            .next()
+            .ok_or_else(|| Error::malformed(self.path, format!("missing {}", name)))?;
+
+        field
+            .parse()
+            .map_err(|_| Error::malformed(self.path, format!("invalid {}: {}", name, field)))
+    }
+
+    /// For fields that older kernels don't report.
+    fn next_or_default<T: FromStr + Default>(&mut self, name: &str) -> Result<T> {
+        match self.fields.clone().next() {
+            Some(_) => self.next(name),
+            None => Ok(T::default()),
+        }
+    }
+}
+
+/// `kernel` is needed to tell apart states whose letters changed meaning over time, assumes a
+/// recent kernel if `None`.
+pub fn parse_stats(
+    source: &dyn ProcSource,
+    pid: u64,
+    kernel: Option<KernelVersion>,
+) -> Result<ProcessStats> {
+    parse_stat_file(source, &Path::new(&pid.to_string()).join("stat"), kernel)
+}
+
+/// Parses any file in the format of `/proc/[pid]/stat`, e.g. `/proc/[pid]/task/[tid]/stat`.
+pub fn parse_stat_file(
+    source: &dyn ProcSource,
+    path: &Path,
+    kernel: Option<KernelVersion>,
+) -> Result<ProcessStats> {
+    let bytes = procfs::read(source, path)?;
+    let contents = String::from_utf8_lossy(&bytes);
+
+    // tcomm may contain anything including spaces and parentheses, but it is the only field
+    // that can contain a ')'.
+    let (open, close) = contents
+        .find('(')
+        .zip(contents.rfind(')'))
+        .filter(|(open, close)| open < close)
+        .ok_or_else(|| Error::malformed(path, "tcomm not in parentheses"))?;
+
+    let pid = contents[..open]
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| Error::malformed(path, "invalid pid"))?;
+    let tcomm = contents[open + 1..close].to_string();
+
+    let mut fields = Fields {
+        path,
+        fields: contents[close + 1..].split_ascii_whitespace(),
+    };
+
+    let state = match fields.next::<String>("state")?.as_bytes() {
+        [state] => ProcessState::parse(*state, kernel),
+        state => {
+            let state = String::from_utf8_lossy(state);
+            return Err(Error::malformed(path, format!("invalid state {}", state)));
+        }
+    };
+
+    Ok(ProcessStats {
+        pid,
+        tcomm,
+        state,
+        ppid: fields.next("ppid")?,
+        pgrp: fields.next("pgrp")?,
+        session: fields.next("session")?,
+        tty_nr: fields.next("tty_nr")?,
+        tpgid: fields.next("tpgid")?,
+        flags: fields.next("flags")?,
+        minflt: fields.next("minflt")?,
+        cminflt: fields.next("cminflt")?,
+        majflt: fields.next("majflt")?,
+        cmajflt: fields.next("cmajflt")?,
+        utime: fields.next("utime")?,
+        stime: fields.next("stime")?,
+        cutime: fields.next("cutime")?,
+        cstime: fields.next("cstime")?,
+        priority: fields.next("priority")?,
+        nice: fields.next("nice")?,
+        num_threads: fields.next("num_threads")?,
+        itrealvalue: fields.next("itrealvalue")?,
+        starttime: fields.next("starttime")?,
+        vsize: fields.next("vsize")?,
+        rss: fields.next("rss")?,
+        rsslim: fields.next("rsslim")?,
+        startcode: fields.next("startcode")?,
+        endcode: fields.next("endcode")?,
+        startstack: fields.next("startstack")?,
+        kstkesp: fields.next("kstkesp")?,
+        kstkeip: fields.next("kstkeip")?,
+        signal: fields.next("signal")?,
+        blocked: fields.next("blocked")?,
+        sigignore: fields.next("sigignore")?,
+        sigcatch: fields.next("sigcatch")?,
+        wchan: fields.next("wchan")?,
+        nswap: fields.next("nswap")?,
+        cnswap: fields.next("cnswap")?,
+        exit_signal: fields.next_or_default("exit_signal")?,
+        processor: fields.next_or_default("processor")?,
+        rt_priority: fields.next_or_default("rt_priority")?,
+        policy: fields.next_or_default("policy")?,
+        delayacct_blkio_ticks: fields.next_or_default("delayacct_blkio_ticks")?,
+        guest_time: fields.next_or_default("guest_time")?,
+        cguest_time: fields.next_or_default("cguest_time")?,
+        start_data: fields.next_or_default("start_data")?,
+        end_data: fields.next_or_default("end_data")?,
+        start_brk: fields.next_or_default("start_brk")?,
+        arg_start: fields.next_or_default("arg_start")?,
+        arg_end: fields.next_or_default("arg_end")?,
+        env_start: fields.next_or_default("env_start")?,
+        env_end: fields.next_or_default("env_end")?,
+        exit_code: fields.next_or_default("exit_code")?,
+    })
+}
+
+/// Version of the running kernel, as far as procfs parsing is concerned.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct KernelVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl KernelVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Reads `sys/kernel/osrelease`, e.g. `6.1.0-13-amd64`.
+    pub fn parse(source: &dyn ProcSource) -> Option<Self> {
+        let release = procfs::read_to_string(source, Path::new("sys/kernel/osrelease")).ok()?;
+        let mut parts = release
+            .trim()
+            .split(|c: char| !c.is_ascii_digit())
+            .map(|part| part.parse::<u32>());
+
+        Some(Self::new(
+            parts.next()?.ok()?,
+            parts.next()?.ok()?,
+            parts.next().and_then(|p| p.ok()).unwrap_or(0),
+        ))
+    }
+}
+
+/// See `task_state_array` in `fs/proc/array.c`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProcessState {
+    Running,
+    Sleeping,
+    UninterruptibleSleeping,
+    Stopped,
+    /// Since 2.6.33, before that this was reported as [`ProcessState::Stopped`].
+    TracingStop,
+    Zombie,
+    /// `X`, or `x` on some older kernels.
+    Dead,
+    Wakekill,
+    /// `W` on 2.6.0 and later.
+    Waking,
+    /// `W` before 2.6.0, the only letter whose meaning depends on the kernel version.
+    Paging,
+    Parked,
+    Idle,
+    Unknown(u8),
+}
+
+impl ProcessState {
+    pub fn parse(state: u8, kernel: Option<KernelVersion>) -> Self {
+        match state {
+            b'R' => ProcessState::Running,
+            b'S' => ProcessState::Sleeping,
+            b'D' => ProcessState::UninterruptibleSleeping,
+            b'T' => ProcessState::Stopped,
+            b't' => ProcessState::TracingStop,
+            b'Z' => ProcessState::Zombie,
+            b'X' | b'x' => ProcessState::Dead,
+            b'K' => ProcessState::Wakekill,
+            b'W' if kernel.is_some_and(|k| k < KernelVersion::new(2, 6, 0)) => ProcessState::Paging,
+            b'W' => ProcessState::Waking,
+            b'P' => ProcessState::Parked,
+            b'I' => ProcessState::Idle,
+            b => ProcessState::Unknown(b),
+        }
+    }
+
+    /// The letter the kernel uses for this state, `X` for both letters of [`ProcessState::Dead`].
+    pub fn code(&self) -> u8 {
+        match self {
+            ProcessState::Running => b'R',
+            ProcessState::Sleeping => b'S',
+            ProcessState::UninterruptibleSleeping => b'D',
+            ProcessState::Stopped => b'T',
+            ProcessState::TracingStop => b't',
+            ProcessState::Zombie => b'Z',
+            ProcessState::Dead => b'X',
+            ProcessState::Wakekill => b'K',
+            ProcessState::Waking | ProcessState::Paging => b'W',
+            ProcessState::Parked => b'P',
+            ProcessState::Idle => b'I',
+            ProcessState::Unknown(state) => *state,
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn color(&self) -> Color32 {
+        match self {
+            ProcessState::Running => Color32::GREEN,
+            ProcessState::Sleeping => Color32::LIGHT_BLUE,
+            ProcessState::UninterruptibleSleeping => Color32::from_rgb(255, 140, 0),
+            ProcessState::Stopped => Color32::YELLOW,
+            ProcessState::TracingStop => Color32::GOLD,
+            ProcessState::Zombie => Color32::RED,
+            ProcessState::Dead => Color32::DARK_RED,
+            ProcessState::Wakekill => Color32::LIGHT_RED,
+            ProcessState::Waking => Color32::LIGHT_GREEN,
+            ProcessState::Paging => Color32::KHAKI,
+            ProcessState::Parked => Color32::BROWN,
+            ProcessState::Idle => Color32::GRAY,
+            ProcessState::Unknown(_) => Color32::from_rgb(255, 0, 255),
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ProcessState::Running => "Running or runnable, waiting for a CPU",
+            ProcessState::Sleeping => "Interruptible sleep, waiting for an event",
+            ProcessState::UninterruptibleSleeping => {
+                "Uninterruptible sleep, usually waiting for I/O, can't be killed"
+            }
+            ProcessState::Stopped => "Stopped by a job control signal",
+            ProcessState::TracingStop => "Stopped by a debugger during tracing",
+            ProcessState::Zombie => "Terminated but not yet reaped by its parent",
+            ProcessState::Dead => "Dead, should never be visible",
+            ProcessState::Wakekill => "Woken up by a fatal signal",
+            ProcessState::Waking => "Being woken up",
+            ProcessState::Paging => "Paging",
+            ProcessState::Parked => "Parked kernel thread",
+            ProcessState::Idle => "Idle kernel thread",
+            ProcessState::Unknown(_) => "State not known to this version of Linux Explorer",
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn show(&self, ui: &mut Ui) {
+        ui.label(RichText::new(self.to_string()).color(self.color()))
+            .on_hover_text(self.description());
+    }
+}
+
+impl Display for ProcessState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessState::Running => write!(f, "Running"),
+            ProcessState::Sleeping => write!(f, "Sleeping"),
+            ProcessState::UninterruptibleSleeping => write!(f, "Uninterruptable Sleep"),
+            ProcessState::Stopped => write!(f, "Stopped"),
+            ProcessState::TracingStop => write!(f, "Tracing Stop"),
+            ProcessState::Zombie => write!(f, "Zombie"),
+            ProcessState::Dead => write!(f, "Dead"),
+            ProcessState::Wakekill => write!(f, "Wakekill"),
+            ProcessState::Waking => write!(f, "Waking"),
+            ProcessState::Paging => write!(f, "Paging"),
+            ProcessState::Parked => write!(f, "Parked"),
+            ProcessState::Idle => write!(f, "Idle"),
+            ProcessState::Unknown(state) => write!(f, "Unknown ({})", *state as char),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::procfs::MemorySource;
+
+    /// Everything after tcomm up to `cnswap`, which every kernel reports.
+    const BASE_FIELDS: &str = "S 1 42 42 0 -1 4194560 110 0 2 0 7 3 0 0 20 0 1 0 500 \
+        12345678 321 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0";
+    /// `exit_signal` to `exit_code`.
+    const NEWER_FIELDS: &str = "17 3 0 0 5 11 0 0 0 0 0 0 0 0 9";
+
+    pub(crate) fn stat(pid: u64, tcomm: &str) -> String {
+        format!("{} ({}) {} {}\n", pid, tcomm, BASE_FIELDS, NEWER_FIELDS)
+    }
+
+    const STATUS: &str = "Name:\tsleep\nUid:\t1000\t1000\t1000\t1000\n\
+        Gid:\t1000\t1000\t1000\t1000\nGroups:\t10 1000\nNSpid:\t42\nVmRSS:\t  1024 kB\n";
+
+    /// Every file of a process except `status`, which is up to the test.
+    pub(crate) fn process(source: MemorySource, pid: u64, tcomm: &str) -> MemorySource {
+        let dir = pid.to_string();
+        source
+            .with(format!("{}/stat", dir), stat(pid, tcomm))
+            .with(format!("{}/task/{}/stat", dir, pid), stat(pid, tcomm))
+            .with(format!("{}/cmdline", dir), "sleep\0infinity\0")
+            .with(format!("{}/cgroup", dir), "0::/user.slice\n")
+            .with(format!("{}/statm", dir), "100 50 10 5 0 20 0\n")
+            .with(
+                format!("{}/smaps_rollup", dir),
+                "Rss: 200 kB\nPss: 100 kB\n",
+            )
+            .with_link(format!("{}/ns/pid", dir), "pid:[4026531836]")
+    }
+
+    fn parse(contents: &str) -> Result<ProcessStats> {
+        let source = MemorySource::new().with("1/stat", contents);
+        parse_stat_file(&source, Path::new("1/stat"), None)
+    }
+
+    #[test]
+    fn stat_fields() {
+        let stats = parse(&stat(42, "sleep")).unwrap();
+        assert_eq!(stats.pid, 42);
+        assert_eq!(stats.tcomm, "sleep");
+        assert_eq!(stats.state, ProcessState::Sleeping);
+        assert_eq!(stats.ppid, 1);
+        assert_eq!(stats.tpgid, -1);
+        assert_eq!(stats.utime, 7);
+        assert_eq!(stats.stime, 3);
+        assert_eq!(stats.starttime, 500);
+        assert_eq!(stats.rss, 321);
+        assert_eq!(stats.rsslim, u64::MAX);
+        assert_eq!(stats.exit_signal, 17);
+        assert_eq!(stats.processor, 3);
+        assert_eq!(stats.exit_code, 9);
+    }
+
+    #[test]
+    fn tcomm_with_spaces_and_parentheses() {
+        let stats = parse(&stat(7, "a) (b c) S 1")).unwrap();
+        assert_eq!(stats.tcomm, "a) (b c) S 1");
+        assert_eq!(stats.state, ProcessState::Sleeping);
+        assert_eq!(stats.ppid, 1);
+        assert_eq!(stats.exit_code, 9);
+    }
+
+    #[test]
+    fn missing_newer_fields() {
+        let stats = parse(&format!("1 (init) {}\n", BASE_FIELDS)).unwrap();
+        assert_eq!(stats.cnswap, 0);
+        assert_eq!(stats.exit_signal, 0);
+        assert_eq!(stats.processor, 0);
+        assert_eq!(stats.exit_code, 0);
+    }
+
+    #[test]
+    fn missing_base_field() {
+        let contents = format!("1 (init) {}\n", BASE_FIELDS.rsplit_once(' ').unwrap().0);
+        let Err(err) = parse(&contents) else {
+            panic!("parsed a stat file without cnswap");
+        };
+        assert_eq!(err.kind, ErrorKind::Malformed("missing cnswap".to_string()));
+        assert_eq!(err.path, Path::new("1/stat"));
+    }
+
+    #[test]
+    fn malformed_stat() {
+        for (contents, reason) in [
+            ("1 init S 0", "tcomm not in parentheses"),
+            ("1 )init( S 0", "tcomm not in parentheses"),
+            ("x (init) S 0", "invalid pid"),
+            ("1 (init) SS 0", "invalid state SS"),
+            ("1 (init) S x", "invalid ppid: x"),
+        ] {
+            let Err(err) = parse(contents) else {
+                panic!("parsed {:?}", contents);
+            };
+            assert_eq!(
+                err.kind,
+                ErrorKind::Malformed(reason.to_string()),
+                "{}",
+                contents
+            );
+        }
+    }
+
+    #[test]
+    fn state_depends_on_kernel() {
+        let old = format!("1 (init) W {}", &BASE_FIELDS[2..]);
+        let source = MemorySource::new().with("1/stat", old);
+        let path = Path::new("1/stat");
+
+        let stats = parse_stat_file(&source, path, Some(KernelVersion::new(2, 4, 0))).unwrap();
+        assert_eq!(stats.state, ProcessState::Paging);
+        let stats = parse_stat_file(&source, path, Some(KernelVersion::new(3, 10, 0))).unwrap();
+        assert_eq!(stats.state, ProcessState::Waking);
+    }
+
+    #[test]
+    fn state_codes() {
+        for code in *b"RSDTtZXKWPI" {
+            assert_eq!(ProcessState::parse(code, None).code(), code);
+        }
+        assert_eq!(ProcessState::parse(b'x', None), ProcessState::Dead);
+        assert_eq!(ProcessState::parse(b'x', None).code(), b'X');
+        assert_eq!(ProcessState::parse(b'?', None), ProcessState::Unknown(b'?'));
+    }
+
+    #[test]
+    fn scan() {
+        let source = process(MemorySource::new(), 42, "sleep")
+            .with("42/status", STATUS)
+            .with("self", "")
+            .with("sys/kernel/osrelease", "6.1.0-13-amd64\n");
+        let scan = parse_processes(&source, &ScanOptions::all()).unwrap();
+
+        assert!(scan.skipped.is_empty());
+        assert_eq!(scan.partial(), 0);
+        let [process] = &scan.processes[..] else {
+            panic!("expected one process, got {}", scan.processes.len());
+        };
+        assert_eq!(process.pid, 42);
+        assert_eq!(process.cmdline, "sleep infinity");
+        assert_eq!(process.credentials.as_ref().unwrap().uid.real, 1000);
+        assert_eq!(process.credentials.as_ref().unwrap().groups, [10, 1000]);
+        assert_eq!(process.ns_pids, [42]);
+        assert_eq!(process.memory.vm_rss, Some(1024 * 1024));
+        assert_eq!(process.threads.len(), 1);
+        assert_eq!(process.cgroup(), Some("/user.slice"));
+    }
+
+    #[test]
+    fn vanished_process() {
+        // The directory was listed, but the process exited before its stat was read.
+        let source = process(MemorySource::new(), 1, "init")
+            .with("1/status", STATUS)
+            .with("2/cmdline", "");
+        let scan = parse_processes(&source, &ScanOptions::all()).unwrap();
+
+        assert_eq!(scan.processes.len(), 1);
+        let [skipped] = &scan.skipped[..] else {
+            panic!("expected one skipped process, got {}", scan.skipped.len());
+        };
+        assert_eq!(skipped.pid, 2);
+        assert_eq!(skipped.error.kind, ErrorKind::NotFound);
+        assert_eq!(skipped.error.path, Path::new("2/stat"));
+    }
+
+    #[test]
+    fn rollup_on_demand() {
+        let source = process(process(MemorySource::new(), 1, "init"), 2, "sleep")
+            .with("1/status", STATUS)
+            .with("2/status", STATUS);
+        let pss = |options: &ScanOptions| -> Vec<Option<u64>> {
+            let scan = parse_processes(&source, options).unwrap();
+            assert_eq!(scan.partial(), 0);
+            scan.processes.iter().map(|p| p.memory.pss()).collect()
+        };
+
+        assert_eq!(pss(&ScanOptions::default()), [None, None]);
+        let selected = ScanOptions {
+            rollup: false,
+            rollup_pid: Some(2),
+        };
+        assert_eq!(pss(&selected), [None, Some(100 << 10)]);
+        assert_eq!(pss(&ScanOptions::all()), [Some(100 << 10); 2]);
+
+        assert!(selected.reads_more_than(&ScanOptions::default()));
+        assert!(ScanOptions::all().reads_more_than(&selected));
+        assert!(!selected.reads_more_than(&ScanOptions::all()));
+        assert!(!ScanOptions::default().reads_more_than(&selected));
+
+        // Recordings only have it where it was needed.
+        let recorded = process(MemorySource::new(), 1, "init")
+            .with("1/status", STATUS)
+            .with_error("1/smaps_rollup", io::ErrorKind::NotFound);
+        let scan = parse_processes(&recorded, &ScanOptions::all()).unwrap();
+        assert_eq!(scan.partial(), 0);
+        assert_eq!(scan.processes[0].memory.pss(), None);
+    }
+
+    #[test]
+    fn unreadable_status() {
+        let source = process(MemorySource::new(), 1, "init")
+            .with_error("1/status", io::ErrorKind::PermissionDenied);
+        let scan = parse_processes(&source, &ScanOptions::all()).unwrap();
+
+        assert!(scan.skipped.is_empty());
+        assert_eq!(scan.partial(), 1);
+        let process = &scan.processes[0];
+        assert!(process.credentials.is_none());
+        assert!(process.ns_pids.is_empty());
+        let [error] = &process.errors[..] else {
+            panic!("expected one error, got {:?}", process.errors);
+        };
+        assert_eq!(error.kind, ErrorKind::PermissionDenied);
+        assert_eq!(error.path, Path::new("1/status"));
+    }
+
+    #[test]
+    fn command_counts() {
+        let source = ["bash", "nginx", "nginx", "cron", "nginx", "bash"]
+            .iter()
+            .enumerate()
+            .fold(MemorySource::new(), |source, (i, tcomm)| {
+                process(source, i as u64 + 1, tcomm)
+            });
+        let scan = parse_processes(&source, &ScanOptions::all()).unwrap();
+
+        let mut counts = CommandCounts::default();
+        for process in &scan.processes {
+            counts.add(process);
+        }
+        let commands = counts.sorted();
+        assert_eq!(
+            commands,
+            [
+                ("nginx".to_string(), 3),
+                ("bash".to_string(), 2),
+                ("cron".to_string(), 1)
+            ]
+        );
+        assert_eq!(command_summary(&commands), "nginx ×3, bash ×2, cron");
+        assert_eq!(command_summary(&[]), "");
+    }
+
+    #[test]
+    fn thread_ns_pids() {
+        const IDS: &str = "Uid:\t0\t0\t0\t0\nGid:\t0\t0\t0\t0\n";
+        // pid 42 is 1 in its container, its second thread 43 is 7 there.
+        let source = process(MemorySource::new(), 42, "server")
+            .with("42/status", format!("{}NSpid:\t42\t1\n", IDS))
+            .with("42/task/42/status", "NSpid:\t42\t1\n")
+            .with("42/task/43/stat", stat(43, "worker"))
+            .with("42/task/43/status", "NSpid:\t43\t7\n")
+            .with("42/task/44/stat", stat(44, "exiting"));
+        let scan = parse_processes(&source, &ScanOptions::all()).unwrap();
+        let server = &scan.processes[0];
+
+        let ns_pids: Vec<u64> = server
+            .threads
+            .iter()
+            .map(|thread| thread.as_process(server).ns_pid())
+            .collect();
+        // 44 exited before its status was read, its host tid is all there is.
+        assert_eq!(ns_pids, [1, 7, 44]);
+        assert!(server.errors.is_empty());
+
+        // Outside of containers the status of threads isn't read at all.
+        let source = process(MemorySource::new(), 42, "server")
+            .with("42/status", format!("{}NSpid:\t42\n", IDS))
+            .with("42/task/43/stat", stat(43, "worker"))
+            .with_error("42/task/43/status", io::ErrorKind::PermissionDenied);
+        let server = &parse_processes(&source, &ScanOptions::all())
+            .unwrap()
+            .processes[0];
+        assert_eq!(server.threads[1].as_process(server).ns_pid(), 43);
+        assert!(server.errors.is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ffi::OsString,
+    io,
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+};
+
+use crate::error::{Error, Result};
+
+/// Something that looks like a procfs mount.
+///
+/// All paths are relative to the root of the procfs, e.g. `1/stat` instead of `/proc/1/stat`.
+pub trait ProcSource: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Names of all entries in the directory at `path`.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>>;
+
+    /// Target of the symlink at `path`, e.g. for `1/fd/0`.
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Whether `path` is a directory after following symlinks.
+    fn is_dir(&self, path: &Path) -> io::Result<bool>;
+
+    /// Whether `path` is a character or block device after following symlinks.
+    fn is_device(&self, path: &Path) -> io::Result<bool>;
+
+    /// Where `path` is in the host's own procfs, for what only works on an open file like
+    /// ioctls. `None` for recordings and copies, which can't be asked.
+    fn host_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// A procfs living somewhere on the filesystem, either the real one at `/proc` or a captured
+/// copy of it.
+pub struct DirSource {
+    root: PathBuf,
+}
+
+impl DirSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Uses `PROC_ROOT` if it is set, `/proc` otherwise.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var_os("PROC_ROOT").unwrap_or_else(|| "/proc".into()))
+    }
+}
+
+impl Default for DirSource {
+    fn default() -> Self {
+        Self::new("/proc")
+    }
+}
+
+impl ProcSource for DirSource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(self.root.join(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        std::fs::read_dir(self.root.join(path))?
+            .map(|entry| entry.map(|e| e.file_name()))
+            .collect()
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::read_link(self.root.join(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> io::Result<bool> {
+        Ok(std::fs::metadata(self.root.join(path))?.is_dir())
+    }
+
+    fn is_device(&self, path: &Path) -> io::Result<bool> {
+        let file_type = std::fs::metadata(self.root.join(path))?.file_type();
+        Ok(file_type.is_char_device() || file_type.is_block_device())
+    }
+
+    fn host_path(&self, path: &Path) -> Option<PathBuf> {
+        (self.root == Path::new("/proc")).then(|| self.root.join(path))
+    }
+}
+
+/// An in-memory procfs, filled file by file.
+#[derive(Default)]
+pub struct MemorySource {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    links: BTreeMap<PathBuf, PathBuf>,
+    /// Files that exist but fail to read, like `status` of a process of another user.
+    errors: BTreeMap<PathBuf, io::ErrorKind>,
+    /// Files or link targets that are devices, like `1/fd/0` for a terminal.
+    devices: BTreeSet<PathBuf>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) {
+        self.files.insert(path.into(), contents.into());
+    }
+
+    pub fn with(mut self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) -> Self {
+        self.insert(path, contents);
+        self
+    }
+
+    pub fn insert_link(&mut self, path: impl Into<PathBuf>, target: impl Into<PathBuf>) {
+        self.links.insert(path.into(), target.into());
+    }
+
+    pub fn with_link(mut self, path: impl Into<PathBuf>, target: impl Into<PathBuf>) -> Self {
+        self.insert_link(path, target);
+        self
+    }
+
+    pub fn insert_error(&mut self, path: impl Into<PathBuf>, kind: io::ErrorKind) {
+        self.errors.insert(path.into(), kind);
+    }
+
+    pub fn with_error(mut self, path: impl Into<PathBuf>, kind: io::ErrorKind) -> Self {
+        self.insert_error(path, kind);
+        self
+    }
+
+    pub fn with_device(mut self, path: impl Into<PathBuf>) -> Self {
+        self.devices.insert(path.into());
+        self
+    }
+}
+
+impl ProcSource for MemorySource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        if let Some(kind) = self.errors.get(path) {
+            return Err((*kind).into());
+        }
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let mut names: Vec<OsString> = self
+            .files
+            .keys()
+            .chain(self.links.keys())
+            .chain(self.errors.keys())
+            .filter_map(|file| file.strip_prefix(path).ok())
+            .filter_map(|rest| rest.components().next())
+            .map(|c| c.as_os_str().to_owned())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        if names.is_empty() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        Ok(names)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        if let Some(kind) = self.errors.get(path) {
+            return Err((*kind).into());
+        }
+        self.links
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    /// Directories only exist implicitly, as the parents of files.
+    fn is_dir(&self, path: &Path) -> io::Result<bool> {
+        Ok(self.read_dir(path).is_ok())
+    }
+
+    fn is_device(&self, path: &Path) -> io::Result<bool> {
+        Ok(self.devices.contains(path))
+    }
+}
+
+/// Reads `path` from `source`, remembering the path in case of an error.
+pub fn read(source: &dyn ProcSource, path: &Path) -> Result<Vec<u8>> {
+    source.read(path).map_err(|err| Error::io(path, err))
+}
+
+pub fn read_to_string(source: &dyn ProcSource, path: &Path) -> Result<String> {
+    let bytes = read(source, path)?;
+    String::from_utf8(bytes).map_err(|err| Error::malformed(path, err.to_string()))
+}
+
+pub fn read_dir(source: &dyn ProcSource, path: &Path) -> Result<Vec<OsString>> {
+    source.read_dir(path).map_err(|err| Error::io(path, err))
+}
+
+pub fn read_link(source: &dyn ProcSource, path: &Path) -> Result<PathBuf> {
+    source.read_link(path).map_err(|err| Error::io(path, err))
+}
+
+/// Files made of `Key: value` lines, like `/proc/[pid]/status` or `/proc/meminfo`.
+#[derive(Clone, Default)]
+pub struct KeyValues {
+    values: HashMap<String, String>,
+}
+
+impl KeyValues {
+    pub fn read(source: &dyn ProcSource, path: &Path) -> Result<Self> {
+        Ok(Self::parse(&read_to_string(source, path)?))
+    }
+
+    /// Lines that don't look like `Key: value` are skipped.
+    pub fn parse(contents: &str) -> Self {
+        let values = contents
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .filter(|(key, _)| !key.contains(char::is_whitespace))
+            .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+            .collect();
+
+        Self { values }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Values like `1234 kB`, in bytes.
+    pub fn kb(&self, key: &str) -> Option<u64> {
+        let value = self.get(key)?;
+        let kb = value.strip_suffix("kB").unwrap_or(value).trim();
+        kb.parse::<u64>().ok().map(|kb| kb * 1024)
+    }
+}
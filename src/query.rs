@@ -0,0 +1,852 @@
+//! Search queries like `state:D user:root rss>500M cmd~/java.*kafka/ (ppid:1 OR NOT comm:kworker)`.
+//!
+//! Terms next to each other are combined with AND. Words without a field are matched against
+//! pid, cmdline and tcomm like a plain search.
+
+use std::{cmp::Ordering, fmt::Display};
+
+use regex::Regex;
+
+use crate::{
+    process::{Process, ProcessState},
+    users::{group_name, user_name},
+};
+
+pub const HELP: &str = "\
+Plain words match pid, command and tcomm.
+
+field:value    contains (state: takes a letter or name)
+field=value    equals
+field~/regex/  matches regex
+field^/path    is the cgroup path or below it
+field>value    also <, >=, <=
+
+Fields: pid ppid nspid user uid group gid state cmd comm cpu rss pss uss
+swap threads nice cgroup cgroup2 container pod unit ns, user and group are
+names, uid and gid numbers, cgroup2 is the v2 path, container and pod take
+ids, unit systemd units and machines, ns namespaces like net:[4026531840]
+Sizes: 500M, 2G, ...
+Combine with AND, OR, NOT, ! and parentheses, AND is implied.
+Quote text with spaces or special characters: cmd:\"a b\"";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    /// Byte offset into the query.
+    pub position: usize,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: {}", self.position + 1, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Default, Clone)]
+pub struct Query {
+    /// `None` for an empty query, which matches everything.
+    expr: Option<Expr>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+            end: input.len(),
+            depth: 0,
+        };
+
+        if tokens.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(Self { expr: Some(expr) }),
+            Some(token) => Err(QueryError::new(token.start, "unexpected ')'")),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expr.is_none()
+    }
+
+    /// Narrows the query down to processes started by or running as `uid`.
+    pub fn owned_by(self, uid: u32) -> Self {
+        let owner = Expr::Owner(uid);
+        Self {
+            expr: Some(match self.expr {
+                Some(expr) => Expr::And(Box::new(expr), Box::new(owner)),
+                None => owner,
+            }),
+        }
+    }
+
+    pub fn matches(&self, process: &Process) -> bool {
+        match &self.expr {
+            Some(expr) => expr.matches(process),
+            None => true,
+        }
+    }
+
+    /// Whether a field compares values from `smaps_rollup`, like `pss>100M`.
+    pub fn needs_rollup(&self) -> bool {
+        self.expr.as_ref().is_some_and(Expr::needs_rollup)
+    }
+}
+
+/// `field=value` with the value quoted if the tokenizer would split it.
+pub fn equals(field: &str, value: &str) -> String {
+    term(field, "=", value)
+}
+
+/// `field^path`, the cgroup `path` and everything below it.
+pub fn within(field: &str, path: &str) -> String {
+    term(field, "^", path)
+}
+
+fn term(field: &str, operator: &str, value: &str) -> String {
+    if value.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        format!("{}{}\"{}\"", field, operator, value)
+    } else {
+        format!("{}{}{}", field, operator, value)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Text(String),
+    Predicate(Field, Predicate),
+    /// Real or effective uid, only built by [`Query::owned_by`].
+    Owner(u32),
+}
+
+impl Expr {
+    fn matches(&self, process: &Process) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(process) && b.matches(process),
+            Expr::Or(a, b) => a.matches(process) || b.matches(process),
+            Expr::Not(expr) => !expr.matches(process),
+            Expr::Text(text) => process.contains(text),
+            Expr::Predicate(field, predicate) => predicate.matches(*field, process),
+            Expr::Owner(uid) => process
+                .credentials
+                .as_ref()
+                .is_some_and(|c| c.uid.real == *uid || c.uid.effective == *uid),
+        }
+    }
+
+    fn needs_rollup(&self) -> bool {
+        match self {
+            Expr::And(a, b) | Expr::Or(a, b) => a.needs_rollup() || b.needs_rollup(),
+            Expr::Not(expr) => expr.needs_rollup(),
+            Expr::Predicate(field, _) => matches!(field, Field::Pss | Field::Uss | Field::Swap),
+            Expr::Text(_) | Expr::Owner(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Pid,
+    Ppid,
+    NsPid,
+    User,
+    Uid,
+    Group,
+    Gid,
+    State,
+    Cmd,
+    Comm,
+    Cpu,
+    Rss,
+    Pss,
+    Uss,
+    Swap,
+    Threads,
+    Nice,
+    Cgroup,
+    Cgroup2,
+    Container,
+    Pod,
+    Unit,
+    Ns,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "pid" => Field::Pid,
+            "ppid" => Field::Ppid,
+            "nspid" => Field::NsPid,
+            "user" => Field::User,
+            "uid" => Field::Uid,
+            "group" => Field::Group,
+            "gid" => Field::Gid,
+            "state" => Field::State,
+            "cmd" | "cmdline" => Field::Cmd,
+            "comm" | "tcomm" => Field::Comm,
+            "cpu" => Field::Cpu,
+            "rss" => Field::Rss,
+            "pss" => Field::Pss,
+            "uss" => Field::Uss,
+            "swap" => Field::Swap,
+            "threads" => Field::Threads,
+            "nice" => Field::Nice,
+            "cgroup" => Field::Cgroup,
+            "cgroup2" => Field::Cgroup2,
+            "container" => Field::Container,
+            "pod" => Field::Pod,
+            "unit" => Field::Unit,
+            "ns" => Field::Ns,
+            _ => return None,
+        })
+    }
+
+    fn is_numeric(&self) -> bool {
+        !matches!(
+            self,
+            Field::User
+                | Field::Group
+                | Field::State
+                | Field::Cmd
+                | Field::Comm
+                | Field::Cgroup
+                | Field::Cgroup2
+                | Field::Container
+                | Field::Pod
+                | Field::Unit
+                | Field::Ns
+        )
+    }
+
+    fn number(&self, process: &Process) -> Option<f64> {
+        Some(match self {
+            Field::Pid => process.pid as f64,
+            Field::Ppid => process.stats.ppid as f64,
+            Field::NsPid => process.ns_pid() as f64,
+            Field::Uid => process.uid()? as f64,
+            Field::Gid => process.gid()? as f64,
+            Field::Cpu => process.cpu_usage?,
+            Field::Rss => process.stats.rss_bytes() as f64,
+            Field::Pss => process.memory.pss()? as f64,
+            Field::Uss => process.memory.uss()? as f64,
+            Field::Swap => process.memory.swap()? as f64,
+            Field::Threads => process.stats.num_threads as f64,
+            Field::Nice => process.stats.nice as f64,
+            _ => return None,
+        })
+    }
+
+    fn text(&self, process: &Process) -> Option<String> {
+        Some(match self {
+            Field::User => user_name(process.uid()?),
+            Field::Group => group_name(process.gid()?),
+            Field::State => process.stats.state.to_string(),
+            Field::Cmd => process.cmdline.clone(),
+            Field::Comm => process.stats.tcomm.clone(),
+            Field::Cgroup => process.cgroup()?.to_string(),
+            Field::Cgroup2 => process.cgroups.as_ref()?.unified()?.to_string(),
+            Field::Container => process.workload()?.container_id()?.to_string(),
+            Field::Pod => process.workload()?.pod_uid()?.to_string(),
+            Field::Unit => process.workload()?.unit()?.to_string(),
+            Field::Ns => process.namespaces.as_ref()?.text(),
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Predicate {
+    Contains(String),
+    Equals(String),
+    Regex(Regex),
+    /// The path or one below it, without the trailing slash.
+    Within(String),
+    Compare(Ordering, bool, f64),
+    State(u8),
+}
+
+impl Predicate {
+    fn matches(&self, field: Field, process: &Process) -> bool {
+        match self {
+            Predicate::State(code) => process.stats.state.code() == *code,
+            Predicate::Compare(ordering, or_equal, value) => {
+                field.number(process).is_some_and(|number| {
+                    let actual = number.total_cmp(value);
+                    actual == *ordering || (*or_equal && actual == Ordering::Equal)
+                })
+            }
+            Predicate::Contains(text) => field.text(process).is_some_and(|t| {
+                if field == Field::State {
+                    t.to_lowercase().contains(&text.to_lowercase())
+                } else {
+                    t.contains(text.as_str())
+                }
+            }),
+            Predicate::Equals(text) => field.text(process).is_some_and(|t| t == *text),
+            Predicate::Regex(regex) => field.text(process).is_some_and(|t| regex.is_match(&t)),
+            Predicate::Within(path) => field.text(process).is_some_and(|t| {
+                t == *path
+                    || t.strip_prefix(path.as_str())
+                        .is_some_and(|t| t.starts_with('/'))
+            }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// Words that start with a quote are always plain text, never keywords or fields.
+    Word {
+        text: String,
+        quoted: bool,
+    },
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token {
+                    kind: TokenKind::LParen,
+                    start,
+                });
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token {
+                    kind: TokenKind::RParen,
+                    start,
+                });
+            }
+            '!' => {
+                chars.next();
+                tokens.push(Token {
+                    kind: TokenKind::Not,
+                    start,
+                });
+            }
+            _ => {
+                let mut text = String::new();
+                let mut quoted = false;
+
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+
+                    if c == '"' {
+                        quoted |= text.is_empty();
+                        loop {
+                            match chars.next() {
+                                Some((_, '"')) => break,
+                                Some((_, c)) => text.push(c),
+                                None => return Err(QueryError::new(i, "unterminated quote")),
+                            }
+                        }
+                    } else if c == '/' && text.ends_with('~') {
+                        // Regexes keep their slashes so the predicate parser can find them.
+                        text.push('/');
+                        loop {
+                            match chars.next() {
+                                Some((_, '\\')) if chars.peek().map(|(_, c)| *c) == Some('/') => {
+                                    chars.next();
+                                    text.push('/');
+                                }
+                                Some((_, '/')) => break,
+                                Some((_, c)) => text.push(c),
+                                None => return Err(QueryError::new(i, "unterminated regex")),
+                            }
+                        }
+                        text.push('/');
+                    } else {
+                        text.push(c);
+                    }
+                }
+
+                let kind = match text.as_str() {
+                    "AND" if !quoted => TokenKind::And,
+                    "OR" if !quoted => TokenKind::Or,
+                    "NOT" if !quoted => TokenKind::Not,
+                    _ => TokenKind::Word { text, quoted },
+                };
+                tokens.push(Token { kind, start });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Deeper `NOT`s and parentheses are refused before the recursion overflows the stack.
+const MAX_DEPTH: usize = 100;
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    /// Where errors at the end of the input point to.
+    end: usize,
+    /// `NOT`s and parentheses around the current token.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    /// Parses something inside the `NOT` or `(` at `start`.
+    fn nested(
+        &mut self,
+        start: usize,
+        parse: impl FnOnce(&mut Self) -> Result<Expr, QueryError>,
+    ) -> Result<Expr, QueryError> {
+        if self.depth == MAX_DEPTH {
+            return Err(QueryError::new(start, "nested too deeply"));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.and()?;
+        while self.peek().is_some_and(|t| t.kind == TokenKind::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.not()?;
+        loop {
+            match self.peek().map(|t| &t.kind) {
+                Some(TokenKind::And) => {
+                    self.next();
+                }
+                Some(TokenKind::Word { .. } | TokenKind::Not | TokenKind::LParen) => {}
+                _ => return Ok(expr),
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, QueryError> {
+        if let Some(token) = self.peek().filter(|t| t.kind == TokenKind::Not) {
+            self.next();
+            let expr = self.nested(token.start, Self::not)?;
+            return Ok(Expr::Not(Box::new(expr)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        let Some(token) = self.next() else {
+            return Err(QueryError::new(self.end, "expected a search term"));
+        };
+
+        match &token.kind {
+            TokenKind::LParen => {
+                let expr = self.nested(token.start, Self::or)?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => Ok(expr),
+                    Some(token) => Err(QueryError::new(token.start, "expected ')'")),
+                    None => Err(QueryError::new(self.end, "missing ')'")),
+                }
+            }
+            TokenKind::Word { text, quoted } => parse_term(text, *quoted, token.start),
+            TokenKind::RParen => Err(QueryError::new(token.start, "unexpected ')'")),
+            TokenKind::And | TokenKind::Or => {
+                Err(QueryError::new(token.start, "expected a search term"))
+            }
+            TokenKind::Not => unreachable!("handled by not()"),
+        }
+    }
+}
+
+const OPERATORS: [&str; 8] = [">=", "<=", ":", "=", "~", "^", ">", "<"];
+
+fn parse_term(text: &str, quoted: bool, start: usize) -> Result<Expr, QueryError> {
+    // Field names start with a letter, like `cgroup2`.
+    let name_len = match text.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => text
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(text.len()),
+        _ => 0,
+    };
+    let operator = OPERATORS
+        .iter()
+        .find(|op| text[name_len..].starts_with(*op));
+
+    let (Some(operator), false, true) = (operator, quoted, name_len > 0) else {
+        return Ok(Expr::Text(text.to_string()));
+    };
+
+    let name = &text[..name_len];
+    let value = &text[name_len + operator.len()..];
+    let value_start = start + name_len + operator.len();
+
+    let field = Field::parse(name)
+        .ok_or_else(|| QueryError::new(start, format!("unknown field '{}'", name)))?;
+    if value.is_empty() {
+        return Err(QueryError::new(value_start, "missing value"));
+    }
+
+    let predicate = match (*operator, field.is_numeric()) {
+        // Letters with the same meaning, like `x` and `X`, match the same processes.
+        (":" | "=", false) if field == Field::State && value.len() == 1 => {
+            Predicate::State(ProcessState::parse(value.as_bytes()[0], None).code())
+        }
+        (":", false) => Predicate::Contains(value.to_string()),
+        ("=", false) => Predicate::Equals(value.to_string()),
+        ("^", _) if matches!(field, Field::Cgroup | Field::Cgroup2) => {
+            // The root is `/` but `//` is no path below it.
+            Predicate::Within(value.trim_end_matches('/').to_string())
+        }
+        ("^", _) => {
+            return Err(QueryError::new(
+                start,
+                format!(
+                    "'{}' is no cgroup path, ^ only works on cgroup and cgroup2",
+                    name
+                ),
+            ))
+        }
+        ("~", false) => {
+            let pattern = value
+                .strip_prefix('/')
+                .and_then(|v| v.strip_suffix('/'))
+                .unwrap_or(value);
+            let regex = Regex::new(pattern).map_err(|err| {
+                // Syntax errors come with a multi-line drawing of where they are.
+                let err = err.to_string();
+                let reason = err
+                    .lines()
+                    .last()
+                    .unwrap_or_default()
+                    .trim_start_matches("error: ");
+                QueryError::new(value_start, format!("invalid regex: {}", reason))
+            })?;
+            Predicate::Regex(regex)
+        }
+        (_, false) => {
+            return Err(QueryError::new(
+                start,
+                format!("'{}' can't be compared with {}", name, operator),
+            ))
+        }
+        ("~", true) => {
+            return Err(QueryError::new(
+                start,
+                format!("'{}' is a number, regexes only work on text", name),
+            ))
+        }
+        (operator, true) => {
+            let number = parse_number(field, value).ok_or_else(|| {
+                QueryError::new(value_start, format!("invalid number '{}'", value))
+            })?;
+            let (ordering, or_equal) = match operator {
+                ">" => (Ordering::Greater, false),
+                ">=" => (Ordering::Greater, true),
+                "<" => (Ordering::Less, false),
+                "<=" => (Ordering::Less, true),
+                _ => (Ordering::Equal, true),
+            };
+            Predicate::Compare(ordering, or_equal, number)
+        }
+    };
+
+    Ok(Expr::Predicate(field, predicate))
+}
+
+/// Sizes may have a binary unit suffix, e.g. `500M`.
+fn parse_number(field: Field, value: &str) -> Option<f64> {
+    if !matches!(field, Field::Rss | Field::Pss | Field::Uss | Field::Swap) {
+        return value.trim_end_matches('%').parse().ok();
+    }
+
+    let value = value.trim_end_matches(['B', 'b']).trim_end_matches('i');
+    let (number, multiplier) = match value.char_indices().last()? {
+        (i, 'K' | 'k') => (&value[..i], 1u64 << 10),
+        (i, 'M' | 'm') => (&value[..i], 1 << 20),
+        (i, 'G' | 'g') => (&value[..i], 1 << 30),
+        (i, 'T' | 't') => (&value[..i], 1 << 40),
+        _ => (value, 1),
+    };
+
+    number
+        .parse::<f64>()
+        .ok()
+        .map(|number| number * multiplier as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        process::{parse_processes, tests, ScanOptions},
+        procfs::MemorySource,
+    };
+
+    /// `sleep infinity` as pid 42 of uid 4242, which has no name anywhere.
+    fn sleep() -> Process {
+        let source = tests::process(MemorySource::new(), 42, "sleep").with(
+            "42/status",
+            "Uid:\t4242\t4242\t4242\t4242\nGid:\t4242\t4242\t4242\t4242\n",
+        );
+        let mut process = parse_processes(&source, &ScanOptions::all())
+            .unwrap()
+            .processes
+            .remove(0);
+        process.cpu_usage = Some(12.5);
+        process
+    }
+
+    #[test]
+    fn matches() {
+        let process = sleep();
+        for (query, expected) in [
+            ("", true),
+            ("sleep", true),
+            ("42", true),
+            ("nothing", false),
+            ("\"pid=42\"", false),
+            ("pid=42", true),
+            ("pid:42", true),
+            ("pid=41", false),
+            ("ppid=1", true),
+            ("pid>41 pid<43", true),
+            ("pid>=42 AND pid<=42", true),
+            ("pid>42", false),
+            ("threads=1 nice=0", true),
+            ("uid=4242 gid=4242", true),
+            ("user=4242 group:424", true),
+            ("cpu>10", true),
+            ("cpu>10%", true),
+            ("cpu>20", false),
+            ("rss>100K rss<100M", true),
+            ("rss>1T", false),
+            ("pss=100K", true),
+            ("pss>=100KiB uss<1MB", true),
+            ("state:S", true),
+            ("state=S", true),
+            ("state:sleep", true),
+            ("state:R", false),
+            ("cmd:infinity", true),
+            ("cmd=sleep", false),
+            ("cmd=\"sleep infinity\"", true),
+            ("comm=sleep", true),
+            ("comm~/^sl(e+)p$/", true),
+            ("comm~/^leep/", false),
+            ("cmd~/a\\/b/", false),
+            ("cgroup2=/user.slice", true),
+            ("cgroup:user", true),
+            ("cgroup2^/user.slice", true),
+            ("cgroup^/", true),
+            ("ns:4026531836", true),
+            ("NOT comm:sleep", false),
+            ("!comm:sleep", false),
+            ("!!comm:sleep", true),
+            // AND binds tighter than OR, NOT tighter than both.
+            ("pid=1 OR pid=42 pid=7", false),
+            ("pid=1 OR pid=42 ppid=1", true),
+            ("(pid=1 OR pid=42) ppid=1", true),
+            ("(pid=1 OR pid=42) ppid=7", false),
+            ("NOT pid=1 AND pid=42", true),
+            ("NOT (pid=1 OR pid=42)", false),
+            ("pid=7 OR NOT pid=1", true),
+        ] {
+            let parsed = Query::parse(query).unwrap_or_else(|err| panic!("{}: {}", query, err));
+            assert_eq!(parsed.matches(&process), expected, "{}", query);
+        }
+    }
+
+    #[test]
+    fn dead_letters() {
+        let mut process = sleep();
+        process.stats.state = ProcessState::parse(b'x', None);
+        for query in ["state:x", "state:X", "state=x", "state:dead"] {
+            assert!(Query::parse(query).unwrap().matches(&process), "{}", query);
+        }
+    }
+
+    #[test]
+    fn owned_by() {
+        let process = sleep();
+        assert!(Query::default().owned_by(4242).matches(&process));
+        assert!(!Query::default().owned_by(0).matches(&process));
+        let query = Query::parse("comm:sleep").unwrap();
+        assert!(query.clone().owned_by(4242).matches(&process));
+        assert!(!query.owned_by(0).matches(&process));
+    }
+
+    #[test]
+    fn errors() {
+        for (query, position, message) in [
+            ("foo:bar", 0, "unknown field 'foo'"),
+            ("sleep size>1", 6, "unknown field 'size'"),
+            ("pid=", 4, "missing value"),
+            ("rss>12X", 4, "invalid number '12X'"),
+            ("rss>M", 4, "invalid number 'M'"),
+            ("pss<1.5.0G", 4, "invalid number '1.5.0G'"),
+            ("pid>1K", 4, "invalid number '1K'"),
+            ("cmd>3", 0, "'cmd' can't be compared with >"),
+            ("state<=R", 0, "'state' can't be compared with <="),
+            ("pid~/1/", 0, "'pid' is a number, regexes only work on text"),
+            (
+                "cmd^/usr",
+                0,
+                "'cmd' is no cgroup path, ^ only works on cgroup and cgroup2",
+            ),
+            (
+                "pid^1",
+                0,
+                "'pid' is no cgroup path, ^ only works on cgroup and cgroup2",
+            ),
+            ("cmd:\"abc", 4, "unterminated quote"),
+            ("cmd~/abc", 4, "unterminated regex"),
+            ("(pid:1", 6, "missing ')'"),
+            ("pid:1)", 5, "unexpected ')'"),
+            ("AND", 0, "expected a search term"),
+            ("pid:1 OR", 8, "expected a search term"),
+            ("NOT", 3, "expected a search term"),
+            ("()", 1, "unexpected ')'"),
+        ] {
+            let err = Query::parse(query).expect_err(query);
+            assert_eq!(err, QueryError::new(position, message), "{}", query);
+        }
+
+        // Would overflow the stack otherwise.
+        let nots = "!".repeat(100_000) + "sleep";
+        assert_eq!(
+            Query::parse(&nots).unwrap_err(),
+            QueryError::new(MAX_DEPTH, "nested too deeply")
+        );
+        let parens = "(".repeat(100_000) + "sleep";
+        assert_eq!(
+            Query::parse(&parens).unwrap_err(),
+            QueryError::new(MAX_DEPTH, "nested too deeply")
+        );
+        let deepest = "(".repeat(MAX_DEPTH) + "sleep" + &")".repeat(MAX_DEPTH);
+        assert!(Query::parse(&deepest).is_ok());
+
+        let err = Query::parse("cmd~/(/").expect_err("unclosed group");
+        assert_eq!(err.position, 4);
+        assert!(err.message.starts_with("invalid regex: "), "{}", err);
+        assert_eq!(
+            QueryError::new(4, "missing value").to_string(),
+            "column 5: missing value"
+        );
+    }
+
+    #[test]
+    fn subtrees() {
+        let in_cgroup = |path: &str| {
+            let source = tests::process(MemorySource::new(), 42, "sleep")
+                .with("42/cgroup", format!("0::{}\n", path));
+            parse_processes(&source, &ScanOptions::all())
+                .unwrap()
+                .processes
+                .remove(0)
+        };
+        for (query, path, expected) in [
+            ("cgroup2^/system.slice", "/system.slice", true),
+            ("cgroup2^/system.slice", "/system.slice/cron.service", true),
+            ("cgroup2^/system.slice/", "/system.slice/cron.service", true),
+            (
+                "cgroup2^/system.slice",
+                "/system.slice-extra/a.service",
+                false,
+            ),
+            ("cgroup2^/system.slice", "/user.slice", false),
+            ("cgroup2^/system.slice/cron.service", "/system.slice", false),
+            ("cgroup2^/", "/", true),
+            ("cgroup2^/", "/init.scope", true),
+            ("cgroup2^\"/a b\"", "/a b/c", true),
+        ] {
+            let parsed = Query::parse(query).unwrap_or_else(|err| panic!("{}: {}", query, err));
+            assert_eq!(
+                parsed.matches(&in_cgroup(path)),
+                expected,
+                "{} {}",
+                query,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        for (value, expected) in [
+            ("512", 512.0),
+            ("500M", 500.0 * 1024.0 * 1024.0),
+            ("2G", 2.0 * 1024.0 * 1024.0 * 1024.0),
+            ("1T", 1024.0f64.powi(4)),
+            ("1.5KiB", 1536.0),
+            ("10kb", 10240.0),
+            ("3m", 3.0 * 1024.0 * 1024.0),
+        ] {
+            assert_eq!(parse_number(Field::Rss, value), Some(expected), "{}", value);
+        }
+        assert_eq!(parse_number(Field::Cpu, "50%"), Some(50.0));
+        assert_eq!(parse_number(Field::Pid, "1K"), None);
+        assert_eq!(parse_number(Field::Rss, "K"), None);
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(equals("unit", "cron.service"), "unit=cron.service");
+        assert_eq!(equals("cgroup", "/a b/(c)"), "cgroup=\"/a b/(c)\"");
+        assert_eq!(within("cgroup2", "/a b"), "cgroup2^\"/a b\"");
+        assert_eq!(within("cgroup2", "/"), "cgroup2^/");
+        let query = Query::parse(&equals("cmd", "sleep infinity")).unwrap();
+        assert!(query.matches(&sleep()));
+    }
+}
@@ -0,0 +1,972 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+use crate::{
+    cpu::CpuTracker,
+    error::{Error, Result},
+    process::{parse_processes, Scan, ScanOptions},
+    procfs::ProcSource,
+    system::{parse_system, SystemInfo, SystemTracker},
+};
+
+/// Recordings start with this, followed by the version and the flags.
+const MAGIC: &[u8; 6] = b"LXREC\0";
+const VERSION: u16 = 1;
+/// Everything after the header is a deflate stream.
+const FLAG_COMPRESSED: u8 = 1;
+/// Every this many frames the full snapshot is written, so seeking doesn't have to start at the
+/// beginning of the recording.
+const KEYFRAME_INTERVAL: usize = 30;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Op {
+    Read,
+    ReadDir,
+    ReadLink,
+    IsDir,
+    IsDevice,
+}
+
+impl Op {
+    fn code(&self) -> u8 {
+        match self {
+            Op::Read => 0,
+            Op::ReadDir => 1,
+            Op::ReadLink => 2,
+            Op::IsDir => 3,
+            Op::IsDevice => 4,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Op::Read),
+            1 => Some(Op::ReadDir),
+            2 => Some(Op::ReadLink),
+            3 => Some(Op::IsDir),
+            4 => Some(Op::IsDevice),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of one [`ProcSource`] call.
+#[derive(Clone, PartialEq, Debug)]
+enum Value {
+    Bytes(Vec<u8>),
+    Names(Vec<OsString>),
+    Link(PathBuf),
+    Bool(bool),
+    /// An errno, so that e.g. `ESRCH` survives the round trip.
+    Error(i32),
+}
+
+impl Value {
+    fn error(err: &io::Error) -> Self {
+        Value::Error(err.raw_os_error().unwrap_or(match err.kind() {
+            io::ErrorKind::NotFound => libc::ENOENT,
+            io::ErrorKind::PermissionDenied => libc::EACCES,
+            _ => libc::EIO,
+        }))
+    }
+}
+
+/// Everything the explorer read from procfs during one sample, which can be read again like
+/// the real thing.
+#[derive(Clone, Default)]
+pub struct Snapshot {
+    entries: HashMap<(Op, PathBuf), Value>,
+}
+
+impl Snapshot {
+    fn get(&self, op: Op, path: &Path) -> io::Result<&Value> {
+        match self.entries.get(&(op, path.to_path_buf())) {
+            Some(Value::Error(errno)) => Err(io::Error::from_raw_os_error(*errno)),
+            Some(value) => Ok(value),
+            // Never looked at while recording.
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn apply(&mut self, changes: &[Change]) {
+        for (key, value) in changes {
+            match value {
+                Some(value) => self.entries.insert(key.clone(), value.clone()),
+                None => self.entries.remove(key),
+            };
+        }
+    }
+
+    /// What turns `previous` into `self`.
+    fn delta(&self, previous: &Snapshot) -> Vec<Change> {
+        let mut changes: Vec<Change> = self
+            .entries
+            .iter()
+            .filter(|(key, value)| previous.entries.get(*key) != Some(*value))
+            .map(|(key, value)| (key.clone(), Some(value.clone())))
+            .collect();
+        changes.extend(
+            previous
+                .entries
+                .keys()
+                .filter(|key| !self.entries.contains_key(*key))
+                .map(|key| (key.clone(), None)),
+        );
+        changes
+    }
+}
+
+impl ProcSource for Snapshot {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.get(Op::Read, path)? {
+            Value::Bytes(bytes) => Ok(bytes.clone()),
+            _ => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        match self.get(Op::ReadDir, path)? {
+            Value::Names(names) => Ok(names.clone()),
+            _ => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.get(Op::ReadLink, path)? {
+            Value::Link(target) => Ok(target.clone()),
+            _ => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    fn is_dir(&self, path: &Path) -> io::Result<bool> {
+        match self.get(Op::IsDir, path)? {
+            Value::Bool(is_dir) => Ok(*is_dir),
+            _ => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    fn is_device(&self, path: &Path) -> io::Result<bool> {
+        match self.get(Op::IsDevice, path)? {
+            Value::Bool(is_device) => Ok(*is_device),
+            _ => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+}
+
+/// A new value for a key, `None` if it wasn't read anymore.
+type Change = ((Op, PathBuf), Option<Value>);
+
+struct Frame {
+    taken_at: SystemTime,
+    /// Holds the full snapshot instead of the changes to the previous frame.
+    keyframe: bool,
+    changes: Vec<Change>,
+}
+
+enum Output {
+    Plain(BufWriter<File>),
+    Compressed(DeflateEncoder<BufWriter<File>>),
+}
+
+impl Output {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Output::Plain(writer) => writer,
+            Output::Compressed(writer) => writer,
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Plain(mut writer) => writer.flush(),
+            Output::Compressed(writer) => writer.finish()?.flush(),
+        }
+    }
+}
+
+/// Writes snapshots to a recording file.
+pub struct RecordingWriter {
+    output: Output,
+    previous: Snapshot,
+    frames: usize,
+}
+
+impl RecordingWriter {
+    pub fn create(path: &Path, compressed: bool) -> Result<Self> {
+        let file = File::create(path).map_err(|err| Error::io(path, err))?;
+        let mut writer = BufWriter::new(file);
+
+        let flags = if compressed { FLAG_COMPRESSED } else { 0 };
+        (|| {
+            writer.write_all(MAGIC)?;
+            writer.write_all(&VERSION.to_le_bytes())?;
+            writer.write_all(&[flags])
+        })()
+        .map_err(|err| Error::io(path, err))?;
+
+        let output = if compressed {
+            Output::Compressed(DeflateEncoder::new(writer, Compression::default()))
+        } else {
+            Output::Plain(writer)
+        };
+
+        Ok(Self {
+            output,
+            previous: Snapshot::default(),
+            frames: 0,
+        })
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Flushes after every frame, so a recording that is cut short is still readable.
+    pub fn write(&mut self, snapshot: Snapshot, taken_at: SystemTime) -> io::Result<()> {
+        let keyframe = self.frames.is_multiple_of(KEYFRAME_INTERVAL);
+        let changes = if keyframe {
+            snapshot.delta(&Snapshot::default())
+        } else {
+            snapshot.delta(&self.previous)
+        };
+
+        let frame = Frame {
+            taken_at,
+            keyframe,
+            changes,
+        };
+        let writer = self.output.writer();
+        write_frame(writer, &frame)?;
+        writer.flush()?;
+
+        self.previous = snapshot;
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        self.output.finish()
+    }
+}
+
+fn write_frame(writer: &mut dyn Write, frame: &Frame) -> io::Result<()> {
+    let millis = frame
+        .taken_at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    writer.write_all(&millis.to_le_bytes())?;
+    writer.write_all(&[frame.keyframe as u8])?;
+    writer.write_all(&(frame.changes.len() as u32).to_le_bytes())?;
+
+    for ((op, path), value) in &frame.changes {
+        writer.write_all(&[op.code()])?;
+        write_bytes(writer, path.as_os_str().as_bytes())?;
+        match value {
+            None => writer.write_all(&[0])?,
+            Some(Value::Bytes(bytes)) => {
+                writer.write_all(&[1])?;
+                write_bytes(writer, bytes)?;
+            }
+            Some(Value::Names(names)) => {
+                writer.write_all(&[2])?;
+                writer.write_all(&(names.len() as u32).to_le_bytes())?;
+                for name in names {
+                    write_bytes(writer, name.as_bytes())?;
+                }
+            }
+            Some(Value::Link(target)) => {
+                writer.write_all(&[3])?;
+                write_bytes(writer, target.as_os_str().as_bytes())?;
+            }
+            Some(Value::Bool(value)) => writer.write_all(&[4, *value as u8])?,
+            Some(Value::Error(errno)) => {
+                writer.write_all(&[5])?;
+                writer.write_all(&errno.to_le_bytes())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_bytes(writer: &mut dyn Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+/// `None` at the end of the file, also if the last frame was cut short.
+fn read_frame(reader: &mut dyn Read) -> io::Result<Option<Frame>> {
+    let mut millis = [0; 8];
+    match reader.read_exact(&mut millis) {
+        Ok(()) => {}
+        Err(err) if is_cut_short(&err) => return Ok(None),
+        Err(err) => return Err(err),
+    }
+
+    match read_frame_body(reader, u64::from_le_bytes(millis)) {
+        Ok(frame) => Ok(Some(frame)),
+        Err(err) if is_cut_short(&err) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// The deflate decoder reports a stream that ends in the middle of a block as invalid input.
+fn is_cut_short(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidInput
+    )
+}
+
+fn read_frame_body(reader: &mut dyn Read, millis: u64) -> io::Result<Frame> {
+    let keyframe = read_u8(reader)? != 0;
+    let count = read_u32(reader)?;
+
+    let mut changes = Vec::with_capacity(count.min(1 << 16) as usize);
+    for _ in 0..count {
+        let op = Op::from_code(read_u8(reader)?).ok_or_else(invalid)?;
+        let path = PathBuf::from(OsString::from_vec(read_bytes(reader)?));
+        let value = match read_u8(reader)? {
+            0 => None,
+            1 => Some(Value::Bytes(read_bytes(reader)?)),
+            2 => {
+                let count = read_u32(reader)?;
+                let names = (0..count)
+                    .map(|_| read_bytes(reader).map(OsString::from_vec))
+                    .collect::<io::Result<_>>()?;
+                Some(Value::Names(names))
+            }
+            3 => Some(Value::Link(PathBuf::from(OsString::from_vec(read_bytes(
+                reader,
+            )?)))),
+            4 => Some(Value::Bool(read_u8(reader)? != 0)),
+            5 => {
+                let mut errno = [0; 4];
+                reader.read_exact(&mut errno)?;
+                Some(Value::Error(i32::from_le_bytes(errno)))
+            }
+            _ => return Err(invalid()),
+        };
+        changes.push(((op, path), value));
+    }
+
+    Ok(Frame {
+        taken_at: UNIX_EPOCH + Duration::from_millis(millis),
+        keyframe,
+        changes,
+    })
+}
+
+fn read_u8(reader: &mut dyn Read) -> io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_u32(reader: &mut dyn Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_bytes(reader: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let len = read_u32(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len as usize {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+fn invalid() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "corrupt frame")
+}
+
+/// A recording loaded into memory, still delta encoded.
+pub struct Recording {
+    frames: Vec<Frame>,
+    /// The last snapshot handed out by [`Recording::snapshot`], to step forward cheaply.
+    cache: Option<(usize, Snapshot)>,
+}
+
+impl Recording {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).map_err(|err| Error::io(path, err))?;
+        let mut reader = BufReader::new(file);
+
+        let mut header = [0; 9];
+        reader
+            .read_exact(&mut header)
+            .map_err(|err| Error::io(path, err))?;
+        if &header[..6] != MAGIC {
+            return Err(Error::malformed(path, "not a recording"));
+        }
+        let version = u16::from_le_bytes([header[6], header[7]]);
+        if version != VERSION {
+            return Err(Error::malformed(
+                path,
+                format!("unsupported recording version {}", version),
+            ));
+        }
+
+        let mut reader: Box<dyn Read> = if header[8] & FLAG_COMPRESSED != 0 {
+            Box::new(DeflateDecoder::new(reader))
+        } else {
+            Box::new(reader)
+        };
+
+        let mut frames = Vec::new();
+        while let Some(frame) = read_frame(&mut reader).map_err(|err| Error::io(path, err))? {
+            frames.push(frame);
+        }
+        if !frames.first().is_some_and(|frame| frame.keyframe) {
+            return Err(Error::malformed(path, "no frames"));
+        }
+
+        Ok(Self {
+            frames,
+            cache: None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn taken_at(&self, index: usize) -> SystemTime {
+        self.frames[index].taken_at
+    }
+
+    /// Rebuilds the snapshot of frame `index` from the keyframe before it.
+    pub fn snapshot(&mut self, index: usize) -> Snapshot {
+        let keyframe_between =
+            |from: usize| self.frames[from + 1..=index].iter().any(|f| f.keyframe);
+        let (mut position, mut snapshot) = match self.cache.take() {
+            Some((cached, snapshot)) if cached <= index && !keyframe_between(cached) => {
+                (cached + 1, snapshot)
+            }
+            _ => {
+                let keyframe = (0..=index)
+                    .rev()
+                    .find(|&i| self.frames[i].keyframe)
+                    .unwrap_or(0);
+                (keyframe, Snapshot::default())
+            }
+        };
+
+        while position <= index {
+            let frame = &self.frames[position];
+            if frame.keyframe {
+                snapshot = Snapshot::default();
+            }
+            snapshot.apply(&frame.changes);
+            position += 1;
+        }
+
+        self.cache = Some((index, snapshot.clone()));
+        snapshot
+    }
+}
+
+/// Passes calls through to another source and, while recording, remembers their results for
+/// the next frame.
+pub struct Recorder {
+    inner: Arc<dyn ProcSource>,
+    state: Mutex<RecorderState>,
+}
+
+#[derive(Default)]
+struct RecorderState {
+    writer: Option<(PathBuf, RecordingWriter)>,
+    current: Snapshot,
+    /// Why the last recording stopped on its own.
+    error: Option<String>,
+}
+
+impl Recorder {
+    pub fn new(inner: Arc<dyn ProcSource>) -> Self {
+        Self {
+            inner,
+            state: Mutex::new(RecorderState::default()),
+        }
+    }
+
+    pub fn start(&self, path: &Path, compressed: bool) -> Result<()> {
+        let writer = RecordingWriter::create(path, compressed)?;
+        let mut state = self.state.lock().unwrap();
+        state.writer = Some((path.to_path_buf(), writer));
+        state.current = Snapshot::default();
+        state.error = None;
+        Ok(())
+    }
+
+    pub fn stop(&self) -> Result<()> {
+        let writer = self.state.lock().unwrap().writer.take();
+        if let Some((path, writer)) = writer {
+            writer.finish().map_err(|err| Error::io(path, err))?;
+        }
+        Ok(())
+    }
+
+    /// Path and number of frames of the running recording.
+    pub fn recording(&self) -> Option<(PathBuf, usize)> {
+        let state = self.state.lock().unwrap();
+        let (path, writer) = state.writer.as_ref()?;
+        Some((path.clone(), writer.frames()))
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.state.lock().unwrap().error.clone()
+    }
+
+    /// Writes everything read since the last frame as a new frame. Write errors stop the
+    /// recording.
+    pub fn finish_frame(&self, taken_at: SystemTime) {
+        let mut state = self.state.lock().unwrap();
+        let snapshot = std::mem::take(&mut state.current);
+        let Some((path, writer)) = &mut state.writer else {
+            return;
+        };
+
+        if let Err(err) = writer.write(snapshot, taken_at) {
+            state.error = Some(Error::io(path.clone(), err).to_string());
+            state.writer = None;
+        }
+    }
+
+    /// `value` is only called while recording, to save copying everything we read.
+    fn record(&self, op: Op, path: &Path, value: impl FnOnce() -> Value) {
+        let mut state = self.state.lock().unwrap();
+        if state.writer.is_some() {
+            state
+                .current
+                .entries
+                .insert((op, path.to_path_buf()), value());
+        }
+    }
+}
+
+impl ProcSource for Recorder {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let result = self.inner.read(path);
+        self.record(Op::Read, path, || match &result {
+            Ok(bytes) => Value::Bytes(bytes.clone()),
+            Err(err) => Value::error(err),
+        });
+        result
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let result = self.inner.read_dir(path);
+        self.record(Op::ReadDir, path, || match &result {
+            Ok(names) => Value::Names(names.clone()),
+            Err(err) => Value::error(err),
+        });
+        result
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        let result = self.inner.read_link(path);
+        self.record(Op::ReadLink, path, || match &result {
+            Ok(target) => Value::Link(target.clone()),
+            Err(err) => Value::error(err),
+        });
+        result
+    }
+
+    fn is_dir(&self, path: &Path) -> io::Result<bool> {
+        let result = self.inner.is_dir(path);
+        self.record(Op::IsDir, path, || match &result {
+            Ok(is_dir) => Value::Bool(*is_dir),
+            Err(err) => Value::error(err),
+        });
+        result
+    }
+
+    fn is_device(&self, path: &Path) -> io::Result<bool> {
+        let result = self.inner.is_device(path);
+        self.record(Op::IsDevice, path, || match &result {
+            Ok(is_device) => Value::Bool(*is_device),
+            Err(err) => Value::error(err),
+        });
+        result
+    }
+
+    fn host_path(&self, path: &Path) -> Option<PathBuf> {
+        self.inner.host_path(path)
+    }
+}
+
+/// One frame of a recording, parsed like a live sample.
+pub struct ReplayFrame {
+    pub source: Arc<Snapshot>,
+    pub scan: Result<Scan>,
+    pub system: Result<SystemInfo>,
+    pub taken_at: SystemTime,
+    /// Directly follows the previously loaded frame, so history can continue.
+    pub sequential: bool,
+}
+
+/// Plays back a recording through the same parsers that handle live samples.
+pub struct Replay {
+    pub path: PathBuf,
+    recording: Recording,
+    /// Index of the last loaded frame.
+    position: Option<usize>,
+    cpu: CpuTracker,
+    system: SystemTracker,
+}
+
+impl Replay {
+    pub fn new(path: PathBuf, recording: Recording) -> Self {
+        Self {
+            path,
+            recording,
+            position: None,
+            cpu: CpuTracker::default(),
+            system: SystemTracker::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.recording.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recording.is_empty()
+    }
+
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    pub fn taken_at(&self, index: usize) -> SystemTime {
+        self.recording.taken_at(index)
+    }
+
+    /// Usage percentages need the frame before, so that is parsed too when jumping around.
+    pub fn load(&mut self, index: usize) -> ReplayFrame {
+        puffin::profile_function!();
+
+        let index = index.min(self.len() - 1);
+        let sequential = index > 0 && self.position == Some(index - 1);
+        if !sequential {
+            self.cpu = CpuTracker::default();
+            self.system = SystemTracker::default();
+            if index > 0 {
+                let previous = self.recording.snapshot(index - 1);
+                // Only primes the trackers.
+                let _ = self.parse(&previous);
+            }
+        }
+
+        let source = Arc::new(self.recording.snapshot(index));
+        let (scan, system) = self.parse(source.as_ref());
+        self.position = Some(index);
+
+        ReplayFrame {
+            source,
+            scan,
+            system,
+            taken_at: self.recording.taken_at(index),
+            sequential,
+        }
+    }
+
+    fn parse(&mut self, source: &Snapshot) -> (Result<Scan>, Result<SystemInfo>) {
+        let scan = parse_processes(source, &ScanOptions::all()).map(|mut scan| {
+            self.cpu.update(source, &mut scan.processes);
+            scan
+        });
+        let system = parse_system(source).map(|mut system| {
+            self.system.update(&mut system);
+            system
+        });
+        (scan, system)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, procfs::MemorySource};
+
+    /// A file in the temporary directory that is removed again when the test is done.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!(
+                "linux-explorer-{}-{}.lxrec",
+                std::process::id(),
+                name
+            )))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn time(frame: usize) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(1_700_000_000_000 + frame as u64 * 1500)
+    }
+
+    /// Mostly the same from frame to frame, like procfs between two samples.
+    fn snapshot(frame: usize) -> Snapshot {
+        let mut entries = HashMap::new();
+        let mut insert = |op, path: &str, value| entries.insert((op, PathBuf::from(path)), value);
+        insert(Op::Read, "stat", Value::Bytes(b"cpu  1 2 3 4\n".repeat(20)));
+        insert(
+            Op::Read,
+            "1/stat",
+            Value::Bytes(format!("1 (init) S 0 {}", frame).into_bytes()),
+        );
+        insert(
+            Op::ReadDir,
+            "",
+            Value::Names(vec!["1".into(), "self".into()]),
+        );
+        insert(
+            Op::ReadLink,
+            "1/ns/pid",
+            Value::Link("pid:[4026531836]".into()),
+        );
+        insert(Op::IsDir, "1", Value::Bool(true));
+        insert(Op::IsDevice, "1/fd/0", Value::Bool(true));
+        insert(Op::Read, "1/environ", Value::Error(libc::EACCES));
+        // Comes and goes.
+        if frame.is_multiple_of(3) {
+            insert(Op::Read, "2/stat", Value::Error(libc::ESRCH));
+        }
+        Snapshot { entries }
+    }
+
+    fn record(path: &Path, compressed: bool, frames: usize) {
+        let mut writer = RecordingWriter::create(path, compressed).unwrap();
+        for frame in 0..frames {
+            writer.write(snapshot(frame), time(frame)).unwrap();
+        }
+        assert_eq!(writer.frames(), frames);
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn round_trip() {
+        for compressed in [false, true] {
+            let file = TempFile::new(&format!("round-trip-{}", compressed));
+            record(&file.0, compressed, 65);
+            let mut recording = Recording::open(&file.0).unwrap();
+            assert_eq!(recording.len(), 65);
+
+            // Forwards uses the cache, the jumps have to go back to a keyframe.
+            let order = (0..65).chain([64, 3, 31, 30, 29, 0, 60, 59]);
+            for frame in order {
+                assert_eq!(
+                    recording.snapshot(frame).entries,
+                    snapshot(frame).entries,
+                    "frame {} compressed {}",
+                    frame,
+                    compressed
+                );
+                assert_eq!(recording.taken_at(frame), time(frame));
+            }
+        }
+    }
+
+    #[test]
+    fn keyframes_and_deltas() {
+        let file = TempFile::new("keyframes");
+        record(&file.0, false, 65);
+        let recording = Recording::open(&file.0).unwrap();
+
+        for (i, frame) in recording.frames.iter().enumerate() {
+            assert_eq!(
+                frame.keyframe,
+                i.is_multiple_of(KEYFRAME_INTERVAL),
+                "frame {}",
+                i
+            );
+            let expected = if frame.keyframe {
+                snapshot(i).entries.len()
+            } else {
+                // The changed stat, and 2/stat appearing or disappearing.
+                1 + usize::from(i % 3 != 2)
+            };
+            assert_eq!(frame.changes.len(), expected, "frame {}", i);
+        }
+
+        // 2/stat is gone in frame 1, so the delta removes it.
+        let removed: Vec<&Change> = recording.frames[1]
+            .changes
+            .iter()
+            .filter(|(_, value)| value.is_none())
+            .collect();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].0, (Op::Read, PathBuf::from("2/stat")));
+    }
+
+    #[test]
+    fn compression() {
+        let plain = TempFile::new("plain");
+        let compressed = TempFile::new("compressed");
+        record(&plain.0, false, 10);
+        record(&compressed.0, true, 10);
+
+        let plain_bytes = std::fs::read(&plain.0).unwrap();
+        let compressed_bytes = std::fs::read(&compressed.0).unwrap();
+        assert_eq!(&plain_bytes[..8], &compressed_bytes[..8]);
+        assert_eq!(plain_bytes[8], 0);
+        assert_eq!(compressed_bytes[8], FLAG_COMPRESSED);
+        assert!(compressed_bytes.len() < plain_bytes.len());
+
+        let mut plain = Recording::open(&plain.0).unwrap();
+        let mut compressed = Recording::open(&compressed.0).unwrap();
+        for frame in 0..10 {
+            assert_eq!(
+                plain.snapshot(frame).entries,
+                compressed.snapshot(frame).entries
+            );
+        }
+    }
+
+    #[test]
+    fn bad_header() {
+        let file = TempFile::new("bad-header");
+        let mut version = MAGIC.to_vec();
+        version.extend_from_slice(&2u16.to_le_bytes());
+        version.push(0);
+        let mut header_only = MAGIC.to_vec();
+        header_only.extend_from_slice(&VERSION.to_le_bytes());
+        header_only.push(0);
+
+        for (contents, reason) in [
+            (b"LXREX\0\x01\0\0".to_vec(), "not a recording"),
+            (version, "unsupported recording version 2"),
+            (header_only, "no frames"),
+        ] {
+            std::fs::write(&file.0, contents).unwrap();
+            let Err(err) = Recording::open(&file.0) else {
+                panic!("opened a recording with {}", reason);
+            };
+            assert_eq!(err.kind, ErrorKind::Malformed(reason.to_string()));
+        }
+
+        std::fs::write(&file.0, b"LXR").unwrap();
+        let Err(err) = Recording::open(&file.0) else {
+            panic!("opened a recording without a header");
+        };
+        assert_eq!(err.kind, ErrorKind::Io(io::ErrorKind::UnexpectedEof));
+    }
+
+    #[test]
+    fn truncated() {
+        for compressed in [false, true] {
+            let file = TempFile::new(&format!("truncated-{}", compressed));
+            let mut writer = RecordingWriter::create(&file.0, compressed).unwrap();
+            writer.write(snapshot(0), time(0)).unwrap();
+            writer.write(snapshot(1), time(1)).unwrap();
+            let two_frames = std::fs::metadata(&file.0).unwrap().len() as usize;
+            writer.write(snapshot(2), time(2)).unwrap();
+            let three_frames = std::fs::metadata(&file.0).unwrap().len() as usize;
+            // Like a crash, without finishing the stream.
+            drop(writer);
+            let bytes = std::fs::read(&file.0).unwrap();
+
+            // Anywhere in the last frame, the frames before it are still there. Compressed
+            // frames can survive losing the end of the flush that follows them.
+            for end in two_frames..three_frames {
+                std::fs::write(&file.0, &bytes[..end]).unwrap();
+                let mut recording = Recording::open(&file.0).unwrap();
+                let cut = format!("compressed {} cut at {}", compressed, end);
+                match recording.len() {
+                    3 if compressed => {
+                        assert_eq!(
+                            recording.snapshot(2).entries,
+                            snapshot(2).entries,
+                            "{}",
+                            cut
+                        )
+                    }
+                    len => assert_eq!(len, 2, "{}", cut),
+                }
+                assert_eq!(
+                    recording.snapshot(1).entries,
+                    snapshot(1).entries,
+                    "{}",
+                    cut
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn errors_survive() {
+        let snapshot = snapshot(0);
+        let err = snapshot.read(Path::new("2/stat")).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ESRCH));
+        let err = snapshot.read(Path::new("1/environ")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        // Never read while recording.
+        let err = snapshot.read(Path::new("1/maps")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        // Read, but as something else.
+        let err = snapshot.read(Path::new("1")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = snapshot.read_dir(Path::new("1/stat")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn recorder() {
+        let file = TempFile::new("recorder");
+        let source = MemorySource::new()
+            .with("1/stat", "1 (init) S 0")
+            .with_error("1/environ", io::ErrorKind::PermissionDenied);
+        let recorder = Recorder::new(Arc::new(source));
+
+        // Nothing is kept before the recording starts.
+        recorder.read(Path::new("1/stat")).unwrap();
+        recorder.start(&file.0, true).unwrap();
+        recorder.read(Path::new("1/stat")).unwrap();
+        recorder.read(Path::new("1/environ")).unwrap_err();
+        recorder.read_dir(Path::new("")).unwrap();
+        recorder.finish_frame(time(0));
+        recorder.read(Path::new("1/stat")).unwrap();
+        recorder.finish_frame(time(1));
+        assert_eq!(recorder.recording(), Some((file.0.clone(), 2)));
+        recorder.stop().unwrap();
+        assert_eq!(recorder.recording(), None);
+
+        let mut recording = Recording::open(&file.0).unwrap();
+        assert_eq!(recording.len(), 2);
+        let first = recording.snapshot(0);
+        assert_eq!(first.read(Path::new("1/stat")).unwrap(), b"1 (init) S 0");
+        assert_eq!(
+            first.read(Path::new("1/environ")).unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+        assert_eq!(first.read_dir(Path::new("")).unwrap(), ["1"]);
+        let second = recording.snapshot(1);
+        assert!(second.read(Path::new("1/stat")).is_ok());
+        assert!(second.read_dir(Path::new("")).is_err());
+    }
+}
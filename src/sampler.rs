@@ -0,0 +1,131 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// A value produced by a [`Sampler`] together with the time it was taken at.
+pub struct Sample<T> {
+    pub value: T,
+    pub taken_at: SystemTime,
+}
+
+enum Command {
+    Pause,
+    Resume,
+    SetInterval(Duration),
+    SampleNow,
+}
+
+/// Calls a sampling function on a background thread in a fixed interval.
+///
+/// The thread stops once the sampler is dropped.
+pub struct Sampler<T> {
+    commands: Sender<Command>,
+    samples: Receiver<Sample<T>>,
+    interval: Duration,
+    paused: bool,
+}
+
+impl<T: Send + 'static> Sampler<T> {
+    /// `on_sample` is called after every new sample, e.g. to wake up the UI.
+    pub fn spawn(
+        interval: Duration,
+        mut sample: impl FnMut() -> T + Send + 'static,
+        on_sample: impl Fn() + Send + 'static,
+    ) -> Self {
+        let (commands, command_receiver) = mpsc::channel();
+        let (sample_sender, samples) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut interval = interval;
+            let mut paused = false;
+            let mut forced = false;
+            let mut next = Instant::now();
+
+            loop {
+                let now = Instant::now();
+                if next <= now {
+                    if !paused || forced {
+                        forced = false;
+                        let value = sample();
+                        let sample = Sample {
+                            value,
+                            taken_at: SystemTime::now(),
+                        };
+
+                        if sample_sender.send(sample).is_err() {
+                            return;
+                        }
+                        on_sample();
+                    }
+                    next = Instant::now() + interval;
+                    continue;
+                }
+
+                match command_receiver.recv_timeout(next - now) {
+                    Ok(Command::Pause) => paused = true,
+                    Ok(Command::Resume) => {
+                        paused = false;
+                        next = Instant::now();
+                    }
+                    Ok(Command::SetInterval(new_interval)) => {
+                        next = next - interval + new_interval;
+                        interval = new_interval;
+                    }
+                    Ok(Command::SampleNow) => {
+                        forced = true;
+                        next = Instant::now();
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+
+        Self {
+            commands,
+            samples,
+            interval,
+            paused: false,
+        }
+    }
+
+    /// Newest sample taken since the last call, older ones are dropped.
+    pub fn latest(&self) -> Option<Sample<T>> {
+        self.samples.try_iter().last()
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+        self.send(Command::SetInterval(interval));
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.send(Command::Pause);
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.send(Command::Resume);
+    }
+
+    /// Takes a single sample right away, even if paused.
+    pub fn sample_now(&self) {
+        self.send(Command::SampleNow);
+    }
+
+    fn send(&self, command: Command) {
+        // The thread only exits when we are dropped.
+        let _ = self.commands.send(command);
+    }
+}
@@ -0,0 +1,858 @@
+use std::{fmt::Display, path::Path, time::Duration};
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Ui};
+
+use crate::{
+    error::{Error, ErrorKind, Result},
+    process::Process,
+    procfs::{KeyValues, ProcSource},
+};
+
+/// Scheduling policy, see sched(7).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Policy {
+    #[default]
+    Other,
+    Batch,
+    Idle,
+    Fifo,
+    RoundRobin,
+    Deadline,
+}
+
+impl Policy {
+    pub const ALL: [Policy; 6] = [
+        Policy::Other,
+        Policy::Batch,
+        Policy::Idle,
+        Policy::Fifo,
+        Policy::RoundRobin,
+        Policy::Deadline,
+    ];
+
+    /// From the `policy` field of `/proc/[pid]/stat`.
+    pub fn from_raw(raw: u64) -> Option<Self> {
+        let policy = match i32::try_from(raw).ok()? {
+            libc::SCHED_OTHER => Policy::Other,
+            libc::SCHED_BATCH => Policy::Batch,
+            libc::SCHED_IDLE => Policy::Idle,
+            libc::SCHED_FIFO => Policy::Fifo,
+            libc::SCHED_RR => Policy::RoundRobin,
+            libc::SCHED_DEADLINE => Policy::Deadline,
+            _ => return None,
+        };
+        Some(policy)
+    }
+
+    fn raw(&self) -> i32 {
+        match self {
+            Policy::Other => libc::SCHED_OTHER,
+            Policy::Batch => libc::SCHED_BATCH,
+            Policy::Idle => libc::SCHED_IDLE,
+            Policy::Fifo => libc::SCHED_FIFO,
+            Policy::RoundRobin => libc::SCHED_RR,
+            Policy::Deadline => libc::SCHED_DEADLINE,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Policy::Other => "SCHED_OTHER",
+            Policy::Batch => "SCHED_BATCH",
+            Policy::Idle => "SCHED_IDLE",
+            Policy::Fifo => "SCHED_FIFO",
+            Policy::RoundRobin => "SCHED_RR",
+            Policy::Deadline => "SCHED_DEADLINE",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Policy::Other => "Default time sharing, weighted by nice",
+            Policy::Batch => "Time sharing for CPU bound work that doesn't need to be interactive",
+            Policy::Idle => "Only runs when nothing else wants the CPU",
+            Policy::Fifo => "Real-time, runs until it blocks or something more important runs",
+            Policy::RoundRobin => "Real-time, like FIFO but with time slices",
+            Policy::Deadline => "Gets the runtime within every period, before the deadline",
+        }
+    }
+
+    /// Whether the policy takes a static priority from 1 to 99.
+    pub fn is_realtime(&self) -> bool {
+        matches!(self, Policy::Fifo | Policy::RoundRobin)
+    }
+}
+
+impl Display for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Parameters of [`Policy::Deadline`], with runtime <= deadline <= period.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Deadline {
+    pub runtime: Duration,
+    pub deadline: Duration,
+    pub period: Duration,
+}
+
+impl Default for Deadline {
+    fn default() -> Self {
+        Self {
+            runtime: Duration::from_millis(10),
+            deadline: Duration::from_millis(30),
+            period: Duration::from_millis(30),
+        }
+    }
+}
+
+impl Deadline {
+    /// The kernel works in units of 1024 ns and refuses shorter runtimes.
+    pub const MIN_RUNTIME: Duration = Duration::from_nanos(1 << 10);
+    /// Default kernel.sched_deadline_period_min_us.
+    pub const MIN_PERIOD: Duration = Duration::from_micros(100);
+    /// Default kernel.sched_deadline_period_max_us.
+    pub const MAX_PERIOD: Duration = Duration::from_micros(1 << 22);
+
+    /// Why the kernel would reject these parameters with a bare EINVAL.
+    pub fn check(&self) -> std::result::Result<(), &'static str> {
+        if self.runtime < Self::MIN_RUNTIME {
+            Err("the runtime must be at least 1024 ns")
+        } else if self.period < Self::MIN_PERIOD {
+            Err("the period must be at least 100 µs")
+        } else if self.period > Self::MAX_PERIOD {
+            Err("the period can't be longer than 4.19 s")
+        } else if self.runtime > self.deadline {
+            Err("the runtime can't be longer than the deadline")
+        } else if self.deadline > self.period {
+            Err("the deadline can't be longer than the period")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// I/O scheduling class, see ioprio_set(2).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum IoClass {
+    /// Derived from the nice value.
+    #[default]
+    None,
+    RealTime,
+    BestEffort,
+    Idle,
+}
+
+impl IoClass {
+    pub const ALL: [IoClass; 4] = [
+        IoClass::None,
+        IoClass::RealTime,
+        IoClass::BestEffort,
+        IoClass::Idle,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IoClass::None => "none",
+            IoClass::RealTime => "realtime",
+            IoClass::BestEffort => "best-effort",
+            IoClass::Idle => "idle",
+        }
+    }
+
+    /// Whether the class has levels from 0 (highest) to 7.
+    pub fn has_level(&self) -> bool {
+        matches!(self, IoClass::RealTime | IoClass::BestEffort)
+    }
+}
+
+const IOPRIO_CLASS_SHIFT: i32 = 13;
+const IOPRIO_WHO_PROCESS: i32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct IoPriority {
+    pub class: IoClass,
+    pub level: u8,
+}
+
+impl IoPriority {
+    fn from_raw(raw: i32) -> Self {
+        let class = match raw >> IOPRIO_CLASS_SHIFT {
+            1 => IoClass::RealTime,
+            2 => IoClass::BestEffort,
+            3 => IoClass::Idle,
+            _ => IoClass::None,
+        };
+        Self {
+            class,
+            level: (raw & 0x7) as u8,
+        }
+    }
+
+    fn raw(&self) -> i32 {
+        let class = match self.class {
+            IoClass::None => 0,
+            IoClass::RealTime => 1,
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        };
+        let level = if self.class.has_level() {
+            i32::from(self.level.min(7))
+        } else {
+            0
+        };
+        class << IOPRIO_CLASS_SHIFT | level
+    }
+}
+
+impl Display for IoPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.class.has_level() {
+            write!(f, "{} {}", self.class.name(), self.level)
+        } else {
+            write!(f, "{}", self.class.name())
+        }
+    }
+}
+
+/// Failure to change the scheduling of a single thread.
+#[derive(Debug, Clone)]
+pub struct SchedError {
+    pub tid: u64,
+    pub kind: ErrorKind,
+}
+
+impl Display for SchedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::PermissionDenied => write!(
+                f,
+                "{}: permission denied, raising the priority or changing processes of other \
+                 users needs CAP_SYS_NICE",
+                self.tid
+            ),
+            ErrorKind::NoSuchProcess => {
+                write!(f, "{}: no such process, it already exited", self.tid)
+            }
+            ErrorKind::Io(std::io::ErrorKind::ResourceBusy) => write!(
+                f,
+                "{}: there is not enough CPU bandwidth left for this deadline",
+                self.tid
+            ),
+            ErrorKind::Io(kind) => write!(f, "{}: {}", self.tid, kind),
+            ErrorKind::Malformed(reason) => write!(f, "{}: {}", self.tid, reason),
+            kind => write!(f, "{}: {:?}", self.tid, kind),
+        }
+    }
+}
+
+impl std::error::Error for SchedError {}
+
+/// Failure to change some of the threads of a process, the others were changed.
+#[derive(Debug, Clone)]
+pub struct ThreadsError {
+    /// Never empty.
+    pub failed: Vec<SchedError>,
+    pub changed: usize,
+}
+
+impl Display for ThreadsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Usually every thread fails the same way, so the first error stands for all.
+        write!(f, "{}", self.failed[0])?;
+        if self.failed.len() == 1 && self.changed == 0 {
+            return Ok(());
+        }
+
+        const SHOWN: usize = 8;
+        let mut tids: Vec<String> = self
+            .failed
+            .iter()
+            .take(SHOWN)
+            .map(|err| err.tid.to_string())
+            .collect();
+        if self.failed.len() > SHOWN {
+            tids.push(format!("{} more", self.failed.len() - SHOWN));
+        }
+        write!(f, " (failed for {}, ", tids.join(", "))?;
+        match self.changed {
+            0 => write!(f, "no thread was changed)"),
+            1 => write!(f, "1 other thread was changed)"),
+            changed => write!(f, "{} other threads were changed)", changed),
+        }
+    }
+}
+
+impl std::error::Error for ThreadsError {}
+
+/// The pid for a syscall, 0 would be the calling thread.
+fn target(tid: u64) -> std::result::Result<i32, SchedError> {
+    match i32::try_from(tid) {
+        Ok(target) if target > 0 => Ok(target),
+        _ => Err(SchedError {
+            tid,
+            kind: ErrorKind::NoSuchProcess,
+        }),
+    }
+}
+
+fn check(tid: u64, ret: i64) -> std::result::Result<(), SchedError> {
+    if ret == -1 {
+        Err(SchedError {
+            tid,
+            kind: ErrorKind::last_os_error(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Sets the nice value of a single thread with setpriority(2).
+pub fn set_nice(tid: u64, nice: i32) -> std::result::Result<(), SchedError> {
+    let target = target(tid)?;
+    // SAFETY: setpriority has no memory safety requirements.
+    let ret = unsafe { libc::setpriority(libc::PRIO_PROCESS, target as libc::id_t, nice) };
+    check(tid, ret.into())
+}
+
+/// Sets the policy of a single thread, `priority` is only used by the real-time policies and
+/// `deadline` only by [`Policy::Deadline`].
+pub fn set_policy(
+    tid: u64,
+    policy: Policy,
+    priority: u32,
+    deadline: Deadline,
+) -> std::result::Result<(), SchedError> {
+    let target = target(tid)?;
+
+    if policy != Policy::Deadline {
+        let param = libc::sched_param {
+            sched_priority: if policy.is_realtime() {
+                priority.clamp(1, 99) as i32
+            } else {
+                0
+            },
+        };
+        // SAFETY: param is a valid sched_param that outlives the call.
+        let ret = unsafe { libc::sched_setscheduler(target, policy.raw(), &param) };
+        return check(tid, ret.into());
+    }
+
+    deadline.check().map_err(|reason| SchedError {
+        tid,
+        kind: ErrorKind::Malformed(reason.to_string()),
+    })?;
+
+    // struct sched_attr from sched_setattr(2), libc has no binding for it.
+    #[repr(C)]
+    struct SchedAttr {
+        size: u32,
+        sched_policy: u32,
+        sched_flags: u64,
+        sched_nice: i32,
+        sched_priority: u32,
+        sched_runtime: u64,
+        sched_deadline: u64,
+        sched_period: u64,
+    }
+    let attr = SchedAttr {
+        size: std::mem::size_of::<SchedAttr>() as u32,
+        sched_policy: libc::SCHED_DEADLINE as u32,
+        sched_flags: 0,
+        sched_nice: 0,
+        sched_priority: 0,
+        sched_runtime: deadline.runtime.as_nanos() as u64,
+        sched_deadline: deadline.deadline.as_nanos() as u64,
+        sched_period: deadline.period.as_nanos() as u64,
+    };
+    // SAFETY: attr is a valid sched_attr of the size it claims and outlives the call.
+    let ret = unsafe { libc::syscall(libc::SYS_sched_setattr, target, &attr, 0u32) };
+    check(tid, ret)
+}
+
+/// Restricts a single thread to `cpus` with sched_setaffinity(2).
+pub fn set_affinity(tid: u64, cpus: &[usize]) -> std::result::Result<(), SchedError> {
+    let target = target(tid)?;
+    // SAFETY: an all zero cpu_set_t is an empty set.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        if cpu < libc::CPU_SETSIZE as usize {
+            // SAFETY: cpu is within the set.
+            unsafe { libc::CPU_SET(cpu, &mut set) };
+        }
+    }
+    // SAFETY: set is a valid cpu_set_t of the given size.
+    let ret =
+        unsafe { libc::sched_setaffinity(target, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+    check(tid, ret.into())
+}
+
+/// The I/O priority of a single thread, which procfs doesn't show.
+pub fn io_priority(tid: u64) -> std::result::Result<IoPriority, SchedError> {
+    let target = target(tid)?;
+    // SAFETY: ioprio_get has no memory safety requirements.
+    let ret = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, target) };
+    check(tid, ret)?;
+    Ok(IoPriority::from_raw(ret as i32))
+}
+
+pub fn set_io_priority(tid: u64, priority: IoPriority) -> std::result::Result<(), SchedError> {
+    let target = target(tid)?;
+    // SAFETY: ioprio_set has no memory safety requirements.
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            target,
+            priority.raw(),
+        )
+    };
+    check(tid, ret)
+}
+
+/// Applies `change` to every thread in `tids`, carrying on past failures so that the process
+/// isn't left with only some of its threads changed without saying which.
+pub fn for_threads(
+    tids: &[u64],
+    change: impl Fn(u64) -> std::result::Result<(), SchedError>,
+) -> std::result::Result<(), ThreadsError> {
+    let failed: Vec<SchedError> = tids.iter().filter_map(|&tid| change(tid).err()).collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(ThreadsError {
+            changed: tids.len() - failed.len(),
+            failed,
+        })
+    }
+}
+
+/// Every thread of `process`, or just the main one if the threads couldn't be read.
+pub fn thread_ids(process: &Process) -> Vec<u64> {
+    if process.threads.is_empty() {
+        vec![process.pid]
+    } else {
+        process.threads.iter().map(|thread| thread.tid).collect()
+    }
+}
+
+/// CPUs the kernel knows about, including offline ones.
+pub fn cpu_count() -> usize {
+    // SAFETY: sysconf has no preconditions.
+    let count = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+    if count > 0 {
+        count as usize
+    } else {
+        1
+    }
+}
+
+/// `Cpus_allowed_list` from `/proc/[pid]/status`.
+pub fn parse_cpus_allowed(source: &dyn ProcSource, pid: u64) -> Result<Vec<usize>> {
+    let path = Path::new(&pid.to_string()).join("status");
+    let status = KeyValues::read(source, &path)?;
+    status
+        .get("Cpus_allowed_list")
+        .and_then(parse_cpu_list)
+        .ok_or_else(|| Error::malformed(&path, "missing Cpus_allowed_list"))
+}
+
+/// A list like `0-3,8,10-11`.
+pub fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in list.split(',').filter(|range| !range.is_empty()) {
+        match range.split_once('-') {
+            Some((first, last)) => cpus.extend(first.parse::<usize>().ok()?..=last.parse().ok()?),
+            None => cpus.push(range.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+/// The reverse of [`parse_cpu_list`], `cpus` must be sorted.
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == cpu => *last = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(first, last)| {
+            if first == last {
+                first.to_string()
+            } else {
+                format!("{}-{}", first, last)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Values being edited in the scheduling tab, taken from the process whenever another one is
+/// selected.
+#[cfg(feature = "gui")]
+pub struct SchedControls {
+    pid: Option<u64>,
+    nice: i32,
+    policy: Policy,
+    priority: u32,
+    deadline: Deadline,
+    cpus: Vec<bool>,
+    io: IoPriority,
+    /// Change every thread instead of just the main one.
+    pub all_threads: bool,
+    /// Outcome of the last change.
+    result: Option<std::result::Result<String, ThreadsError>>,
+    /// Set after a change so the process list can be refreshed right away.
+    pub changed: bool,
+}
+
+#[cfg(feature = "gui")]
+impl Default for SchedControls {
+    fn default() -> Self {
+        Self {
+            pid: None,
+            nice: 0,
+            policy: Policy::default(),
+            priority: 1,
+            deadline: Deadline::default(),
+            cpus: Vec::new(),
+            io: IoPriority::default(),
+            all_threads: true,
+            result: None,
+            changed: false,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl SchedControls {
+    fn reset(&mut self, process: &Process, cpus_allowed: &Result<Vec<usize>>) {
+        self.pid = Some(process.pid);
+        self.nice = process.stats.nice as i32;
+        self.policy = Policy::from_raw(process.stats.policy).unwrap_or_default();
+        self.priority = (process.stats.rt_priority as u32).max(1);
+        self.deadline = Deadline::default();
+        self.cpus = (0..cpu_count())
+            .map(|cpu| match cpus_allowed {
+                Ok(allowed) => allowed.contains(&cpu),
+                Err(_) => true,
+            })
+            .collect();
+        self.io = io_priority(process.pid).unwrap_or_default();
+        self.result = None;
+    }
+
+    fn apply(
+        &mut self,
+        process: &Process,
+        what: String,
+        change: impl Fn(u64) -> std::result::Result<(), SchedError>,
+    ) {
+        let tids = if self.all_threads {
+            thread_ids(process)
+        } else {
+            vec![process.pid]
+        };
+        self.result = Some(for_threads(&tids, change).map(|()| what));
+        self.changed = true;
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn show_scheduling(
+    ui: &mut Ui,
+    process: &Process,
+    cpus_allowed: &Result<Vec<usize>>,
+    controls: &mut SchedControls,
+    live: bool,
+) {
+    puffin::profile_function!();
+
+    let policy = Policy::from_raw(process.stats.policy);
+    egui::Grid::new("scheduling").striped(true).show(ui, |ui| {
+        let mut row = |name: &str, value: String, color: Color32| {
+            ui.label(RichText::new(name).color(Color32::WHITE));
+            ui.label(RichText::new(value).color(color));
+            ui.end_row();
+        };
+        row("Nice", process.stats.nice.to_string(), Color32::LIGHT_GRAY);
+        row(
+            "Priority",
+            process.stats.priority.to_string(),
+            Color32::LIGHT_GRAY,
+        );
+        row(
+            "Policy",
+            match policy {
+                Some(policy) if policy.is_realtime() => {
+                    format!("{}, priority {}", policy, process.stats.rt_priority)
+                }
+                Some(policy) => policy.to_string(),
+                None => format!("unknown ({})", process.stats.policy),
+            },
+            Color32::LIGHT_GRAY,
+        );
+        match cpus_allowed {
+            Ok(cpus) => row("Affinity", format_cpu_list(cpus), Color32::LIGHT_GRAY),
+            Err(err) => row("Affinity", err.to_string(), Color32::YELLOW),
+        }
+        if live {
+            match io_priority(process.pid) {
+                Ok(priority) => row("I/O priority", priority.to_string(), Color32::LIGHT_GRAY),
+                Err(err) => row("I/O priority", err.to_string(), Color32::YELLOW),
+            }
+        }
+    });
+    ui.separator();
+
+    if !live {
+        ui.label(
+            RichText::new("The processes of a recording can't be changed").color(Color32::YELLOW),
+        );
+        return;
+    }
+
+    if controls.pid != Some(process.pid) {
+        controls.reset(process, cpus_allowed);
+    }
+
+    ui.checkbox(
+        &mut controls.all_threads,
+        format!("All {} threads", process.threads.len()),
+    );
+
+    egui::Grid::new("scheduling_controls").show(ui, |ui| {
+        ui.label(RichText::new("Nice").color(Color32::WHITE));
+        ui.add(egui::DragValue::new(&mut controls.nice).clamp_range(-20..=19));
+        if ui.button("Renice").clicked() {
+            let nice = controls.nice;
+            controls.apply(process, format!("Set nice to {}", nice), |tid| {
+                set_nice(tid, nice)
+            });
+        }
+        ui.end_row();
+
+        ui.label(RichText::new("Policy").color(Color32::WHITE));
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("policy")
+                .selected_text(controls.policy.name())
+                .show_ui(ui, |ui| {
+                    for policy in Policy::ALL {
+                        ui.selectable_value(&mut controls.policy, policy, policy.name())
+                            .on_hover_text(policy.description());
+                    }
+                });
+            if controls.policy.is_realtime() {
+                ui.label(RichText::new("priority").color(Color32::WHITE));
+                ui.add(egui::DragValue::new(&mut controls.priority).clamp_range(1..=99));
+            }
+        });
+        let invalid = match controls.policy {
+            Policy::Deadline => controls.deadline.check().err(),
+            _ => None,
+        };
+        if ui
+            .add_enabled(invalid.is_none(), egui::Button::new("Apply"))
+            .on_disabled_hover_text(invalid.unwrap_or_default())
+            .clicked()
+        {
+            let (policy, priority, deadline) =
+                (controls.policy, controls.priority, controls.deadline);
+            controls.apply(process, format!("Set policy to {}", policy), |tid| {
+                set_policy(tid, policy, priority, deadline)
+            });
+        }
+        ui.end_row();
+
+        if controls.policy == Policy::Deadline {
+            ui.label("");
+            ui.horizontal(|ui| {
+                let deadline = &mut controls.deadline;
+                let max = Deadline::MAX_PERIOD.as_micros() as u64;
+                for (name, value, min) in [
+                    ("runtime", &mut deadline.runtime, 2),
+                    ("deadline", &mut deadline.deadline, 2),
+                    (
+                        "period",
+                        &mut deadline.period,
+                        Deadline::MIN_PERIOD.as_micros() as u64,
+                    ),
+                ] {
+                    ui.label(RichText::new(name).color(Color32::WHITE));
+                    let mut micros = value.as_micros() as u64;
+                    ui.add(
+                        egui::DragValue::new(&mut micros)
+                            .clamp_range(min..=max)
+                            .suffix("µs"),
+                    );
+                    *value = Duration::from_micros(micros);
+                }
+                if let Err(reason) = deadline.check() {
+                    ui.label(RichText::new(reason).color(Color32::YELLOW));
+                }
+            });
+            ui.end_row();
+        }
+
+        ui.label(RichText::new("Affinity").color(Color32::WHITE));
+        ui.horizontal_wrapped(|ui| {
+            for (cpu, allowed) in controls.cpus.iter_mut().enumerate() {
+                ui.checkbox(allowed, cpu.to_string());
+            }
+        });
+        let cpus: Vec<usize> = (0..controls.cpus.len())
+            .filter(|&cpu| controls.cpus[cpu])
+            .collect();
+        if ui
+            .add_enabled(!cpus.is_empty(), egui::Button::new("Apply"))
+            .on_disabled_hover_text("At least one CPU is needed")
+            .clicked()
+        {
+            controls.apply(
+                process,
+                format!("Set affinity to {}", format_cpu_list(&cpus)),
+                |tid| set_affinity(tid, &cpus),
+            );
+        }
+        ui.end_row();
+
+        ui.label(RichText::new("I/O priority").color(Color32::WHITE));
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("io_class")
+                .selected_text(controls.io.class.name())
+                .show_ui(ui, |ui| {
+                    for class in IoClass::ALL {
+                        ui.selectable_value(&mut controls.io.class, class, class.name());
+                    }
+                });
+            if controls.io.class.has_level() {
+                ui.label(RichText::new("level").color(Color32::WHITE));
+                ui.add(egui::DragValue::new(&mut controls.io.level).clamp_range(0..=7))
+                    .on_hover_text("0 is the highest");
+            }
+        });
+        if ui.button("Apply").clicked() {
+            let io = controls.io;
+            controls.apply(process, format!("Set I/O priority to {}", io), |tid| {
+                set_io_priority(tid, io)
+            });
+        }
+        ui.end_row();
+    });
+
+    match &controls.result {
+        Some(Ok(done)) => {
+            ui.label(RichText::new(done).color(Color32::WHITE));
+        }
+        Some(Err(err)) => {
+            ui.label(RichText::new(err.to_string()).color(Color32::RED));
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail_odd(tid: u64) -> std::result::Result<(), SchedError> {
+        if tid % 2 == 1 {
+            Err(SchedError {
+                tid,
+                kind: ErrorKind::PermissionDenied,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn every_thread_is_tried() {
+        let tried = std::cell::RefCell::new(Vec::new());
+        let err = for_threads(&[1, 2, 3, 4], |tid| {
+            tried.borrow_mut().push(tid);
+            fail_odd(tid)
+        })
+        .unwrap_err();
+        assert_eq!(tried.into_inner(), [1, 2, 3, 4]);
+        assert_eq!(err.changed, 2);
+        assert_eq!(
+            err.failed.iter().map(|err| err.tid).collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert!(err
+            .to_string()
+            .ends_with("(failed for 1, 3, 2 other threads were changed)"));
+
+        assert!(for_threads(&[2, 4], fail_odd).is_ok());
+        let single = for_threads(&[1], fail_odd).unwrap_err();
+        assert_eq!(single.to_string(), single.failed[0].to_string());
+        assert!(for_threads(&[1, 3], fail_odd)
+            .unwrap_err()
+            .to_string()
+            .ends_with("(failed for 1, 3, no thread was changed)"));
+        let many: Vec<u64> = (1..=21).step_by(2).collect();
+        assert!(for_threads(&many, fail_odd)
+            .unwrap_err()
+            .to_string()
+            .contains("13, 15, 3 more, no thread"));
+    }
+
+    #[test]
+    fn deadline_parameters() {
+        let ms = Duration::from_millis;
+        let deadline = |runtime, deadline, period| Deadline {
+            runtime: ms(runtime),
+            deadline: ms(deadline),
+            period: ms(period),
+        };
+        assert_eq!(Deadline::default().check(), Ok(()));
+        assert_eq!(deadline(10, 10, 10).check(), Ok(()));
+        assert!(deadline(0, 10, 10).check().is_err());
+        assert!(deadline(20, 10, 30).check().is_err());
+        assert!(deadline(10, 30, 20).check().is_err());
+
+        // The kernel's own limits.
+        let us = Duration::from_micros;
+        let tiny = Deadline {
+            runtime: Duration::from_nanos(1023),
+            ..Deadline::default()
+        };
+        assert!(tiny.check().is_err());
+        let shortest = Deadline {
+            runtime: Deadline::MIN_RUNTIME,
+            deadline: us(100),
+            period: us(100),
+        };
+        assert_eq!(shortest.check(), Ok(()));
+        let fast = Deadline {
+            period: us(99),
+            ..shortest
+        };
+        assert!(fast.check().is_err());
+        assert_eq!(deadline(10, 30, 4194).check(), Ok(()));
+        assert!(deadline(10, 30, 4195).check().is_err());
+
+        // Rejected before the syscall, whatever the thread.
+        let err = set_policy(
+            u64::from(std::process::id()),
+            Policy::Deadline,
+            0,
+            deadline(20, 10, 30),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}: the runtime can't be longer than the deadline",
+                std::process::id()
+            )
+        );
+    }
+}
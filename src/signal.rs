@@ -0,0 +1,218 @@
+use std::fmt::Display;
+
+use crate::{error::ErrorKind, process::Scan};
+
+/// A signal by number, see signal(7).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Signal(pub i32);
+
+impl Signal {
+    pub const HUP: Signal = Signal(libc::SIGHUP);
+    pub const INT: Signal = Signal(libc::SIGINT);
+    pub const KILL: Signal = Signal(libc::SIGKILL);
+    pub const TERM: Signal = Signal(libc::SIGTERM);
+    pub const STOP: Signal = Signal(libc::SIGSTOP);
+    pub const CONT: Signal = Signal(libc::SIGCONT);
+
+    /// The signals offered without opening the full list.
+    pub const COMMON: [Signal; 6] = [
+        Signal::TERM,
+        Signal::KILL,
+        Signal::STOP,
+        Signal::CONT,
+        Signal::HUP,
+        Signal::INT,
+    ];
+
+    /// Every signal that can be sent, the standard ones followed by the real-time ones.
+    pub fn all() -> impl Iterator<Item = Signal> {
+        (1..=31)
+            .chain(libc::SIGRTMIN()..=libc::SIGRTMAX())
+            .map(Signal)
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        let name = match self.0 {
+            libc::SIGHUP => "SIGHUP",
+            libc::SIGINT => "SIGINT",
+            libc::SIGQUIT => "SIGQUIT",
+            libc::SIGILL => "SIGILL",
+            libc::SIGTRAP => "SIGTRAP",
+            libc::SIGABRT => "SIGABRT",
+            libc::SIGBUS => "SIGBUS",
+            libc::SIGFPE => "SIGFPE",
+            libc::SIGKILL => "SIGKILL",
+            libc::SIGUSR1 => "SIGUSR1",
+            libc::SIGSEGV => "SIGSEGV",
+            libc::SIGUSR2 => "SIGUSR2",
+            libc::SIGPIPE => "SIGPIPE",
+            libc::SIGALRM => "SIGALRM",
+            libc::SIGTERM => "SIGTERM",
+            libc::SIGSTKFLT => "SIGSTKFLT",
+            libc::SIGCHLD => "SIGCHLD",
+            libc::SIGCONT => "SIGCONT",
+            libc::SIGSTOP => "SIGSTOP",
+            libc::SIGTSTP => "SIGTSTP",
+            libc::SIGTTIN => "SIGTTIN",
+            libc::SIGTTOU => "SIGTTOU",
+            libc::SIGURG => "SIGURG",
+            libc::SIGXCPU => "SIGXCPU",
+            libc::SIGXFSZ => "SIGXFSZ",
+            libc::SIGVTALRM => "SIGVTALRM",
+            libc::SIGPROF => "SIGPROF",
+            libc::SIGWINCH => "SIGWINCH",
+            libc::SIGIO => "SIGIO",
+            libc::SIGPWR => "SIGPWR",
+            libc::SIGSYS => "SIGSYS",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// What the signal usually does, for the menus.
+    pub fn description(&self) -> &'static str {
+        match self.0 {
+            libc::SIGHUP => "Hangup, many daemons reload their configuration",
+            libc::SIGINT => "Interrupt, like Ctrl+C",
+            libc::SIGQUIT => "Quit and dump core, like Ctrl+\\",
+            libc::SIGKILL => "Kill, can't be caught or ignored",
+            libc::SIGTERM => "Terminate, lets the process clean up",
+            libc::SIGSTOP => "Stop, can't be caught or ignored",
+            libc::SIGCONT => "Continue if stopped",
+            libc::SIGTSTP => "Stop, like Ctrl+Z",
+            libc::SIGUSR1 | libc::SIGUSR2 => "User-defined",
+            _ if self.0 >= libc::SIGRTMIN() => "Real-time, user-defined",
+            _ => "",
+        }
+    }
+}
+
+impl Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{} ({})", name, self.0),
+            None if self.0 >= libc::SIGRTMIN() => {
+                write!(f, "SIGRTMIN+{} ({})", self.0 - libc::SIGRTMIN(), self.0)
+            }
+            None => write!(f, "signal {}", self.0),
+        }
+    }
+}
+
+/// Failure to send a signal to a single process.
+#[derive(Debug, Clone)]
+pub struct SignalError {
+    pub pid: u64,
+    pub kind: ErrorKind,
+}
+
+impl Display for SignalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::PermissionDenied => write!(
+                f,
+                "{}: permission denied, the process belongs to another user",
+                self.pid
+            ),
+            ErrorKind::NoSuchProcess => {
+                write!(f, "{}: no such process, it already exited", self.pid)
+            }
+            ErrorKind::Io(kind) => write!(f, "{}: {}", self.pid, kind),
+            kind => write!(f, "{}: {:?}", self.pid, kind),
+        }
+    }
+}
+
+impl std::error::Error for SignalError {}
+
+/// Sends `signal` to the process or thread `pid` with kill(2).
+pub fn send(pid: u64, signal: Signal) -> Result<(), SignalError> {
+    // 0 and negative pids would address whole process groups.
+    let target = match i32::try_from(pid) {
+        Ok(target) if target > 0 => target,
+        _ => {
+            return Err(SignalError {
+                pid,
+                kind: ErrorKind::NoSuchProcess,
+            })
+        }
+    };
+
+    // SAFETY: kill has no memory safety requirements.
+    if unsafe { libc::kill(target, signal.0) } == 0 {
+        return Ok(());
+    }
+    Err(SignalError {
+        pid,
+        kind: ErrorKind::last_os_error(),
+    })
+}
+
+/// A signal waiting for confirmation.
+#[derive(Clone)]
+pub struct SignalRequest {
+    pub pids: Vec<u64>,
+    pub signal: Signal,
+}
+
+/// What `pid` is in `scan`, for the confirmation.
+pub fn describe_target(scan: &Scan, pid: u64) -> String {
+    match scan.find(pid) {
+        Some(process) if process.pid == pid => process.command(),
+        Some(process) => match process.threads.iter().find(|thread| thread.tid == pid) {
+            Some(thread) => format!(
+                "thread {} of {}, the whole process gets the signal",
+                thread.stats.tcomm, process.pid
+            ),
+            None => process.command(),
+        },
+        None => "exited".to_string(),
+    }
+}
+
+/// Why signalling `pid` deserves a second look.
+pub fn target_warning(pid: u64) -> Option<&'static str> {
+    if pid == std::process::id() as u64 {
+        Some("this is Linux Explorer itself")
+    } else if pid == 1 {
+        Some("init, the system may go down with it")
+    } else {
+        None
+    }
+}
+
+/// What happened after a [`SignalRequest`] was confirmed.
+pub struct SignalReport {
+    pub signal: Signal,
+    pub sent: usize,
+    pub errors: Vec<SignalError>,
+}
+
+impl SignalRequest {
+    pub fn send(&self) -> SignalReport {
+        let mut report = SignalReport {
+            signal: self.signal,
+            sent: 0,
+            errors: Vec::new(),
+        };
+        for &pid in &self.pids {
+            match send(pid, self.signal) {
+                Ok(()) => report.sent += 1,
+                Err(err) => report.errors.push(err),
+            }
+        }
+        report
+    }
+}
+
+impl Display for SignalReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Sent {} to {} of {} processes",
+            self.signal,
+            self.sent,
+            self.sent + self.errors.len()
+        )
+    }
+}
@@ -0,0 +1,596 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "gui")]
+use egui::{pos2, Color32, Rect, RichText, Sense, Ui, Vec2};
+
+use crate::{
+    error::{Error, Result},
+    procfs::{self, KeyValues, ProcSource},
+};
+#[cfg(feature = "gui")]
+use crate::{
+    history::{show_chart, SystemHistory, Unit},
+    time,
+    units::format_bytes,
+};
+
+/// Reads the `btime` line of `/proc/stat`.
+pub fn parse_boot_time(source: &dyn ProcSource) -> Result<SystemTime> {
+    let path = Path::new("stat");
+    let contents = procfs::read_to_string(source, path)?;
+
+    let btime = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .ok_or_else(|| Error::malformed(path, "missing btime"))?;
+    let btime = btime
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| Error::malformed(path, format!("invalid btime: {}", btime)))?;
+
+    Ok(UNIX_EPOCH + Duration::from_secs(btime))
+}
+
+/// Global memory usage from `/proc/meminfo`, in bytes.
+#[derive(Clone, Copy, Default)]
+pub struct MemInfo {
+    pub total: u64,
+    pub free: u64,
+    /// Estimate of what can be allocated without swapping, since 3.14.
+    pub available: Option<u64>,
+    pub buffers: u64,
+    /// Page cache, without `Shmem`, which shows up in there too.
+    pub cached: u64,
+    pub shmem: u64,
+    /// Slab memory that can be reclaimed, counted as cache by `free`.
+    pub slab_reclaimable: u64,
+    pub dirty: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    pub swap_cached: u64,
+}
+
+impl MemInfo {
+    pub fn parse(source: &dyn ProcSource) -> Result<Self> {
+        let path = Path::new("meminfo");
+        let meminfo = KeyValues::read(source, path)?;
+        let kb = |key| meminfo.kb(key).unwrap_or(0);
+
+        Ok(Self {
+            total: meminfo
+                .kb("MemTotal")
+                .ok_or_else(|| Error::malformed(path, "missing MemTotal"))?,
+            free: kb("MemFree"),
+            available: meminfo.kb("MemAvailable"),
+            buffers: kb("Buffers"),
+            cached: kb("Cached").saturating_sub(kb("Shmem")),
+            shmem: kb("Shmem"),
+            slab_reclaimable: kb("SReclaimable"),
+            dirty: kb("Dirty"),
+            swap_total: kb("SwapTotal"),
+            swap_free: kb("SwapFree"),
+            swap_cached: kb("SwapCached"),
+        })
+    }
+
+    /// Used the way `free` counts it, everything that isn't free, buffers or cache.
+    pub fn used(&self) -> u64 {
+        self.total
+            .saturating_sub(self.free)
+            .saturating_sub(self.buffers)
+            .saturating_sub(self.cached)
+            .saturating_sub(self.slab_reclaimable)
+    }
+
+    /// The memory bar without free, so that they add up to the total. Shmem is taken out of used,
+    /// which counts it.
+    pub fn parts(&self) -> [(&'static str, u64); 4] {
+        [
+            ("Used", self.used().saturating_sub(self.shmem)),
+            ("Buffers", self.buffers),
+            ("Cache", self.cached + self.slab_reclaimable),
+            ("Shmem", self.shmem),
+        ]
+    }
+
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+}
+
+/// One `cpu` line of `/proc/stat`, in clock ticks.
+#[derive(Clone, Copy, Default)]
+pub struct CpuStat {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+    /// Already included in `user`.
+    pub guest: u64,
+    /// Already included in `nice`.
+    pub guest_nice: u64,
+}
+
+impl CpuStat {
+    fn parse(fields: &[u64]) -> Self {
+        let field = |i: usize| fields.get(i).copied().unwrap_or(0);
+        Self {
+            user: field(0),
+            nice: field(1),
+            system: field(2),
+            idle: field(3),
+            iowait: field(4),
+            irq: field(5),
+            softirq: field(6),
+            steal: field(7),
+            guest: field(8),
+            guest_nice: field(9),
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.busy() + self.idle + self.iowait
+    }
+
+    /// Everything but idle and iowait.
+    pub fn busy(&self) -> u64 {
+        self.user + self.nice + self.system + self.irq + self.softirq + self.steal
+    }
+
+    /// Percentage of the time between `previous` and `self` that the CPU was busy.
+    pub fn usage_since(&self, previous: &CpuStat) -> f64 {
+        let total = self.total().saturating_sub(previous.total());
+        if total == 0 {
+            return 0.0;
+        }
+        self.busy().saturating_sub(previous.busy()) as f64 / total as f64 * 100.0
+    }
+}
+
+/// The counters of `/proc/stat`.
+#[derive(Clone, Default)]
+pub struct KernelStat {
+    pub total: CpuStat,
+    /// Indexed by the number in `cpuN`, offline CPUs are missing.
+    pub cpus: Vec<(usize, CpuStat)>,
+    /// Context switches since boot.
+    pub ctxt: u64,
+    /// Interrupts since boot.
+    pub intr: u64,
+    /// Forks since boot.
+    pub processes: u64,
+    pub procs_running: u64,
+    pub procs_blocked: u64,
+}
+
+impl KernelStat {
+    pub fn parse(source: &dyn ProcSource) -> Result<Self> {
+        let path = Path::new("stat");
+        let contents = procfs::read_to_string(source, path)?;
+
+        let mut stat = KernelStat::default();
+        for line in contents.lines() {
+            let mut fields = line.split_ascii_whitespace();
+            let Some(name) = fields.next() else {
+                continue;
+            };
+            let values = fields
+                .map(|f| f.parse::<u64>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|_| Error::malformed(path, format!("invalid {} line", name)))?;
+            let first = values.first().copied().unwrap_or(0);
+
+            match name {
+                "cpu" => stat.total = CpuStat::parse(&values),
+                "ctxt" => stat.ctxt = first,
+                "intr" => stat.intr = first,
+                "processes" => stat.processes = first,
+                "procs_running" => stat.procs_running = first,
+                "procs_blocked" => stat.procs_blocked = first,
+                name => {
+                    if let Some(cpu) = name.strip_prefix("cpu").and_then(|n| n.parse().ok()) {
+                        stat.cpus.push((cpu, CpuStat::parse(&values)));
+                    }
+                }
+            }
+        }
+
+        Ok(stat)
+    }
+}
+
+/// `/proc/loadavg`.
+#[derive(Clone, Copy, Default)]
+pub struct LoadAvg {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+    /// Runnable threads.
+    pub running: u64,
+    /// All threads.
+    pub threads: u64,
+    pub last_pid: u64,
+}
+
+impl LoadAvg {
+    pub fn parse(source: &dyn ProcSource) -> Result<Self> {
+        let path = Path::new("loadavg");
+        let contents = procfs::read_to_string(source, path)?;
+        let malformed = || Error::malformed(path, format!("invalid loadavg: {}", contents.trim()));
+
+        let fields: Vec<&str> = contents.split_ascii_whitespace().collect();
+        let [one, five, fifteen, threads, last_pid] = fields[..] else {
+            return Err(malformed());
+        };
+        let (running, threads) = threads.split_once('/').ok_or_else(malformed)?;
+
+        let number = |s: &str| s.parse().map_err(|_| malformed());
+        let float = |s: &str| s.parse().map_err(|_| malformed());
+        Ok(Self {
+            one: float(one)?,
+            five: float(five)?,
+            fifteen: float(fifteen)?,
+            running: number(running)?,
+            threads: number(threads)?,
+            last_pid: number(last_pid)?,
+        })
+    }
+}
+
+/// `/proc/uptime`.
+#[derive(Clone, Copy, Default)]
+pub struct Uptime {
+    pub uptime: Duration,
+    /// Summed over all CPUs, so it can be larger than the uptime.
+    pub idle: Duration,
+}
+
+impl Uptime {
+    pub fn parse(source: &dyn ProcSource) -> Result<Self> {
+        let path = Path::new("uptime");
+        let contents = procfs::read_to_string(source, path)?;
+
+        let seconds = contents
+            .split_ascii_whitespace()
+            .map(|f| f.parse::<f64>().ok().map(Duration::from_secs_f64))
+            .collect::<Option<Vec<_>>>();
+        let Some(&[uptime, idle]) = seconds.as_deref() else {
+            return Err(Error::malformed(
+                path,
+                format!("invalid uptime: {}", contents.trim()),
+            ));
+        };
+
+        Ok(Self { uptime, idle })
+    }
+}
+
+/// Everything the system dashboard shows.
+#[derive(Clone, Default)]
+pub struct SystemInfo {
+    pub meminfo: MemInfo,
+    pub stat: KernelStat,
+    pub loadavg: LoadAvg,
+    pub uptime: Uptime,
+    /// Set from the second sample on, see [`SystemTracker`].
+    pub rates: Option<SystemRates>,
+}
+
+pub fn parse_system(source: &dyn ProcSource) -> Result<SystemInfo> {
+    puffin::profile_function!();
+
+    Ok(SystemInfo {
+        meminfo: MemInfo::parse(source)?,
+        stat: KernelStat::parse(source)?,
+        loadavg: LoadAvg::parse(source)?,
+        uptime: Uptime::parse(source)?,
+        rates: None,
+    })
+}
+
+/// Usage and per-second rates between two samples.
+#[derive(Clone, Default)]
+pub struct SystemRates {
+    pub cpu_usage: f64,
+    /// In the order of [`KernelStat::cpus`].
+    pub cpu_usages: Vec<f64>,
+    pub ctxt_per_second: f64,
+    pub intr_per_second: f64,
+    pub forks_per_second: f64,
+}
+
+/// Remembers the previous `/proc/stat` to turn its counters into rates.
+///
+/// Time is measured with `/proc/uptime`, so rates come out right for recorded samples too.
+#[derive(Default)]
+pub struct SystemTracker {
+    previous: Option<(KernelStat, Duration)>,
+}
+
+impl SystemTracker {
+    pub fn update(&mut self, info: &mut SystemInfo) {
+        let now = info.uptime.uptime;
+
+        if let Some((previous, previous_uptime)) = &self.previous {
+            let elapsed = now.saturating_sub(*previous_uptime).as_secs_f64().max(1e-3);
+            let rate =
+                |current: u64, previous: u64| current.saturating_sub(previous) as f64 / elapsed;
+
+            let cpu_usages = info
+                .stat
+                .cpus
+                .iter()
+                .map(|(cpu, current)| {
+                    previous
+                        .cpus
+                        .iter()
+                        .find(|(c, _)| c == cpu)
+                        .map(|(_, previous)| current.usage_since(previous))
+                        .unwrap_or(0.0)
+                })
+                .collect();
+
+            info.rates = Some(SystemRates {
+                cpu_usage: info.stat.total.usage_since(&previous.total),
+                cpu_usages,
+                ctxt_per_second: rate(info.stat.ctxt, previous.ctxt),
+                intr_per_second: rate(info.stat.intr, previous.intr),
+                forks_per_second: rate(info.stat.processes, previous.processes),
+            });
+        }
+
+        self.previous = Some((info.stat.clone(), now));
+    }
+}
+
+#[cfg(feature = "gui")]
+/// `now` is the end of the charts, when the frame was taken while replaying.
+pub fn show_system(ui: &mut Ui, info: &SystemInfo, history: &SystemHistory, now: SystemTime) {
+    puffin::profile_function!();
+
+    let label = |ui: &mut Ui, text: &str| {
+        ui.label(RichText::new(text).color(Color32::WHITE));
+    };
+    let value = |ui: &mut Ui, text: String| {
+        ui.label(RichText::new(text).color(Color32::LIGHT_GRAY));
+    };
+
+    egui::Grid::new("system").striped(true).show(ui, |ui| {
+        label(ui, "Uptime");
+        value(ui, time::format_duration(info.uptime.uptime));
+        label(ui, "Load");
+        value(
+            ui,
+            format!(
+                "{:.2} {:.2} {:.2}",
+                info.loadavg.one, info.loadavg.five, info.loadavg.fifteen
+            ),
+        );
+        ui.end_row();
+
+        label(ui, "Running");
+        value(ui, info.stat.procs_running.to_string());
+        label(ui, "Blocked");
+        value(ui, info.stat.procs_blocked.to_string());
+        ui.end_row();
+
+        label(ui, "Threads");
+        value(ui, info.loadavg.threads.to_string());
+        label(ui, "Last pid");
+        value(ui, info.loadavg.last_pid.to_string());
+        ui.end_row();
+
+        let rate = |rate: Option<f64>| match rate {
+            Some(rate) => format!("{:.0}/s", rate),
+            None => "-".to_string(),
+        };
+        label(ui, "Context switches");
+        value(ui, rate(info.rates.as_ref().map(|r| r.ctxt_per_second)));
+        label(ui, "Interrupts");
+        value(ui, rate(info.rates.as_ref().map(|r| r.intr_per_second)));
+        ui.end_row();
+
+        label(ui, "Forks");
+        value(ui, rate(info.rates.as_ref().map(|r| r.forks_per_second)));
+        ui.end_row();
+    });
+
+    ui.separator();
+    show_memory(ui, &info.meminfo);
+
+    ui.separator();
+    show_chart(
+        ui,
+        "system_cpu",
+        Unit::Percent,
+        now,
+        &[("CPU%", &history.cpu)],
+    );
+    show_chart(
+        ui,
+        "system_memory",
+        Unit::Bytes,
+        now,
+        &[("Used", &history.memory), ("Swap", &history.swap)],
+    );
+    show_chart(
+        ui,
+        "system_load",
+        Unit::Plain,
+        now,
+        &[("Load 1m", &history.load)],
+    );
+
+    ui.separator();
+    ui.label(RichText::new("CPU").color(Color32::WHITE).strong());
+    let Some(rates) = &info.rates else {
+        ui.label(RichText::new("Waiting for the second sample").color(Color32::GRAY));
+        return;
+    };
+
+    show_usage_bar(ui, "all", rates.cpu_usage);
+    for ((cpu, _), usage) in info.stat.cpus.iter().zip(&rates.cpu_usages) {
+        show_usage_bar(ui, &format!("cpu{}", cpu), *usage);
+    }
+}
+
+#[cfg(feature = "gui")]
+fn show_usage_bar(ui: &mut Ui, name: &str, usage: f64) {
+    ui.horizontal(|ui| {
+        ui.add_sized(
+            [60.0, ui.spacing().interact_size.y],
+            egui::Label::new(RichText::new(name).color(Color32::WHITE)),
+        );
+        ui.add(
+            egui::ProgressBar::new((usage / 100.0) as f32)
+                .desired_width(300.0)
+                .text(format!("{:.1}%", usage)),
+        );
+    });
+}
+
+/// A bar split into used, buffers, cache, shmem and free, and one for swap.
+#[cfg(feature = "gui")]
+fn show_memory(ui: &mut Ui, meminfo: &MemInfo) {
+    const USED: Color32 = Color32::from_rgb(0xe0, 0x50, 0x40);
+    const BUFFERS: Color32 = Color32::from_rgb(0x4e, 0x9a, 0xe0);
+    const CACHE: Color32 = Color32::from_rgb(0xc0, 0x80, 0x30);
+    const SHMEM: Color32 = Color32::from_rgb(0xa0, 0x60, 0xc0);
+    const SWAP: Color32 = Color32::from_rgb(0x50, 0xb0, 0x50);
+
+    ui.label(RichText::new("Memory").color(Color32::WHITE).strong());
+    let [used, buffers, cache, shmem] = meminfo.parts();
+    let memory = [
+        (used.0, used.1, USED),
+        (buffers.0, buffers.1, BUFFERS),
+        (cache.0, cache.1, CACHE),
+        (shmem.0, shmem.1, SHMEM),
+    ];
+    show_stacked_bar(ui, meminfo.total, &memory);
+
+    ui.horizontal_wrapped(|ui| {
+        for (name, bytes, color) in memory {
+            ui.label(RichText::new("■").color(color));
+            ui.label(
+                RichText::new(format!("{} {}", name, format_bytes(bytes)))
+                    .color(Color32::LIGHT_GRAY),
+            );
+        }
+        ui.label(
+            RichText::new(format!(
+                "Free {}  Available {}  Dirty {}  Total {}",
+                format_bytes(meminfo.free),
+                meminfo
+                    .available
+                    .map(format_bytes)
+                    .unwrap_or_else(|| "-".to_string()),
+                format_bytes(meminfo.dirty),
+                format_bytes(meminfo.total),
+            ))
+            .color(Color32::LIGHT_GRAY),
+        );
+    });
+
+    ui.label(RichText::new("Swap").color(Color32::WHITE).strong());
+    if meminfo.swap_total == 0 {
+        ui.label(RichText::new("No swap").color(Color32::GRAY));
+        return;
+    }
+    show_stacked_bar(
+        ui,
+        meminfo.swap_total,
+        &[("Used", meminfo.swap_used(), SWAP)],
+    );
+    ui.label(
+        RichText::new(format!(
+            "Used {}  Cached {}  Total {}",
+            format_bytes(meminfo.swap_used()),
+            format_bytes(meminfo.swap_cached),
+            format_bytes(meminfo.swap_total),
+        ))
+        .color(Color32::LIGHT_GRAY),
+    );
+}
+
+#[cfg(feature = "gui")]
+fn show_stacked_bar(ui: &mut Ui, total: u64, parts: &[(&str, u64, Color32)]) {
+    let (rect, response) = ui.allocate_exact_size(
+        Vec2::new(ui.available_width().min(600.0), 20.0),
+        Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::from_gray(30));
+
+    let total = total.max(1) as f32;
+    let mut x = rect.left();
+    for (name, bytes, color) in parts {
+        let width = *bytes as f32 / total * rect.width();
+        let part = Rect::from_min_max(pos2(x, rect.top()), pos2(x + width, rect.bottom()));
+        painter.rect_filled(part, 0.0, *color);
+        if response.hover_pos().is_some_and(|pos| part.contains(pos)) {
+            response
+                .clone()
+                .on_hover_text_at_pointer(format!("{} {}", name, format_bytes(*bytes)));
+        }
+        x += width;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs::MemorySource;
+
+    #[test]
+    fn guest_time_is_counted_once() {
+        let source = MemorySource::new().with(
+            "stat",
+            "cpu  100 10 50 800 20 5 5 10 40 4\n\
+             cpu0 100 10 50 800 20 5 5 10 40 4\n\
+             ctxt 1234\nprocesses 99\nprocs_running 2\n",
+        );
+        let stat = KernelStat::parse(&source).unwrap();
+
+        assert_eq!(stat.total.busy(), 180);
+        assert_eq!(stat.total.total(), 1000);
+        assert_eq!(stat.cpus.len(), 1);
+        assert_eq!(stat.ctxt, 1234);
+        assert_eq!(stat.processes, 99);
+        assert_eq!(stat.procs_running, 2);
+    }
+
+    #[test]
+    fn memory_adds_up() {
+        let source = MemorySource::new().with(
+            "meminfo",
+            "MemTotal:       16000000 kB\n\
+             MemFree:         2000000 kB\n\
+             MemAvailable:    9000000 kB\n\
+             Buffers:          500000 kB\n\
+             Cached:          6000000 kB\n\
+             SwapCached:        10000 kB\n\
+             SwapTotal:       4000000 kB\n\
+             SwapFree:        3000000 kB\n\
+             Dirty:              1000 kB\n\
+             Shmem:           1500000 kB\n\
+             SReclaimable:     700000 kB\n",
+        );
+        let meminfo = MemInfo::parse(&source).unwrap();
+
+        let [used, buffers, cache, shmem] = meminfo.parts().map(|(_, bytes)| bytes);
+        assert_eq!(used + buffers + cache + shmem + meminfo.free, meminfo.total);
+        assert_eq!(shmem, 1_500_000 * 1024);
+        assert_eq!(cache, (4_500_000 + 700_000) * 1024);
+        assert_eq!(used, 6_800_000 * 1024);
+        // Shmem is used memory even though the kernel counts it as cache.
+        assert_eq!(meminfo.used(), used + shmem);
+        assert_eq!(meminfo.swap_used(), 1_000_000 * 1024);
+    }
+}
@@ -0,0 +1,693 @@
+use std::{borrow::Cow, cmp::Ordering, collections::HashSet};
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Sense, TextStyle, Ui};
+#[cfg(feature = "gui")]
+use egui_extras::TableBuilder;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+use crate::{
+    history::{show_sparkline, History},
+    signal::{Signal, SignalRequest},
+};
+use crate::{
+    process::Process,
+    query::Query,
+    time,
+    tree::{with_threads, ProcessTree, TreeRow},
+    units::format_bytes,
+    users::{group_name, user_name},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Column {
+    Pid,
+    /// The pid inside the innermost pid namespace.
+    NsPid,
+    User,
+    Group,
+    State,
+    Cpu,
+    CpuHistory,
+    Rss,
+    RssHistory,
+    Pss,
+    Uss,
+    Swap,
+    Threads,
+    StartTime,
+    Command,
+}
+
+impl Column {
+    pub const ALL: [Column; 15] = [
+        Column::Pid,
+        Column::NsPid,
+        Column::User,
+        Column::Group,
+        Column::State,
+        Column::Cpu,
+        Column::CpuHistory,
+        Column::Rss,
+        Column::RssHistory,
+        Column::Pss,
+        Column::Uss,
+        Column::Swap,
+        Column::Threads,
+        Column::StartTime,
+        Column::Command,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::NsPid => "NS PID",
+            Column::User => "User",
+            Column::Group => "Group",
+            Column::State => "State",
+            Column::Cpu => "CPU%",
+            Column::CpuHistory => "CPU history",
+            Column::Rss => "RSS",
+            Column::RssHistory => "RSS history",
+            Column::Pss => "PSS",
+            Column::Uss => "USS",
+            Column::Swap => "Swap",
+            Column::Threads => "Threads",
+            Column::StartTime => "Started",
+            Column::Command => "Command",
+        }
+    }
+
+    /// Lowercase name for the command line, e.g. `--sort rss`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Column::Pid => "pid",
+            Column::NsPid => "nspid",
+            Column::User => "user",
+            Column::Group => "group",
+            Column::State => "state",
+            Column::Cpu => "cpu",
+            Column::CpuHistory => "cpu_history",
+            Column::Rss => "rss",
+            Column::RssHistory => "rss_history",
+            Column::Pss => "pss",
+            Column::Uss => "uss",
+            Column::Swap => "swap",
+            Column::Threads => "threads",
+            Column::StartTime => "started",
+            Column::Command => "command",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Column> {
+        Column::ALL.into_iter().find(|column| column.key() == key)
+    }
+
+    /// Only shown as a picture.
+    pub fn is_history(&self) -> bool {
+        matches!(self, Column::CpuHistory | Column::RssHistory)
+    }
+
+    /// The cell as text, the way the table shows it.
+    pub fn text(&self, process: &Process) -> String {
+        match self {
+            Column::Pid => process.pid.to_string(),
+            Column::NsPid => process.ns_pid().to_string(),
+            Column::User => match process.uid() {
+                Some(uid) => user_name(uid),
+                None => "?".to_string(),
+            },
+            Column::Group => match process.gid() {
+                Some(gid) => group_name(gid),
+                None => "?".to_string(),
+            },
+            Column::State => process.stats.state.to_string(),
+            Column::Cpu => match process.cpu_usage {
+                Some(usage) => format!("{:.1}", usage),
+                None => "-".to_string(),
+            },
+            Column::Rss => format_bytes(process.stats.rss_bytes()),
+            Column::Pss => optional_bytes(process.memory.pss()),
+            Column::Uss => optional_bytes(process.memory.uss()),
+            Column::Swap => optional_bytes(process.memory.swap()),
+            Column::Threads => process.stats.num_threads.to_string(),
+            Column::StartTime => match process.start_time {
+                Some(start_time) => time::format_local_date_time(start_time),
+                None => "?".to_string(),
+            },
+            Column::Command => process.command(),
+            Column::CpuHistory | Column::RssHistory => String::new(),
+        }
+    }
+
+    pub fn compare(&self, a: &Process, b: &Process) -> Ordering {
+        match self {
+            Column::Pid => a.pid.cmp(&b.pid),
+            Column::NsPid => a.ns_pid().cmp(&b.ns_pid()),
+            Column::User | Column::Group => self.text(a).cmp(&self.text(b)),
+            Column::State => a.stats.state.to_string().cmp(&b.stats.state.to_string()),
+            Column::Cpu | Column::CpuHistory => a
+                .cpu_usage
+                .unwrap_or(0.0)
+                .total_cmp(&b.cpu_usage.unwrap_or(0.0)),
+            Column::Rss | Column::RssHistory => a.stats.rss.cmp(&b.stats.rss),
+            Column::Pss => a.memory.pss().cmp(&b.memory.pss()),
+            Column::Uss => a.memory.uss().cmp(&b.memory.uss()),
+            Column::Swap => a.memory.swap().cmp(&b.memory.swap()),
+            Column::Threads => a.stats.num_threads.cmp(&b.stats.num_threads),
+            Column::StartTime => a.stats.starttime.cmp(&b.stats.starttime),
+            Column::Command => a.command().cmp(&b.command()),
+        }
+    }
+
+    /// Busy and big processes are the interesting ones, so those columns start out descending.
+    pub fn default_ascending(&self) -> bool {
+        !matches!(
+            self,
+            Column::Cpu
+                | Column::CpuHistory
+                | Column::Rss
+                | Column::RssHistory
+                | Column::Pss
+                | Column::Uss
+                | Column::Swap
+                | Column::Threads
+        )
+    }
+
+    /// Filled in from `smaps_rollup`, see [`ScanOptions::rollup`](crate::process::ScanOptions).
+    pub fn needs_rollup(&self) -> bool {
+        matches!(self, Column::Pss | Column::Uss | Column::Swap)
+    }
+
+    /// Columns that are shown until the user decides otherwise.
+    fn visible_by_default(&self) -> bool {
+        !matches!(
+            self,
+            Column::NsPid | Column::Group | Column::Uss | Column::Swap
+        )
+    }
+
+    #[cfg(feature = "gui")]
+    fn initial_width(&self) -> f32 {
+        match self {
+            Column::Pid | Column::NsPid => 70.0,
+            Column::User | Column::Group => 80.0,
+            Column::State => 130.0,
+            Column::Cpu => 70.0,
+            Column::CpuHistory | Column::RssHistory => 100.0,
+            Column::Rss | Column::Pss | Column::Uss | Column::Swap => 80.0,
+            Column::Threads => 70.0,
+            Column::StartTime => 170.0,
+            Column::Command => 400.0,
+        }
+    }
+}
+
+/// Order, visibility and sorting of the table columns, persisted between sessions.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ColumnSettings {
+    /// Every column in display order, with whether it is visible.
+    pub columns: Vec<(Column, bool)>,
+    pub sort_column: Column,
+    pub sort_ascending: bool,
+}
+
+impl Default for ColumnSettings {
+    fn default() -> Self {
+        Self {
+            columns: Column::ALL
+                .iter()
+                .map(|&c| (c, c.visible_by_default()))
+                .collect(),
+            sort_column: Column::Pid,
+            sort_ascending: true,
+        }
+    }
+}
+
+impl ColumnSettings {
+    /// Adds columns that didn't exist yet when the settings were saved, and makes sure that at
+    /// least one column is visible.
+    pub fn normalize(&mut self) {
+        self.columns.retain(|(c, _)| Column::ALL.contains(c));
+        for column in Column::ALL {
+            if !self.columns.iter().any(|(c, _)| *c == column) {
+                self.columns.push((column, false));
+            }
+        }
+
+        if !self.columns.iter().any(|(_, visible)| *visible) {
+            self.columns[0].1 = true;
+        }
+    }
+
+    pub fn visible(&self) -> Vec<Column> {
+        self.columns
+            .iter()
+            .filter(|(_, visible)| *visible)
+            .map(|(c, _)| *c)
+            .collect()
+    }
+
+    /// Whether a visible or the sort column needs `smaps_rollup`.
+    pub fn needs_rollup(&self) -> bool {
+        self.sort_column.needs_rollup()
+            || self
+                .columns
+                .iter()
+                .any(|(column, visible)| *visible && column.needs_rollup())
+    }
+
+    pub fn compare(&self, a: &Process, b: &Process) -> Ordering {
+        let ordering = self.sort_column.compare(a, b);
+        if self.sort_ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    }
+
+    /// Sorts by `column`, or flips the direction if we already do.
+    pub fn sort_by(&mut self, column: Column) {
+        if self.sort_column == column {
+            self.sort_ascending = !self.sort_ascending;
+        } else {
+            self.sort_column = column;
+            self.sort_ascending = column.default_ascending();
+        }
+    }
+
+    /// Checkboxes for visibility and buttons for moving columns around.
+    #[cfg(feature = "gui")]
+    pub fn show_menu(&mut self, ui: &mut Ui) {
+        let mut swap = None;
+        let last = self.columns.len() - 1;
+
+        for (i, (column, visible)) in self.columns.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(i > 0, egui::Button::new("⏶").small())
+                    .clicked()
+                {
+                    swap = Some((i - 1, i));
+                }
+                if ui
+                    .add_enabled(i < last, egui::Button::new("⏷").small())
+                    .clicked()
+                {
+                    swap = Some((i, i + 1));
+                }
+                ui.checkbox(visible, column.name());
+            });
+        }
+
+        if let Some((a, b)) = swap {
+            self.columns.swap(a, b);
+        }
+        self.normalize();
+    }
+}
+
+/// The rows of the process list after searching, sorting and laying out the tree, shared by
+/// the frontends.
+pub struct ProcessRows<'a> {
+    /// Borrowed from the scan, only threads shown as rows are owned.
+    pub processes: Vec<Cow<'a, Process>>,
+    pub tree: Option<ProcessTree>,
+    pub rows: Vec<TreeRow>,
+}
+
+impl<'a> ProcessRows<'a> {
+    /// `tree_mode` is ignored while threads are shown as rows.
+    pub fn build(
+        processes: &'a [Process],
+        query: &Query,
+        columns: &ColumnSettings,
+        tree_mode: bool,
+        threads_as_rows: bool,
+        collapsed: &HashSet<u64>,
+        expanded_threads: &HashSet<u64>,
+    ) -> Self {
+        puffin::profile_function!();
+
+        let tree_mode = tree_mode && !threads_as_rows;
+
+        // The tree needs every process to find the ancestors of search hits.
+        let mut processes: Vec<Cow<Process>> = if threads_as_rows {
+            processes
+                .iter()
+                .flat_map(|p| p.threads.iter().map(|t| t.as_process(p)))
+                .filter(|p| query.matches(p))
+                .map(Cow::Owned)
+                .collect()
+        } else if query.is_empty() || tree_mode {
+            processes.iter().map(Cow::Borrowed).collect()
+        } else {
+            processes
+                .iter()
+                .filter(|p| query.matches(p))
+                .map(Cow::Borrowed)
+                .collect()
+        };
+
+        processes.sort_by(|a, b| columns.compare(a, b));
+
+        let tree = tree_mode.then(|| ProcessTree::build(&processes));
+        let rows: Vec<TreeRow> = match &tree {
+            Some(tree) => {
+                let visible: Vec<bool> = if query.is_empty() {
+                    vec![true; processes.len()]
+                } else {
+                    let matches: Vec<bool> = processes.iter().map(|p| query.matches(p)).collect();
+                    tree.with_ancestors(&matches)
+                };
+                tree.flatten(&visible, |i| !collapsed.contains(&processes[i].pid))
+            }
+            None => (0..processes.len())
+                .map(|index| TreeRow {
+                    index,
+                    depth: 0,
+                    thread: None,
+                })
+                .collect(),
+        };
+        let rows = with_threads(rows, &processes, |i| {
+            expanded_threads.contains(&processes[i].pid)
+        });
+
+        Self {
+            processes,
+            tree,
+            rows,
+        }
+    }
+}
+
+/// The process list, either flat or as a tree.
+#[cfg(feature = "gui")]
+pub struct ProcessTable<'a> {
+    pub processes: &'a [Cow<'a, Process>],
+    pub rows: &'a [TreeRow],
+    /// Only set in tree mode.
+    pub tree: Option<&'a ProcessTree>,
+    pub settings: &'a mut ColumnSettings,
+    pub collapsed: &'a mut HashSet<u64>,
+    /// Pids of processes whose threads are shown below them.
+    pub expanded_threads: &'a mut HashSet<u64>,
+    pub selected: &'a mut Option<u64>,
+    /// Pids picked with Ctrl+click, signals go to all of them.
+    pub marked: &'a mut HashSet<u64>,
+    /// Where the context menu puts the signal to confirm, `None` disables the menu.
+    pub signal: Option<&'a mut Option<SignalRequest>>,
+    pub history: &'a History,
+}
+
+#[cfg(feature = "gui")]
+impl<'a> ProcessTable<'a> {
+    pub fn show(mut self, ui: &mut Ui) {
+        puffin::profile_function!();
+
+        let columns = self.settings.visible();
+        let row_height = ui.text_style_height(&TextStyle::Body) + 4.0;
+
+        let mut builder = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .sense(Sense::click())
+            .auto_shrink(false);
+        for (i, column) in columns.iter().enumerate() {
+            builder = if i == columns.len() - 1 {
+                builder.column(egui_extras::Column::remainder().clip(true))
+            } else {
+                builder.column(egui_extras::Column::initial(column.initial_width()).clip(true))
+            };
+        }
+
+        let settings = self.settings;
+        let mut sort_by = None;
+        builder
+            .header(row_height, |mut header| {
+                for column in &columns {
+                    header.col(|ui| {
+                        let arrow = match (settings.sort_column == *column, settings.sort_ascending)
+                        {
+                            (false, _) => "",
+                            (true, true) => " ⏶",
+                            (true, false) => " ⏷",
+                        };
+
+                        let text = RichText::new(format!("{}{}", column.name(), arrow))
+                            .color(Color32::WHITE)
+                            .strong();
+                        if ui
+                            .add(egui::Label::new(text).sense(Sense::click()))
+                            .clicked()
+                        {
+                            sort_by = Some(*column);
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(row_height, self.rows.len(), |mut table_row| {
+                    let row = self.rows[table_row.index()];
+                    let thread_process;
+                    let process = match row.thread {
+                        Some(thread) => {
+                            let process = &self.processes[row.index];
+                            thread_process = process.threads[thread].as_process(process);
+                            &thread_process
+                        }
+                        None => &self.processes[row.index],
+                    };
+                    table_row.set_selected(
+                        *self.selected == Some(process.pid) || self.marked.contains(&process.pid),
+                    );
+
+                    for column in &columns {
+                        table_row.col(|ui| match column {
+                            Column::Command => show_command(
+                                ui,
+                                process,
+                                row,
+                                self.tree,
+                                self.collapsed,
+                                self.expanded_threads,
+                            ),
+                            Column::CpuHistory | Column::RssHistory => {
+                                if row.thread.is_none() {
+                                    show_history(ui, process, *column, self.history);
+                                }
+                            }
+                            column => show_cell(ui, process, *column),
+                        });
+                    }
+
+                    let response = table_row.response();
+                    if response.clicked() {
+                        if response.ctx.input(|input| input.modifiers.command) {
+                            if !self.marked.remove(&process.pid) {
+                                self.marked.insert(process.pid);
+                            }
+                        } else {
+                            self.marked.clear();
+                            *self.selected = match *self.selected {
+                                Some(pid) if pid == process.pid => None,
+                                _ => Some(process.pid),
+                            };
+                        }
+                    }
+
+                    if let Some(signal) = &mut self.signal {
+                        // The marked processes if the menu was opened on one of them.
+                        let pids: Vec<u64> = if self.marked.contains(&process.pid) {
+                            let mut pids: Vec<u64> = self.marked.iter().copied().collect();
+                            pids.sort();
+                            pids
+                        } else {
+                            vec![process.pid]
+                        };
+                        response.context_menu(|ui| {
+                            if let Some(picked) = show_signal_menu(ui, pids.len()) {
+                                **signal = Some(SignalRequest {
+                                    pids,
+                                    signal: picked,
+                                });
+                                ui.close_menu();
+                            }
+                        });
+                    }
+                });
+            });
+
+        if let Some(column) = sort_by {
+            settings.sort_by(column);
+        }
+    }
+}
+
+/// Returns the signal that was clicked.
+#[cfg(feature = "gui")]
+fn show_signal_menu(ui: &mut Ui, processes: usize) -> Option<Signal> {
+    let title = match processes {
+        1 => "Send signal".to_string(),
+        n => format!("Send signal to {} processes", n),
+    };
+    ui.label(RichText::new(title).color(Color32::WHITE));
+    ui.separator();
+
+    let mut picked = None;
+    for signal in Signal::COMMON {
+        if ui
+            .button(signal.to_string())
+            .on_hover_text(signal.description())
+            .clicked()
+        {
+            picked = Some(signal);
+        }
+    }
+    ui.menu_button("Other", |ui| {
+        egui::ScrollArea::vertical()
+            .max_height(400.0)
+            .show(ui, |ui| {
+                for signal in Signal::all() {
+                    let button = ui.button(signal.to_string());
+                    let button = match signal.description() {
+                        "" => button,
+                        description => button.on_hover_text(description),
+                    };
+                    if button.clicked() {
+                        picked = Some(signal);
+                    }
+                }
+            });
+    });
+    picked
+}
+
+#[cfg(feature = "gui")]
+fn show_cell(ui: &mut Ui, process: &Process, column: Column) {
+    if column == Column::State {
+        process.stats.state.show(ui);
+        return;
+    }
+
+    if matches!(column, Column::User | Column::Group) && process.is_setuid() {
+        show_setuid(ui, process, column);
+        return;
+    }
+
+    ui.label(RichText::new(column.text(process)).color(Color32::LIGHT_GRAY));
+}
+
+/// The owner of a process that runs as someone else, with who started it.
+#[cfg(feature = "gui")]
+fn show_setuid(ui: &mut Ui, process: &Process, column: Column) {
+    let Some(credentials) = &process.credentials else {
+        return;
+    };
+    ui.label(RichText::new(column.text(process)).color(Color32::YELLOW))
+        .on_hover_text(format!(
+            "Runs as {}:{}, started by {}:{}",
+            user_name(credentials.uid.effective),
+            group_name(credentials.gid.effective),
+            user_name(credentials.uid.real),
+            group_name(credentials.gid.real),
+        ));
+}
+
+#[cfg(feature = "gui")]
+fn show_history(ui: &mut Ui, process: &Process, column: Column, history: &History) {
+    let Some(history) = history.process(process.pid) else {
+        return;
+    };
+
+    match column {
+        Column::CpuHistory => show_sparkline(ui, &history.cpu, 100.0, Color32::GREEN),
+        Column::RssHistory => show_sparkline(ui, &history.rss, 0.0, Color32::LIGHT_BLUE),
+        _ => unreachable!("{} has no history", column.name()),
+    }
+}
+
+fn optional_bytes(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(bytes) => format_bytes(bytes),
+        None => "-".to_string(),
+    }
+}
+
+#[cfg(feature = "gui")]
+fn show_command(
+    ui: &mut Ui,
+    process: &Process,
+    row: TreeRow,
+    tree: Option<&ProcessTree>,
+    collapsed: &mut HashSet<u64>,
+    expanded_threads: &mut HashSet<u64>,
+) {
+    ui.add_space(row.depth as f32 * 16.0);
+
+    if row.thread.is_some() {
+        ui.label(RichText::new(format!("↳ {}", process.stats.tcomm)).color(Color32::GRAY));
+        return;
+    }
+
+    if let Some(tree) = tree {
+        if tree.children[row.index].is_empty() {
+            ui.add_space(ui.spacing().interact_size.y);
+        } else if collapsed.contains(&process.pid) {
+            if ui.small_button("+").clicked() {
+                collapsed.remove(&process.pid);
+            }
+        } else if ui.small_button("-").clicked() {
+            collapsed.insert(process.pid);
+        }
+    }
+
+    if process.threads.len() > 1 {
+        let expanded = expanded_threads.contains(&process.pid);
+        if ui
+            .selectable_label(expanded, format!("{}T", process.threads.len()))
+            .on_hover_text("Show threads")
+            .clicked()
+        {
+            if expanded {
+                expanded_threads.remove(&process.pid);
+            } else {
+                expanded_threads.insert(process.pid);
+            }
+        }
+    }
+
+    let color = if process.errors.is_empty() {
+        Color32::WHITE
+    } else {
+        Color32::YELLOW
+    };
+    ui.label(RichText::new(process.command()).color(color));
+
+    if !process.errors.is_empty() {
+        ui.label(RichText::new("(partial)").color(Color32::YELLOW));
+    }
+
+    if let Some(tree) = tree {
+        if tree.orphaned[row.index] {
+            ui.label(RichText::new("orphan").color(Color32::YELLOW))
+                .on_hover_text("Parent is gone, shown under init instead");
+        }
+
+        if !tree.children[row.index].is_empty() {
+            let totals = &tree.totals[row.index];
+            ui.label(
+                RichText::new(format!(
+                    "Σ {:.1}% {}",
+                    totals.cpu_usage,
+                    format_bytes(totals.rss_bytes)
+                ))
+                .color(Color32::GRAY),
+            );
+        }
+    }
+}
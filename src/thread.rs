@@ -0,0 +1,131 @@
+use std::path::Path;
+
+#[cfg(feature = "gui")]
+use egui::{Color32, RichText, Ui};
+
+use crate::{
+    error::{Error, ErrorKind},
+    namespace::parse_ns_pids,
+    process::{parse_stat_file, KernelVersion, Process, ProcessStats},
+    procfs::{self, KeyValues, ProcSource},
+};
+
+/// A task from `/proc/[pid]/task`, the main thread included.
+#[derive(Clone)]
+pub struct Thread {
+    pub tid: u64,
+    pub stats: ProcessStats,
+    /// See [`Process::cpu_usage`].
+    pub cpu_usage: Option<f64>,
+    /// See [`Process::ns_pids`], only read for processes in a nested pid namespace. Elsewhere the
+    /// tid is the same in every namespace the thread is in.
+    pub ns_pids: Vec<u64>,
+}
+
+impl Thread {
+    /// A stand-in process for showing the thread wherever processes are shown, sharing
+    /// everything but stat and the pids with `process`.
+    pub fn as_process(&self, process: &Process) -> Process {
+        Process {
+            pid: self.tid,
+            cmdline: process.cmdline.clone(),
+            stats: self.stats.clone(),
+            credentials: process.credentials.clone(),
+            cgroups: process.cgroups.clone(),
+            namespaces: process.namespaces.clone(),
+            ns_pids: self.ns_pids.clone(),
+            start_time: process.start_time,
+            memory: process.memory.clone(),
+            threads: Vec::new(),
+            cpu_usage: self.cpu_usage,
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// Threads exiting mid-scan are skipped, other failures are added to `errors`. `NSpid` is only
+/// read from the status of each thread if `nested`, i.e. the process is in a child pid namespace.
+pub fn parse_threads(
+    source: &dyn ProcSource,
+    dir: &Path,
+    kernel: Option<KernelVersion>,
+    nested: bool,
+    errors: &mut Vec<Error>,
+) -> Vec<Thread> {
+    let task_dir = dir.join("task");
+    let names = match procfs::read_dir(source, &task_dir) {
+        Ok(names) => names,
+        Err(err) => {
+            errors.push(err);
+            return Vec::new();
+        }
+    };
+
+    let mut threads = Vec::with_capacity(names.len());
+    for name in names {
+        let Some(tid) = name.to_str().and_then(|name| name.parse::<u64>().ok()) else {
+            continue;
+        };
+
+        let stats = match parse_stat_file(source, &task_dir.join(&name).join("stat"), kernel) {
+            Ok(stats) => stats,
+            Err(err) if matches!(err.kind, ErrorKind::NotFound | ErrorKind::NoSuchProcess) => {
+                continue
+            }
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
+
+        let ns_pids = if nested {
+            match KeyValues::read(source, &task_dir.join(&name).join("status")) {
+                Ok(status) => status.get("NSpid").map(parse_ns_pids).unwrap_or_default(),
+                Err(err) => {
+                    if !matches!(err.kind, ErrorKind::NotFound | ErrorKind::NoSuchProcess) {
+                        errors.push(err);
+                    }
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        threads.push(Thread {
+            tid,
+            stats,
+            cpu_usage: None,
+            ns_pids,
+        });
+    }
+
+    threads.sort_by_key(|thread| thread.tid);
+    threads
+}
+
+#[cfg(feature = "gui")]
+pub fn show_threads(ui: &mut Ui, threads: &[Thread]) {
+    puffin::profile_function!();
+
+    egui::Grid::new("threads").striped(true).show(ui, |ui| {
+        for header in ["TID", "Name", "State", "CPU%", "CPU"] {
+            ui.label(RichText::new(header).color(Color32::WHITE));
+        }
+        ui.end_row();
+
+        for thread in threads {
+            ui.label(RichText::new(thread.tid.to_string()).color(Color32::LIGHT_GRAY));
+            ui.label(RichText::new(&thread.stats.tcomm).color(Color32::LIGHT_GRAY));
+            thread.stats.state.show(ui);
+            let cpu_usage = match thread.cpu_usage {
+                Some(usage) => format!("{:.1}", usage),
+                None => "-".to_string(),
+            };
+            ui.label(RichText::new(cpu_usage).color(Color32::LIGHT_GRAY));
+            ui.label(RichText::new(thread.stats.processor.to_string()).color(Color32::LIGHT_GRAY))
+                .on_hover_text("CPU the thread last ran on");
+            ui.end_row();
+        }
+    });
+}
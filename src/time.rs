@@ -0,0 +1,55 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Formats `time` as `HH:MM:SS` in the local timezone.
+pub fn format_local(time: SystemTime) -> String {
+    match local_tm(time) {
+        Some(tm) => format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec),
+        None => "??:??:??".to_string(),
+    }
+}
+
+/// Formats `time` as `YYYY-MM-DD HH:MM:SS` in the local timezone.
+pub fn format_local_date_time(time: SystemTime) -> String {
+    match local_tm(time) {
+        Some(tm) => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec
+        ),
+        None => "????-??-?? ??:??:??".to_string(),
+    }
+}
+
+/// Formats `duration` like `3d 4h 05m`, leaving out leading zero units.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+
+    if days > 0 {
+        format!("{}d {}h {:02}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else {
+        format!("{}m {:02}s", minutes, secs % 60)
+    }
+}
+
+fn local_tm(time: SystemTime) -> Option<libc::tm> {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as libc::time_t)
+        .unwrap_or(0);
+
+    // SAFETY: localtime_r only writes to the tm we pass it.
+    unsafe {
+        let mut tm = std::mem::zeroed::<libc::tm>();
+        if libc::localtime_r(&secs, &mut tm).is_null() {
+            return None;
+        }
+        Some(tm)
+    }
+}
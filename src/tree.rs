@@ -0,0 +1,199 @@
+use std::{collections::HashMap, ops::Deref};
+
+use crate::process::Process;
+
+/// Parent/child relationships between the processes of a scan, by index into the slice the tree
+/// was built from.
+pub struct ProcessTree {
+    pub roots: Vec<usize>,
+    pub children: Vec<Vec<usize>>,
+    /// Set for processes whose real parent is gone, they are attached to init instead, or are
+    /// roots if there is no init either.
+    pub orphaned: Vec<bool>,
+    /// Totals over each process and all of its descendants.
+    pub totals: Vec<SubtreeTotals>,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct SubtreeTotals {
+    pub cpu_usage: f64,
+    pub rss_bytes: u64,
+    pub processes: usize,
+}
+
+/// A single visible line of a flattened tree.
+#[derive(Clone, Copy)]
+pub struct TreeRow {
+    pub index: usize,
+    pub depth: usize,
+    /// Set for rows showing one of the threads of the process at `index`.
+    pub thread: Option<usize>,
+}
+
+impl ProcessTree {
+    /// Children keep the order they have in `processes`.
+    pub fn build(processes: &[impl Deref<Target = Process>]) -> Self {
+        puffin::profile_function!();
+
+        let by_pid: HashMap<u64, usize> = processes
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.pid, i))
+            .collect();
+        let init = by_pid.get(&1).copied();
+
+        let mut parents = vec![None; processes.len()];
+        let mut orphaned = vec![false; processes.len()];
+        for (i, process) in processes.iter().enumerate() {
+            // pid 0 is the idle task, parent of init and kthreadd.
+            if process.stats.ppid <= 0 {
+                continue;
+            }
+
+            match by_pid.get(&(process.stats.ppid as u64)) {
+                // A parent that started after its child is a reused pid, the real one is gone.
+                Some(&parent) if processes[parent].stats.starttime <= process.stats.starttime => {
+                    parents[i] = Some(parent)
+                }
+                _ => {
+                    orphaned[i] = true;
+                    parents[i] = init.filter(|&init| init != i);
+                }
+            }
+        }
+
+        // Racy reads can produce cycles, break them by turning one member into a root.
+        for i in 0..processes.len() {
+            let mut current = i;
+            for _ in 0..processes.len() {
+                match parents[current] {
+                    Some(parent) if parent == i => {
+                        parents[i] = None;
+                        orphaned[i] = true;
+                        break;
+                    }
+                    Some(parent) => current = parent,
+                    None => break,
+                }
+            }
+        }
+
+        let mut roots = Vec::new();
+        let mut children = vec![Vec::new(); processes.len()];
+        for (i, parent) in parents.iter().enumerate() {
+            match parent {
+                Some(parent) => children[*parent].push(i),
+                None => roots.push(i),
+            }
+        }
+
+        let mut tree = Self {
+            roots,
+            children,
+            orphaned,
+            totals: vec![SubtreeTotals::default(); processes.len()],
+        };
+        for root in tree.roots.clone() {
+            tree.sum_totals(processes, root);
+        }
+
+        tree
+    }
+
+    fn sum_totals(
+        &mut self,
+        processes: &[impl Deref<Target = Process>],
+        index: usize,
+    ) -> SubtreeTotals {
+        let process = &processes[index];
+        let mut totals = SubtreeTotals {
+            cpu_usage: process.cpu_usage.unwrap_or(0.0),
+            rss_bytes: process.stats.rss_bytes(),
+            processes: 1,
+        };
+
+        for child in self.children[index].clone() {
+            let child = self.sum_totals(processes, child);
+            totals.cpu_usage += child.cpu_usage;
+            totals.rss_bytes += child.rss_bytes;
+            totals.processes += child.processes;
+        }
+
+        self.totals[index] = totals;
+        totals
+    }
+
+    /// Marks every process that is visible itself or has a visible descendant.
+    pub fn with_ancestors(&self, visible: &[bool]) -> Vec<bool> {
+        let mut result = visible.to_vec();
+        for &root in &self.roots {
+            self.mark_ancestors(root, &mut result);
+        }
+        result
+    }
+
+    fn mark_ancestors(&self, index: usize, result: &mut [bool]) -> bool {
+        let mut any = result[index];
+        for &child in &self.children[index] {
+            any |= self.mark_ancestors(child, result);
+        }
+        result[index] = any;
+        any
+    }
+
+    /// Depth-first list of the rows to show, skipping the children of collapsed processes.
+    pub fn flatten(&self, visible: &[bool], is_expanded: impl Fn(usize) -> bool) -> Vec<TreeRow> {
+        puffin::profile_function!();
+
+        let mut rows = Vec::new();
+        let mut stack: Vec<TreeRow> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&index| TreeRow {
+                index,
+                depth: 0,
+                thread: None,
+            })
+            .collect();
+
+        while let Some(row) = stack.pop() {
+            if !visible[row.index] {
+                continue;
+            }
+
+            rows.push(row);
+            if is_expanded(row.index) {
+                stack.extend(self.children[row.index].iter().rev().map(|&index| TreeRow {
+                    index,
+                    depth: row.depth + 1,
+                    thread: None,
+                }));
+            }
+        }
+
+        rows
+    }
+}
+
+/// Adds a row for every thread after the rows of processes that have their threads expanded.
+pub fn with_threads(
+    rows: Vec<TreeRow>,
+    processes: &[impl Deref<Target = Process>],
+    is_expanded: impl Fn(usize) -> bool,
+) -> Vec<TreeRow> {
+    let mut result = Vec::with_capacity(rows.len());
+    for row in rows {
+        result.push(row);
+        if is_expanded(row.index) {
+            result.extend(
+                (0..processes[row.index].threads.len()).map(|thread| TreeRow {
+                    thread: Some(thread),
+                    depth: row.depth + 1,
+                    ..row
+                }),
+            );
+        }
+    }
+    result
+}
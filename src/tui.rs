@@ -0,0 +1,1689 @@
+//! The terminal frontend, for hosts without a display.
+
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Clear, List, ListState, Paragraph, Row, Sparkline, Table, TableState, Tabs,
+        Wrap,
+    },
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    cgroup::{group_by_workload, GroupSort, Workload, WorkloadGroup},
+    cgroupfs::{find_root, CgroupStats, CgroupTree},
+    cpu::CpuTracker,
+    details::{DetailsTab, ProcessDetails},
+    error::{Error, Result},
+    fd::{Fd, FdKind},
+    history::{History, Series, Unit},
+    maps::{group_mappings, Mapping},
+    namespace::{list_namespaces, NamespaceInfo, NamespaceKind, Owners},
+    net::ProcessSockets,
+    process::{parse_processes, Process, ProcessState, Scan, ScanOptions},
+    procfs::{DirSource, ProcSource},
+    query::{self, Query, QueryError},
+    sampler::Sampler,
+    sched::{
+        for_threads, format_cpu_list, io_priority, set_nice, thread_ids, Policy, ThreadsError,
+    },
+    signal::{describe_target, target_warning, Signal, SignalReport, SignalRequest},
+    table::{Column, ColumnSettings, ProcessRows},
+    time,
+    tree::TreeRow,
+    units::format_bytes,
+    users::current_uid,
+};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+/// How long to wait for a key before checking for a new sample.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const KEYS: &str = "q quit  / search  ↑↓ select  enter details  tab tabs  J/K scroll  \
+    s/S sort  t tree  ←→ fold  T threads  H thread rows  w group  c cgroups  n namespaces  \
+    m mine  space mark  x signal  +/- nice  p pause  F5 refresh";
+
+/// Runs until the user quits, the terminal is restored on the way out.
+pub fn run(source: Arc<dyn ProcSource>) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = Tui::new(source).run(&mut terminal);
+    ratatui::try_restore()?;
+    result
+}
+
+struct Tui {
+    source: Arc<dyn ProcSource>,
+    sampler: Sampler<Result<Scan>>,
+    /// What the sampler reads, depending on what is shown.
+    scan_options: Arc<Mutex<ScanOptions>>,
+    /// Shared with the rows built from it, which live across calls that change the app.
+    scan: Arc<Scan>,
+    /// Set if the last scan failed as a whole, e.g. because the procfs root is missing.
+    scan_error: Option<Error>,
+    last_sample: Option<SystemTime>,
+    history: History,
+    search_text: String,
+    /// Keys go to the search line instead of being commands.
+    searching: bool,
+    /// Last query that parsed, kept while the search text has a syntax error.
+    query: Query,
+    query_error: Option<QueryError>,
+    columns: ColumnSettings,
+    tree_mode: bool,
+    /// Pids whose children are hidden in tree mode.
+    collapsed: HashSet<u64>,
+    /// Pids whose threads are shown below them.
+    expanded_threads: HashSet<u64>,
+    /// Show every thread as its own row instead of processes, like `top -H`.
+    threads_as_rows: bool,
+    /// Only show processes started by or running as the current user.
+    mine_only: bool,
+    /// Show containers, pods and systemd units with their combined usage instead of processes.
+    group_mode: bool,
+    group_sort: GroupSort,
+    /// Highlighted row of the grouped list.
+    group_cursor: usize,
+    /// Where cgroup2 is mounted, `None` if it isn't.
+    cgroup_root: Option<PathBuf>,
+    /// Browse the cgroup hierarchy instead of processes.
+    cgroup_mode: bool,
+    /// Loaded in cgroup mode, dropped with every new sample.
+    cgroup_tree: Option<Result<CgroupTree>>,
+    cgroup_cursor: usize,
+    /// List every namespace instead of processes.
+    namespace_mode: bool,
+    /// Built in namespace mode, dropped with every new sample.
+    namespaces: Option<Vec<NamespaceInfo>>,
+    namespace_owners: Owners,
+    namespace_cursor: usize,
+    /// Pid of the highlighted row, which follows the process when the order changes.
+    selected: Option<u64>,
+    show_details: bool,
+    details: Option<ProcessDetails>,
+    details_tab: DetailsTab,
+    details_scroll: u16,
+    /// Pids marked to get the same signal, tids for thread rows.
+    marked: HashSet<u64>,
+    /// Cursor in the signal picker while it is open.
+    signal_menu: Option<usize>,
+    signal_request: Option<SignalRequest>,
+    signal_report: Option<SignalReport>,
+    show_signal_errors: bool,
+    /// Outcome of the last renice, shown in the scheduling tab.
+    sched_result: Option<std::result::Result<String, ThreadsError>>,
+    /// Rows that fit into the table, for paging.
+    page: usize,
+    quit: bool,
+}
+
+impl Tui {
+    fn new(source: Arc<dyn ProcSource>) -> Self {
+        let mut cpu = CpuTracker::default();
+        let sampler_source = source.clone();
+        let scan_options = Arc::new(Mutex::new(ScanOptions::default()));
+        let sampler_options = scan_options.clone();
+        let sampler = Sampler::spawn(
+            DEFAULT_INTERVAL,
+            move || {
+                let options = sampler_options.lock().unwrap().clone();
+                let mut scan = parse_processes(sampler_source.as_ref(), &options)?;
+                cpu.update(sampler_source.as_ref(), &mut scan.processes);
+                Ok(scan)
+            },
+            // The event loop polls often enough.
+            || {},
+        );
+
+        Self {
+            source,
+            sampler,
+            scan_options,
+            scan: Arc::default(),
+            scan_error: None,
+            last_sample: None,
+            history: History::default(),
+            search_text: String::new(),
+            searching: false,
+            query: Query::default(),
+            query_error: None,
+            columns: ColumnSettings::default(),
+            tree_mode: false,
+            collapsed: HashSet::new(),
+            expanded_threads: HashSet::new(),
+            threads_as_rows: false,
+            mine_only: false,
+            group_mode: false,
+            group_sort: GroupSort::default(),
+            group_cursor: 0,
+            cgroup_root: find_root(),
+            cgroup_mode: false,
+            cgroup_tree: None,
+            cgroup_cursor: 0,
+            namespace_mode: false,
+            namespaces: None,
+            namespace_owners: Owners::default(),
+            namespace_cursor: 0,
+            selected: None,
+            show_details: false,
+            details: None,
+            details_tab: DetailsTab::default(),
+            details_scroll: 0,
+            marked: HashSet::new(),
+            signal_menu: None,
+            signal_request: None,
+            signal_report: None,
+            show_signal_errors: false,
+            sched_result: None,
+            page: 1,
+            quit: false,
+        }
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        let mut dirty = true;
+        while !self.quit {
+            self.update_scan_options();
+            dirty |= self.receive_samples();
+
+            if dirty {
+                let scan = Arc::clone(&self.scan);
+                let rows = self.rows(&scan);
+                if self.show_details {
+                    self.load_details();
+                }
+                if self.cgroup_mode {
+                    self.load_cgroups();
+                }
+                if self.namespace_mode {
+                    self.load_namespaces();
+                }
+                terminal.draw(|frame| self.draw(frame, &rows))?;
+                dirty = false;
+            }
+
+            if event::poll(POLL_INTERVAL)? {
+                match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => {
+                        let scan = Arc::clone(&self.scan);
+                        let rows = self.rows(&scan);
+                        self.handle_key(key, &rows);
+                        dirty = true;
+                    }
+                    Event::Resize(_, _) => dirty = true,
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `smaps_rollup` only for what shows or filters by PSS, USS or swap, sampling right away
+    /// once it is needed.
+    fn update_scan_options(&mut self) {
+        let options = ScanOptions {
+            rollup: self.columns.needs_rollup() || self.query.needs_rollup() || self.group_mode,
+            rollup_pid: self.selected.filter(|_| self.show_details),
+        };
+        let mut current = self.scan_options.lock().unwrap();
+        if *current != options {
+            let more = options.reads_more_than(&current);
+            *current = options;
+            if more && !self.sampler.is_paused() {
+                self.sampler.sample_now();
+            }
+        }
+    }
+
+    /// Returns whether there was a new sample.
+    fn receive_samples(&mut self) -> bool {
+        let Some(sample) = self.sampler.latest() else {
+            return false;
+        };
+
+        match sample.value {
+            Ok(scan) => {
+                self.history
+                    .record_processes(&scan.processes, sample.taken_at);
+                self.scan = Arc::new(scan);
+                self.scan_error = None;
+            }
+            Err(err) => self.scan_error = Some(err),
+        }
+        self.last_sample = Some(sample.taken_at);
+
+        let scan = &self.scan;
+        self.marked.retain(|&pid| scan.find(pid).is_some());
+
+        // Keep the details as fresh as the process list.
+        self.details = None;
+        CgroupTree::expire(&mut self.cgroup_tree);
+        self.namespaces = None;
+        true
+    }
+
+    fn rows<'a>(&self, scan: &'a Scan) -> ProcessRows<'a> {
+        let mine;
+        let query = if self.mine_only {
+            mine = self.query.clone().owned_by(current_uid());
+            &mine
+        } else {
+            &self.query
+        };
+        ProcessRows::build(
+            &scan.processes,
+            query,
+            &self.columns,
+            self.tree_mode,
+            self.threads_as_rows,
+            &self.collapsed,
+            &self.expanded_threads,
+        )
+    }
+
+    fn groups(&self) -> Vec<WorkloadGroup> {
+        let mine;
+        let query = if self.mine_only {
+            mine = self.query.clone().owned_by(current_uid());
+            &mine
+        } else {
+            &self.query
+        };
+        group_by_workload(&self.scan.processes, query, self.group_sort)
+    }
+
+    fn load_cgroups(&mut self) {
+        if let (None, Some(root)) = (&self.cgroup_tree, &self.cgroup_root) {
+            self.cgroup_tree = Some(CgroupTree::load(&DirSource::new(root)));
+        }
+    }
+
+    fn load_namespaces(&mut self) {
+        if self.namespaces.is_none() {
+            self.namespaces = Some(list_namespaces(
+                &self.scan.processes,
+                self.source.as_ref(),
+                &mut self.namespace_owners,
+            ));
+        }
+    }
+
+    fn load_details(&mut self) {
+        let Some(pid) = self.selected else {
+            self.details = None;
+            return;
+        };
+        let details = match &mut self.details {
+            Some(details) if details.pid == pid => details,
+            details => details.insert(ProcessDetails::new(pid)),
+        };
+        details.load(self.source.as_ref(), self.details_tab);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, rows: &ProcessRows) {
+        if self.show_signal_errors {
+            self.show_signal_errors = false;
+            return;
+        }
+        if self.signal_request.is_some() {
+            self.handle_confirm_key(key);
+            return;
+        }
+        if let Some(cursor) = self.signal_menu {
+            self.handle_signal_menu_key(key, cursor);
+            return;
+        }
+        if self.searching {
+            match key.code {
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.search_text.clear();
+                    self.update_query();
+                }
+                KeyCode::Backspace => {
+                    self.search_text.pop();
+                    self.update_query();
+                }
+                KeyCode::Char(c) => {
+                    self.search_text.push(c);
+                    self.update_query();
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.group_mode {
+            self.handle_group_key(key);
+            return;
+        }
+        if self.cgroup_mode {
+            self.handle_cgroup_key(key);
+            return;
+        }
+        if self.namespace_mode {
+            self.handle_namespace_key(key);
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc => self.show_details = false,
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(rows, 1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(rows, -1),
+            KeyCode::PageDown => self.move_cursor(rows, self.page as isize),
+            KeyCode::PageUp => self.move_cursor(rows, -(self.page as isize)),
+            KeyCode::Home | KeyCode::Char('g') => self.move_cursor(rows, isize::MIN / 2),
+            KeyCode::End | KeyCode::Char('G') => self.move_cursor(rows, isize::MAX / 2),
+            KeyCode::Enter => {
+                self.show_details = !self.show_details && self.selected.is_some();
+                self.details_scroll = 0;
+            }
+            KeyCode::Tab => self.step_tab(1),
+            KeyCode::BackTab => self.step_tab(DetailsTab::ALL.len() - 1),
+            KeyCode::Char('J') => self.details_scroll = self.details_scroll.saturating_add(1),
+            KeyCode::Char('K') => self.details_scroll = self.details_scroll.saturating_sub(1),
+            KeyCode::Char('s') => {
+                let visible = self.columns.visible();
+                let next = visible
+                    .iter()
+                    .position(|column| *column == self.columns.sort_column)
+                    .map_or(0, |i| (i + 1) % visible.len());
+                self.columns.sort_by(visible[next]);
+            }
+            KeyCode::Char('S') => self.columns.sort_by(self.columns.sort_column),
+            KeyCode::Char('t') => self.tree_mode = !self.tree_mode,
+            KeyCode::Char('H') => self.threads_as_rows = !self.threads_as_rows,
+            KeyCode::Char('m') => self.mine_only = !self.mine_only,
+            KeyCode::Char('w') => {
+                self.group_mode = true;
+                self.group_cursor = 0;
+            }
+            KeyCode::Char('c') => {
+                self.cgroup_mode = true;
+                self.load_cgroups();
+            }
+            KeyCode::Char('n') => {
+                self.namespace_mode = true;
+                self.load_namespaces();
+            }
+            KeyCode::Left => {
+                if let Some(pid) = self.selected_process(rows) {
+                    self.collapsed.insert(pid);
+                }
+            }
+            KeyCode::Right => {
+                if let Some(pid) = self.selected_process(rows) {
+                    self.collapsed.remove(&pid);
+                }
+            }
+            KeyCode::Char('T') => {
+                if let Some(pid) = self.selected_process(rows) {
+                    if !self.expanded_threads.remove(&pid) {
+                        self.expanded_threads.insert(pid);
+                    }
+                }
+            }
+            KeyCode::Char(' ') => {
+                if let Some(pid) = self.selected {
+                    if !self.marked.remove(&pid) {
+                        self.marked.insert(pid);
+                    }
+                    self.move_cursor(rows, 1);
+                }
+            }
+            KeyCode::Char('x') if self.selected.is_some() || !self.marked.is_empty() => {
+                self.signal_menu = Some(0);
+            }
+            KeyCode::Char('+') => self.renice(rows, 1),
+            KeyCode::Char('-') => self.renice(rows, -1),
+            KeyCode::Char('p') => {
+                if self.sampler.is_paused() {
+                    self.sampler.resume();
+                } else {
+                    self.sampler.pause();
+                }
+            }
+            KeyCode::F(5) => self.sampler.sample_now(),
+            _ => {}
+        }
+    }
+
+    fn handle_group_key(&mut self, key: KeyEvent) {
+        let groups = self.groups();
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc | KeyCode::Char('w') => self.group_mode = false,
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Char('m') => self.mine_only = !self.mine_only,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.group_cursor = (self.group_cursor + 1).min(groups.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.group_cursor = self.group_cursor.saturating_sub(1);
+            }
+            KeyCode::Char('s') => {
+                let index = GroupSort::ALL
+                    .iter()
+                    .position(|sort| *sort == self.group_sort)
+                    .unwrap_or(0);
+                self.group_sort = GroupSort::ALL[(index + 1) % GroupSort::ALL.len()];
+            }
+            // Show the processes of the group.
+            KeyCode::Enter => {
+                if let Some(group) = groups.get(self.group_cursor) {
+                    self.search_text = group.workload.search();
+                    self.update_query();
+                    self.group_mode = false;
+                    self.selected = None;
+                }
+            }
+            // The tree is only read again every so often by itself.
+            KeyCode::F(5) => {
+                self.cgroup_tree = None;
+                self.load_cgroups();
+                self.sampler.sample_now();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_cgroup_key(&mut self, key: KeyEvent) {
+        let paths: Vec<String> = match &self.cgroup_tree {
+            Some(Ok(tree)) => tree
+                .root
+                .flatten()
+                .into_iter()
+                .map(|(node, _)| node.path.clone())
+                .collect(),
+            _ => Vec::new(),
+        };
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc | KeyCode::Char('c') => self.cgroup_mode = false,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.cgroup_cursor = (self.cgroup_cursor + 1).min(paths.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.cgroup_cursor = self.cgroup_cursor.saturating_sub(1);
+            }
+            KeyCode::PageDown => {
+                self.cgroup_cursor =
+                    (self.cgroup_cursor + self.page).min(paths.len().saturating_sub(1));
+            }
+            KeyCode::PageUp => self.cgroup_cursor = self.cgroup_cursor.saturating_sub(self.page),
+            // Show the members of the cgroup.
+            KeyCode::Enter => {
+                if let Some(path) = paths.get(self.cgroup_cursor) {
+                    self.search_text = query::within("cgroup2", path);
+                    self.update_query();
+                    self.cgroup_mode = false;
+                    self.selected = None;
+                }
+            }
+            KeyCode::F(5) => self.sampler.sample_now(),
+            _ => {}
+        }
+    }
+
+    fn handle_namespace_key(&mut self, key: KeyEvent) {
+        let count = self.namespaces.as_ref().map_or(0, Vec::len);
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc | KeyCode::Char('n') => self.namespace_mode = false,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.namespace_cursor = (self.namespace_cursor + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.namespace_cursor = self.namespace_cursor.saturating_sub(1);
+            }
+            KeyCode::PageDown => {
+                self.namespace_cursor =
+                    (self.namespace_cursor + self.page).min(count.saturating_sub(1));
+            }
+            KeyCode::PageUp => {
+                self.namespace_cursor = self.namespace_cursor.saturating_sub(self.page);
+            }
+            // Show the members of the namespace.
+            KeyCode::Enter => {
+                let namespace = self
+                    .namespaces
+                    .as_ref()
+                    .and_then(|namespaces| namespaces.get(self.namespace_cursor))
+                    .map(|info| info.namespace);
+                if let Some(namespace) = namespace {
+                    self.search_text = namespace.search();
+                    self.update_query();
+                    self.namespace_mode = false;
+                    self.selected = None;
+                }
+            }
+            KeyCode::F(5) => self.sampler.sample_now(),
+            _ => {}
+        }
+    }
+
+    fn handle_signal_menu_key(&mut self, key: KeyEvent, cursor: usize) {
+        let signals = menu_signals();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.signal_menu = Some((cursor + 1).min(signals.len() - 1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.signal_menu = Some(cursor.saturating_sub(1)),
+            KeyCode::Enter => {
+                self.signal_menu = None;
+                self.signal_request = Some(SignalRequest {
+                    pids: self.signal_targets(),
+                    signal: signals[cursor],
+                });
+            }
+            KeyCode::Esc => self.signal_menu = None,
+            _ => {}
+        }
+    }
+
+    fn handle_confirm_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('y') => {
+                if let Some(request) = self.signal_request.take() {
+                    let report = request.send();
+                    self.show_signal_errors = !report.errors.is_empty();
+                    self.signal_report = Some(report);
+                    self.marked.clear();
+                    // Show the effect right away.
+                    self.sampler.sample_now();
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Esc => self.signal_request = None,
+            _ => {}
+        }
+    }
+
+    /// Changes the nice value of every thread of the selected process, only while the scheduling
+    /// tab shows the outcome.
+    fn renice(&mut self, rows: &ProcessRows, by: i64) {
+        if !self.show_details || self.details_tab != DetailsTab::Scheduling {
+            return;
+        }
+        let Some(process) = self
+            .selected_process(rows)
+            .and_then(|pid| self.scan.find(pid))
+        else {
+            return;
+        };
+        let nice = (process.stats.nice + by).clamp(-20, 19) as i32;
+        self.sched_result = Some(
+            for_threads(&thread_ids(process), |tid| set_nice(tid, nice))
+                .map(|()| format!("Set nice of {} to {}", process.pid, nice)),
+        );
+        self.sampler.sample_now();
+    }
+
+    /// The marked pids, or the selected one if nothing is marked.
+    fn signal_targets(&self) -> Vec<u64> {
+        if self.marked.is_empty() {
+            return self.selected.into_iter().collect();
+        }
+        let mut pids: Vec<u64> = self.marked.iter().copied().collect();
+        pids.sort_unstable();
+        pids
+    }
+
+    fn update_query(&mut self) {
+        match Query::parse(&self.search_text) {
+            Ok(query) => {
+                self.query = query;
+                self.query_error = None;
+            }
+            Err(err) => self.query_error = Some(err),
+        }
+    }
+
+    fn step_tab(&mut self, by: usize) {
+        let index = DetailsTab::ALL
+            .iter()
+            .position(|tab| *tab == self.details_tab)
+            .unwrap_or(0);
+        self.details_tab = DetailsTab::ALL[(index + by) % DetailsTab::ALL.len()];
+        self.details_scroll = 0;
+    }
+
+    fn cursor(&self, rows: &ProcessRows) -> Option<usize> {
+        let selected = self.selected?;
+        (0..rows.rows.len()).find(|&i| row_pid(rows, i) == selected)
+    }
+
+    fn move_cursor(&mut self, rows: &ProcessRows, by: isize) {
+        if rows.rows.is_empty() {
+            return;
+        }
+        let target = match self.cursor(rows) {
+            Some(cursor) => cursor as isize + by,
+            // Start from above the first row.
+            None => by - 1,
+        };
+        let target = target.clamp(0, rows.rows.len() as isize - 1) as usize;
+        self.selected = Some(row_pid(rows, target));
+        self.details_scroll = 0;
+    }
+
+    /// The process of the selected row, also for thread rows.
+    fn selected_process(&self, rows: &ProcessRows) -> Option<u64> {
+        let row = rows.rows[self.cursor(rows)?];
+        Some(rows.processes[row.index].pid)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rows: &ProcessRows) {
+        let [search, body, status, keys] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.draw_search(frame, search);
+        if self.group_mode {
+            self.draw_groups(frame, body);
+        } else if self.cgroup_mode {
+            self.draw_cgroups(frame, body);
+        } else if self.namespace_mode {
+            self.draw_namespaces(frame, body);
+        } else if self.show_details {
+            let [table, details] =
+                Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .areas(body);
+            self.draw_table(frame, table, rows);
+            self.draw_details(frame, details);
+        } else {
+            self.draw_table(frame, body, rows);
+        }
+        self.draw_status(frame, status);
+        frame.render_widget(Paragraph::new(KEYS).fg(Color::DarkGray), keys);
+
+        if let Some(cursor) = self.signal_menu {
+            self.draw_signal_menu(frame, body, cursor);
+        }
+        if let Some(request) = &self.signal_request {
+            self.draw_signal_confirm(frame, body, request);
+        }
+        if self.show_signal_errors {
+            self.draw_signal_errors(frame, body);
+        }
+    }
+
+    fn draw_search(&self, frame: &mut Frame, area: Rect) {
+        let mut spans = vec![
+            Span::from("Search ").white(),
+            Span::from(self.search_text.as_str()).fg(if self.query_error.is_some() {
+                Color::Red
+            } else {
+                Color::Gray
+            }),
+        ];
+        if self.searching {
+            spans.push(Span::from("█").gray());
+        }
+        if let Some(err) = &self.query_error {
+            spans.push(Span::from(format!("  Syntax error at {}", err)).red());
+        }
+        for (enabled, name) in [
+            (self.tree_mode && !self.threads_as_rows, "  [tree]"),
+            (self.threads_as_rows, "  [threads]"),
+            (self.mine_only, "  [mine]"),
+            (self.group_mode, "  [group]"),
+            (self.cgroup_mode, "  [cgroups]"),
+            (self.namespace_mode, "  [namespaces]"),
+        ] {
+            if enabled {
+                spans.push(Span::from(name).white());
+            }
+        }
+        frame.render_widget(Line::from(spans), area);
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect, rows: &ProcessRows) {
+        let columns = self.columns.visible();
+
+        let header = Row::new(columns.iter().map(|column| {
+            let arrow = match (
+                self.columns.sort_column == *column,
+                self.columns.sort_ascending,
+            ) {
+                (false, _) => "",
+                (true, true) => "▲",
+                (true, false) => "▼",
+            };
+            Line::from(format!("{}{}", column.name(), arrow))
+        }))
+        .style(Style::new().white().bold());
+
+        let body = rows.rows.iter().map(|row| {
+            let thread_process;
+            let process = match row.thread {
+                Some(thread) => {
+                    let process = &rows.processes[row.index];
+                    thread_process = process.threads[thread].as_process(process);
+                    &thread_process
+                }
+                None => &rows.processes[row.index],
+            };
+
+            let style = if self.marked.contains(&tree_row_pid(rows, *row)) {
+                Style::new().bg(Color::Blue)
+            } else {
+                Style::new()
+            };
+            Row::new(columns.iter().map(|column| {
+                match column {
+                    Column::Command => self.command_cell(rows, row.index, row.depth, row.thread),
+                    Column::State => Line::from(process.stats.state.to_string())
+                        .fg(state_color(&process.stats.state)),
+                    Column::CpuHistory | Column::RssHistory => {
+                        Line::from(match (row.thread, self.history.process(process.pid)) {
+                            (None, Some(history)) if *column == Column::CpuHistory => {
+                                sparkline_text(&history.cpu, 100.0, 12)
+                            }
+                            (None, Some(history)) => sparkline_text(&history.rss, 0.0, 12),
+                            _ => String::new(),
+                        })
+                        .fg(if *column == Column::CpuHistory {
+                            Color::Green
+                        } else {
+                            Color::LightBlue
+                        })
+                    }
+                    Column::User | Column::Group if process.is_setuid() => {
+                        Line::from(column.text(process)).yellow()
+                    }
+                    column => Line::from(column.text(process)).gray(),
+                }
+            }))
+            .style(style)
+        });
+
+        let widths = columns.iter().map(|column| column_width(*column));
+        let table = Table::new(body, widths).header(header).row_highlight_style(
+            Style::new()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        // Minus the header.
+        self.page = (area.height as usize).saturating_sub(1).max(1);
+        let mut state = TableState::default().with_selected(self.cursor(rows));
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn draw_groups(&mut self, frame: &mut Frame, area: Rect) {
+        let groups = self.groups();
+        self.group_cursor = self.group_cursor.min(groups.len().saturating_sub(1));
+
+        let header = Row::new(
+            ["Kind", "Name"]
+                .into_iter()
+                .map(str::to_string)
+                .chain(GroupSort::ALL.iter().map(|sort| {
+                    let arrow = if *sort == self.group_sort { "▼" } else { "" };
+                    format!("{}{}", sort.name(), arrow)
+                }))
+                .chain(["Commands".to_string()]),
+        )
+        .style(Style::new().white().bold());
+
+        let body = groups.iter().map(|group| {
+            Row::new([
+                Line::from(group.workload.kind()).gray(),
+                Line::from(group.workload.name()).white(),
+                Line::from(group.processes.to_string()).gray(),
+                Line::from(format!("{:.1}", group.cpu_usage)).gray(),
+                Line::from(format_bytes(group.rss_bytes)).gray(),
+                Line::from(format_bytes(group.pss)).gray(),
+                Line::from(group.command_summary()).gray(),
+            ])
+        });
+
+        let widths = [
+            Constraint::Length(9),
+            Constraint::Min(30),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Min(20),
+        ];
+        let table = Table::new(body, widths).header(header).row_highlight_style(
+            Style::new()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        );
+        let mut state =
+            TableState::default().with_selected((!groups.is_empty()).then_some(self.group_cursor));
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn draw_cgroups(&mut self, frame: &mut Frame, area: Rect) {
+        let tree = match (&self.cgroup_root, &self.cgroup_tree) {
+            (None, _) => {
+                frame.render_widget(Line::from("cgroup2 is not mounted").yellow(), area);
+                return;
+            }
+            (_, Some(Err(err))) => {
+                frame.render_widget(Line::from(err.to_string()).red(), area);
+                return;
+            }
+            (_, Some(Ok(tree))) => tree,
+            (_, None) => return,
+        };
+
+        let [list, stats] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(area);
+
+        let nodes = tree.root.flatten();
+        self.cgroup_cursor = self.cgroup_cursor.min(nodes.len().saturating_sub(1));
+        self.page = (list.height as usize).max(1);
+        let items: Vec<Line> = nodes
+            .iter()
+            .map(|(node, depth)| {
+                let mut spans = vec![
+                    Span::from("  ".repeat(*depth)),
+                    Span::from(node.name().to_string()).fg(if node.procs.is_err() {
+                        Color::Yellow
+                    } else {
+                        Color::White
+                    }),
+                    Span::from(format!(" ({})", node.total_procs())).gray(),
+                ];
+                if let Some(current) = node.memory_current {
+                    spans.push(Span::from(format!(" {}", format_bytes(current))).dark_gray());
+                }
+                Line::from(spans)
+            })
+            .collect();
+        let list_widget = List::new(items).highlight_style(
+            Style::new()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        );
+        let mut state = ListState::default().with_selected(Some(self.cgroup_cursor));
+        frame.render_stateful_widget(list_widget, list, &mut state);
+
+        let (Some((node, _)), Some(root)) = (nodes.get(self.cgroup_cursor), &self.cgroup_root)
+        else {
+            return;
+        };
+        let block = Block::new()
+            .borders(Borders::LEFT)
+            .title(format!(" {} ", node.path))
+            .white();
+        let inner = block.inner(stats);
+        frame.render_widget(block, stats);
+        let lines = cgroup_lines(&CgroupStats::load(&DirSource::new(root), &node.path));
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+    }
+
+    fn draw_namespaces(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(namespaces) = &self.namespaces else {
+            return;
+        };
+        self.namespace_cursor = self
+            .namespace_cursor
+            .min(namespaces.len().saturating_sub(1));
+
+        let header = Row::new(["Kind", "Inode", "", "Processes", "Owner", "Commands"])
+            .style(Style::new().white().bold());
+        let body = namespaces.iter().map(|info| {
+            let ns = info.namespace;
+            let owner = match (info.owner, ns.kind) {
+                (Some(owner), NamespaceKind::User) => format!("parent user:[{}]", owner),
+                (Some(owner), _) => format!("user:[{}]", owner),
+                (None, _) => "-".to_string(),
+            };
+            Row::new([
+                Line::from(ns.kind.name()).gray(),
+                Line::from(ns.inode.to_string()).white(),
+                Line::from(if info.host { "host" } else { "" }).dark_gray(),
+                Line::from(info.pids.len().to_string()).gray(),
+                Line::from(owner).gray(),
+                Line::from(info.command_summary()).gray(),
+            ])
+        });
+
+        let widths = [
+            Constraint::Length(7),
+            Constraint::Length(11),
+            Constraint::Length(5),
+            Constraint::Length(10),
+            Constraint::Length(26),
+            Constraint::Min(20),
+        ];
+        let table = Table::new(body, widths).header(header).row_highlight_style(
+            Style::new()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        );
+        self.page = (area.height as usize).saturating_sub(1).max(1);
+        let mut state = TableState::default()
+            .with_selected((!namespaces.is_empty()).then_some(self.namespace_cursor));
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn command_cell(
+        &self,
+        rows: &ProcessRows,
+        index: usize,
+        depth: usize,
+        thread: Option<usize>,
+    ) -> Line<'static> {
+        let process = &rows.processes[index];
+        let mut spans = vec![Span::from("  ".repeat(depth))];
+
+        if let Some(thread) = thread {
+            spans.push(Span::from(format!("↳ {}", process.threads[thread].stats.tcomm)).gray());
+            return Line::from(spans);
+        }
+
+        if let Some(tree) = &rows.tree {
+            spans.push(Span::from(if tree.children[index].is_empty() {
+                "  "
+            } else if self.collapsed.contains(&process.pid) {
+                "+ "
+            } else {
+                "- "
+            }));
+        }
+
+        if process.threads.len() > 1 {
+            let threads = Span::from(format!("{}T ", process.threads.len()));
+            spans.push(if self.expanded_threads.contains(&process.pid) {
+                threads.white().bold()
+            } else {
+                threads.dark_gray()
+            });
+        }
+
+        if process.errors.is_empty() {
+            spans.push(Span::from(process.command()).white());
+        } else {
+            spans.push(Span::from(process.command()).yellow());
+            spans.push(Span::from(" (partial)").yellow());
+        }
+
+        if let Some(tree) = &rows.tree {
+            if tree.orphaned[index] {
+                spans.push(Span::from(" orphan").yellow());
+            }
+            if !tree.children[index].is_empty() {
+                let totals = &tree.totals[index];
+                spans.push(
+                    Span::from(format!(
+                        " Σ {:.1}% {}",
+                        totals.cpu_usage,
+                        format_bytes(totals.rss_bytes)
+                    ))
+                    .dark_gray(),
+                );
+            }
+        }
+
+        Line::from(spans)
+    }
+
+    fn draw_details(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::new()
+            .borders(Borders::LEFT)
+            .title(match self.selected {
+                Some(pid) => format!(" {} ", pid),
+                None => String::new(),
+            })
+            .white();
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [tabs, content] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(inner);
+        let selected = DetailsTab::ALL
+            .iter()
+            .position(|tab| *tab == self.details_tab)
+            .unwrap_or(0);
+        frame.render_widget(
+            Tabs::new(DetailsTab::ALL.iter().map(|tab| tab.name()))
+                .select(selected)
+                .highlight_style(Style::new().white().bold().underlined())
+                .gray(),
+            tabs,
+        );
+
+        let process = self.selected.and_then(|pid| self.scan.find(pid));
+        let (Some(process), Some(details)) = (process, &self.details) else {
+            frame.render_widget(Line::from("Process exited").yellow(), content);
+            return;
+        };
+
+        if self.details_tab == DetailsTab::History {
+            self.draw_history(frame, content, process);
+            return;
+        }
+
+        let lines = match self.details_tab {
+            DetailsTab::Overview => overview_lines(process),
+            DetailsTab::Files => result_lines(&details.fds, |fds| fd_lines(fds)),
+            DetailsTab::Maps => result_lines(&details.maps, |maps| map_lines(maps)),
+            DetailsTab::Sockets => result_lines(&details.sockets, socket_lines),
+            DetailsTab::Scheduling => match &details.cpus_allowed {
+                Some(cpus_allowed) => scheduling_lines(process, cpus_allowed, &self.sched_result),
+                None => Vec::new(),
+            },
+            DetailsTab::History => unreachable!(),
+        };
+        frame.render_widget(
+            Paragraph::new(lines).scroll((self.details_scroll, 0)),
+            content,
+        );
+    }
+
+    fn draw_history(&self, frame: &mut Frame, area: Rect, process: &Process) {
+        let Some(history) = self.history.process(process.pid) else {
+            frame.render_widget(Line::from("No history yet").yellow(), area);
+            return;
+        };
+
+        let [cpu, rss] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+        for (area, name, series, unit, min_max, color) in [
+            (
+                cpu,
+                "CPU%",
+                &history.cpu,
+                Unit::Percent,
+                100.0,
+                Color::Green,
+            ),
+            (rss, "RSS", &history.rss, Unit::Bytes, 0.0, Color::LightBlue),
+        ] {
+            let last = series.last().map(|(_, value)| *value).unwrap_or(0.0);
+            let block = Block::new()
+                .borders(Borders::TOP)
+                .title(format!(
+                    " {} {} (max {}) ",
+                    name,
+                    unit.format(last),
+                    unit.format(series.max())
+                ))
+                .white();
+
+            // Sparkline wants integers, keep some resolution for small values.
+            let scale = 100.0 / series.max().max(min_max).max(f64::EPSILON);
+            let width = block.inner(area).width as usize;
+            let data: Vec<u64> = series
+                .iter()
+                .rev()
+                .take(width)
+                .rev()
+                .map(|(_, value)| (value * scale) as u64)
+                .collect();
+            frame.render_widget(
+                Sparkline::default()
+                    .block(block)
+                    .data(&data)
+                    .max(100)
+                    .style(Style::new().fg(color)),
+                area,
+            );
+        }
+    }
+
+    fn draw_signal_menu(&self, frame: &mut Frame, area: Rect, cursor: usize) {
+        let signals = menu_signals();
+        let targets = self.signal_targets().len();
+        let items = signals.iter().map(|signal| {
+            Line::from(vec![
+                Span::from(format!("{:<18}", signal.to_string())).white(),
+                Span::from(signal.description()).gray(),
+            ])
+        });
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(match targets {
+                        1 => " Send signal ".to_string(),
+                        n => format!(" Send signal to {} processes ", n),
+                    })
+                    .title_bottom(" ↑↓ select  enter pick  esc cancel "),
+            )
+            .highlight_style(
+                Style::new()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        let area = popup_area(area, 64, signals.len() as u16 + 2);
+        frame.render_widget(Clear, area);
+        let mut state = ListState::default().with_selected(Some(cursor));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn draw_signal_confirm(&self, frame: &mut Frame, area: Rect, request: &SignalRequest) {
+        let targets = match request.pids.len() {
+            1 => "this process".to_string(),
+            n => format!("these {} processes", n),
+        };
+        let mut lines =
+            vec![Line::from(format!("Send {} to {}?", request.signal, targets)).white()];
+        let description = request.signal.description();
+        if !description.is_empty() {
+            lines.push(Line::from(description).gray());
+        }
+        lines.push(Line::default());
+        for &pid in &request.pids {
+            let mut spans = vec![Span::from(format!("{:>7} ", pid)).white()];
+            // Before the command, which may be cut off.
+            if let Some(warning) = target_warning(pid) {
+                spans.push(Span::from(format!("{}: ", warning)).yellow());
+            }
+            spans.push(Span::from(describe_target(&self.scan, pid)).gray());
+            lines.push(Line::from(spans));
+        }
+
+        let paragraph = Paragraph::new(lines).block(
+            Block::bordered()
+                .title(" Send signal ")
+                .title_bottom(" y send  n cancel "),
+        );
+        let area = popup_area(area, 80, request.pids.len() as u16 + 5);
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+    }
+
+    fn draw_signal_errors(&self, frame: &mut Frame, area: Rect) {
+        let Some(report) = &self.signal_report else {
+            return;
+        };
+        let mut lines = vec![Line::from(report.to_string()).white(), Line::default()];
+        lines.extend(
+            report
+                .errors
+                .iter()
+                .map(|err| Line::from(err.to_string()).red()),
+        );
+
+        let paragraph = Paragraph::new(lines).block(
+            Block::bordered()
+                .title(" Sending failed ")
+                .title_bottom(" any key to close "),
+        );
+        let area = popup_area(area, 80, report.errors.len() as u16 + 4);
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+    }
+
+    fn draw_status(&self, frame: &mut Frame, area: Rect) {
+        let last_sample = match self.last_sample {
+            Some(time) => format!("Last sample {}", time::format_local(time)),
+            None => "No sample yet".to_string(),
+        };
+        let mut spans = vec![Span::from(last_sample).white()];
+        if self.sampler.is_paused() {
+            spans.push(Span::from(" (paused)").yellow());
+        }
+        if let Some(err) = &self.scan_error {
+            spans.push(Span::from(format!(" | {}", err)).red());
+        }
+
+        spans.push(Span::from(format!(" | {} processes", self.scan.processes.len())).white());
+        let partial = self.scan.partial();
+        spans.push(
+            Span::from(format!(" | {} partial", partial)).fg(if partial == 0 {
+                Color::Gray
+            } else {
+                Color::Yellow
+            }),
+        );
+        spans.push(
+            Span::from(format!(
+                " | {} skipped ({} permission denied)",
+                self.scan.skipped.len(),
+                self.scan.permission_denied()
+            ))
+            .fg(if self.scan.skipped.is_empty() {
+                Color::Gray
+            } else {
+                Color::Yellow
+            }),
+        );
+        if let Some(report) = &self.signal_report {
+            spans.push(
+                Span::from(format!(" | {}", report)).fg(if report.errors.is_empty() {
+                    Color::White
+                } else {
+                    Color::Red
+                }),
+            );
+        }
+        if !self.marked.is_empty() {
+            spans.push(Span::from(format!(" | {} marked", self.marked.len())).white());
+        }
+        frame.render_widget(Line::from(spans), area);
+    }
+}
+
+/// Pid of a row, or tid for thread rows.
+fn row_pid(rows: &ProcessRows, i: usize) -> u64 {
+    tree_row_pid(rows, rows.rows[i])
+}
+
+fn tree_row_pid(rows: &ProcessRows, row: TreeRow) -> u64 {
+    let process = &rows.processes[row.index];
+    match row.thread {
+        Some(thread) => process.threads[thread].tid,
+        None => process.pid,
+    }
+}
+
+/// The common signals first, then the rest in order.
+fn menu_signals() -> Vec<Signal> {
+    let mut signals = Signal::COMMON.to_vec();
+    signals.extend(Signal::all().filter(|signal| !Signal::COMMON.contains(signal)));
+    signals
+}
+
+/// A centered rectangle of at most `width` by `height` inside `area`.
+fn popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn column_width(column: Column) -> Constraint {
+    match column {
+        Column::Pid | Column::NsPid | Column::Cpu | Column::Threads => Constraint::Length(7),
+        Column::User | Column::Group => Constraint::Length(9),
+        Column::State => Constraint::Length(12),
+        Column::CpuHistory | Column::RssHistory => Constraint::Length(12),
+        Column::Rss | Column::Pss | Column::Uss | Column::Swap => Constraint::Length(8),
+        Column::StartTime => Constraint::Length(19),
+        Column::Command => Constraint::Min(20),
+    }
+}
+
+fn state_color(state: &ProcessState) -> Color {
+    match state {
+        ProcessState::Running => Color::Green,
+        ProcessState::Sleeping => Color::LightBlue,
+        ProcessState::UninterruptibleSleeping => Color::Rgb(255, 140, 0),
+        ProcessState::Stopped | ProcessState::TracingStop => Color::Yellow,
+        ProcessState::Zombie | ProcessState::Dead => Color::Red,
+        ProcessState::Wakekill => Color::LightRed,
+        ProcessState::Waking => Color::LightGreen,
+        ProcessState::Paging | ProcessState::Parked => Color::Gray,
+        ProcessState::Idle => Color::DarkGray,
+        ProcessState::Unknown(_) => Color::Magenta,
+    }
+}
+
+/// The last `width` values as block characters, scaled so that `min_max` still fits.
+fn sparkline_text(series: &Series, min_max: f64, width: usize) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let max = series.max().max(min_max).max(f64::EPSILON);
+    let skip = series.len().saturating_sub(width);
+    series
+        .iter()
+        .skip(skip)
+        .map(|(_, value)| {
+            let level = (value / max * (BLOCKS.len() - 1) as f64).round() as usize;
+            BLOCKS[level.min(BLOCKS.len() - 1)]
+        })
+        .collect()
+}
+
+fn heading(text: &str) -> Line<'static> {
+    Line::from(text.to_string()).white().bold()
+}
+
+/// `name value` pairs laid out in `per_line` columns.
+fn field_lines(fields: &[(&str, String)], per_line: usize) -> Vec<Line<'static>> {
+    let name_width = fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let value_width = fields
+        .iter()
+        .map(|(_, value)| value.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    fields
+        .chunks(per_line)
+        .map(|row| {
+            let spans: Vec<Span> = row
+                .iter()
+                .flat_map(|(name, value)| {
+                    [
+                        Span::from(format!("{:name_width$} ", name)).white(),
+                        Span::from(format!("{:value_width$}  ", value)).gray(),
+                    ]
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn scheduling_lines(
+    process: &Process,
+    cpus_allowed: &Result<Vec<usize>>,
+    result: &Option<std::result::Result<String, ThreadsError>>,
+) -> Vec<Line<'static>> {
+    let stats = &process.stats;
+    let policy = match Policy::from_raw(stats.policy) {
+        Some(policy) if policy.is_realtime() => {
+            format!("{}, priority {}", policy, stats.rt_priority)
+        }
+        Some(policy) => policy.to_string(),
+        None => format!("unknown ({})", stats.policy),
+    };
+    let fields = [
+        ("Nice", stats.nice.to_string()),
+        ("Priority", stats.priority.to_string()),
+        ("Policy", policy),
+        (
+            "Affinity",
+            cpus_allowed
+                .as_ref()
+                .map_or_else(ToString::to_string, |cpus| format_cpu_list(cpus)),
+        ),
+        (
+            "I/O priority",
+            io_priority(process.pid).map_or_else(|err| err.to_string(), |io| io.to_string()),
+        ),
+    ];
+
+    let mut lines = field_lines(&fields, 1);
+    lines.push(Line::default());
+    lines.push(Line::from("+/- changes the nice value of every thread").dark_gray());
+    match result {
+        Some(Ok(done)) => lines.push(Line::from(done.clone()).white()),
+        Some(Err(err)) => lines.push(Line::from(err.to_string()).red()),
+        None => {}
+    }
+    lines
+}
+
+fn cgroup_lines(stats: &CgroupStats) -> Vec<Line<'static>> {
+    let mut lines = match &stats.procs {
+        Ok(procs) => {
+            let pids: Vec<String> = procs.iter().map(u64::to_string).collect();
+            vec![Line::from(vec![
+                Span::from(format!("{} processes ", procs.len())).white(),
+                Span::from(pids.join(" ")).gray(),
+            ])]
+        }
+        Err(err) => vec![Line::from(err.to_string()).yellow()],
+    };
+    lines.push(Line::from("enter shows the processes").dark_gray());
+
+    for (name, fields) in stats.sections() {
+        lines.push(Line::default());
+        lines.push(heading(name));
+        let fields: Vec<(&str, String)> = fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect();
+        lines.extend(field_lines(&fields, 1));
+    }
+    for error in &stats.errors {
+        lines.push(Line::from(error.to_string()).yellow());
+    }
+    lines
+}
+
+fn overview_lines(process: &Process) -> Vec<Line<'static>> {
+    let state = &process.stats.state;
+    let mut lines = vec![
+        Line::from(vec![
+            Span::from("State ").white(),
+            Span::from(state.to_string()).fg(state_color(state)),
+            Span::from(format!("  {}", state.description())).dark_gray(),
+        ]),
+        Line::from(process.cmdline.clone()).gray(),
+        Line::default(),
+    ];
+
+    if let Some(credentials) = &process.credentials {
+        lines.push(heading("Credentials"));
+        for (kind, fields, changed) in [
+            ("Uid", credentials.user_fields(), credentials.is_setuid()),
+            ("Gid", credentials.group_fields(), credentials.is_setgid()),
+        ] {
+            let mut spans = vec![Span::from(format!("{:6} ", kind)).white()];
+            for (name, value) in fields {
+                spans.push(Span::from(format!("{} ", name)).white());
+                let value = Span::from(format!("{}  ", value));
+                spans.push(if changed && name == "effective" {
+                    value.yellow()
+                } else {
+                    value.gray()
+                });
+            }
+            lines.push(Line::from(spans));
+        }
+        if !credentials.groups.is_empty() {
+            lines.push(Line::from(vec![
+                Span::from("Groups ").white(),
+                Span::from(credentials.group_names()).gray(),
+            ]));
+        }
+        lines.push(Line::default());
+    }
+
+    if let Some(cgroups) = &process.cgroups {
+        lines.push(heading("Cgroups"));
+        let workload = Workload::from_path(cgroups.path());
+        let mut fields = vec![("Workload", workload.to_string())];
+        let names: Vec<String> = cgroups
+            .entries
+            .iter()
+            .map(|entry| entry.hierarchy_name())
+            .collect();
+        fields.extend(
+            names
+                .iter()
+                .zip(&cgroups.entries)
+                .map(|(name, entry)| (name.as_str(), entry.path.clone())),
+        );
+        lines.extend(field_lines(&fields, 1));
+        lines.push(Line::default());
+    }
+
+    if let Some(namespaces) = &process.namespaces {
+        lines.push(heading("Namespaces"));
+        let mut fields: Vec<(&str, String)> = namespaces
+            .entries
+            .iter()
+            .map(|ns| (ns.kind.name(), ns.inode.to_string()))
+            .collect();
+        if process.ns_pids.len() > 1 {
+            let pids: Vec<String> = process.ns_pids.iter().map(u64::to_string).collect();
+            fields.push(("NSpid", pids.join(" ")));
+        }
+        lines.extend(field_lines(&fields, 2));
+        lines.push(Line::default());
+    }
+    lines.push(heading("Memory"));
+
+    let memory: Vec<(&str, String)> = process
+        .memory
+        .fields()
+        .into_iter()
+        .map(|(name, value)| (name, value.map_or("-".to_string(), format_bytes)))
+        .collect();
+    lines.extend(field_lines(&memory, 2));
+
+    lines.push(Line::default());
+    lines.push(heading("Threads"));
+    lines.push(
+        Line::from(format!(
+            "{:>7} {:16} {:12} {:>6} {:>4}",
+            "TID", "Name", "State", "CPU%", "CPU"
+        ))
+        .white(),
+    );
+    for thread in &process.threads {
+        let cpu_usage = match thread.cpu_usage {
+            Some(usage) => format!("{:.1}", usage),
+            None => "-".to_string(),
+        };
+        lines.push(Line::from(vec![
+            Span::from(format!("{:>7} {:16} ", thread.tid, thread.stats.tcomm)).gray(),
+            Span::from(format!("{:12} ", thread.stats.state.to_string()))
+                .fg(state_color(&thread.stats.state)),
+            Span::from(format!("{:>6} {:>4}", cpu_usage, thread.stats.processor)).gray(),
+        ]));
+    }
+
+    lines.push(Line::default());
+    lines.push(heading("Stat"));
+    lines.extend(field_lines(&process.stats.fields(), 2));
+
+    for error in &process.errors {
+        lines.push(Line::from(error.to_string()).yellow());
+    }
+    lines
+}
+
+/// Nothing for details that weren't loaded.
+fn result_lines<T>(
+    result: &Option<Result<T>>,
+    lines: impl Fn(&T) -> Vec<Line<'static>>,
+) -> Vec<Line<'static>> {
+    match result {
+        Some(Ok(value)) => lines(value),
+        Some(Err(err)) => vec![Line::from(err.to_string()).yellow()],
+        None => Vec::new(),
+    }
+}
+
+fn fd_lines(fds: &[Fd]) -> Vec<Line<'static>> {
+    let mut summary: Vec<Span> = FdKind::ALL
+        .iter()
+        .filter_map(|kind| {
+            let count = fds.iter().filter(|fd| fd.kind == *kind).count();
+            (count > 0).then(|| Span::from(format!("{} {}  ", count, kind)).white())
+        })
+        .collect();
+    let deleted = fds.iter().filter(|fd| fd.deleted).count();
+    if deleted > 0 {
+        summary.push(Span::from(format!("{} deleted", deleted)).red());
+    }
+
+    let mut lines = vec![
+        Line::from(summary),
+        Line::from(format!(
+            "{:>5} {:8} {:>10} {:>7}  {}",
+            "FD", "Type", "Pos", "Flags", "Target"
+        ))
+        .white(),
+    ];
+    for fd in fds {
+        let (pos, flags) = match &fd.info {
+            Some(info) => (info.pos.to_string(), format!("{:o}", info.flags)),
+            None => (String::new(), String::new()),
+        };
+        let line = Line::from(format!(
+            "{:>5} {:8} {:>10} {:>7}  {}",
+            fd.fd,
+            fd.kind.to_string(),
+            pos,
+            flags,
+            fd.target
+        ));
+        lines.push(if fd.deleted { line.red() } else { line.gray() });
+    }
+    lines
+}
+
+fn map_lines(mappings: &[Mapping]) -> Vec<Line<'static>> {
+    if mappings.is_empty() {
+        return vec![Line::from("No mappings, probably a kernel thread").yellow()];
+    }
+
+    let mut groups = group_mappings(mappings);
+    groups.sort_by_key(|group| Reverse(group.rss));
+
+    let mut lines = vec![Line::from(format!(
+        "{:>8} {:>8} {:>8} {:>8} {:>8} {:>4}  {}",
+        "Size", "RSS", "PSS", "Dirty", "Swap", "Maps", "Path"
+    ))
+    .white()];
+    lines.extend(groups.iter().map(|group| {
+        Line::from(format!(
+            "{:>8} {:>8} {:>8} {:>8} {:>8} {:>4}  {}",
+            format_bytes(group.size),
+            format_bytes(group.rss),
+            format_bytes(group.pss),
+            format_bytes(group.private_dirty),
+            format_bytes(group.swap),
+            group.mappings,
+            group.name
+        ))
+        .gray()
+    }));
+    lines
+}
+
+fn socket_lines(sockets: &ProcessSockets) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if sockets.unknown > 0 {
+        lines.push(
+            Line::from(format!(
+                "{} sockets of other families (netlink, packet, ...)",
+                sockets.unknown
+            ))
+            .dark_gray(),
+        );
+    }
+    lines.extend(
+        sockets
+            .errors
+            .iter()
+            .map(|error| Line::from(error.to_string()).yellow()),
+    );
+
+    lines.push(
+        Line::from(format!(
+            "{:>5} {:6} {:>6} {:>6} {:12} {:24} {}",
+            "FD", "Proto", "Recv-Q", "Send-Q", "State", "Local", "Remote"
+        ))
+        .white(),
+    );
+    lines.extend(sockets.sockets.iter().map(|(fd, socket)| {
+        Line::from(format!(
+            "{:>5} {:6} {:>6} {:>6} {:12} {:24} {}",
+            fd,
+            socket.protocol.to_string(),
+            socket.rx_queue,
+            socket.tx_queue,
+            socket.state.to_string(),
+            socket.local_address(),
+            socket.remote_address()
+        ))
+        .gray()
+    }));
+    lines
+}
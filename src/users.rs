@@ -0,0 +1,145 @@
+use std::{
+    collections::HashMap,
+    ffi::{c_char, CStr},
+    path::Path,
+    sync::{Mutex, OnceLock, PoisonError},
+};
+
+/// Names of users and groups, read straight from `/etc/passwd` and `/etc/group` so that
+/// resolving thousands of processes doesn't go through NSS for each of them.
+///
+/// Ids that aren't in the files are looked up with getpwuid_r(3) and getgrgid_r(3) once, which
+/// covers users from LDAP and the like. Misses are cached too.
+#[derive(Default)]
+pub struct UserNames {
+    users: HashMap<u32, Option<String>>,
+    groups: HashMap<u32, Option<String>>,
+}
+
+impl UserNames {
+    pub fn load() -> Self {
+        Self::from_files(Path::new("/etc/passwd"), Path::new("/etc/group"))
+    }
+
+    /// Missing or unreadable files count as empty.
+    pub fn from_files(passwd: &Path, group: &Path) -> Self {
+        let read = |path: &Path| std::fs::read_to_string(path).unwrap_or_default();
+        Self {
+            users: parse_names(&read(passwd)),
+            groups: parse_names(&read(group)),
+        }
+    }
+
+    pub fn user(&mut self, uid: u32) -> Option<&str> {
+        self.users
+            .entry(uid)
+            .or_insert_with(|| lookup_user(uid))
+            .as_deref()
+    }
+
+    pub fn group(&mut self, gid: u32) -> Option<&str> {
+        self.groups
+            .entry(gid)
+            .or_insert_with(|| lookup_group(gid))
+            .as_deref()
+    }
+}
+
+/// Lines like `name:password:id:...`, the format of both files.
+fn parse_names(contents: &str) -> HashMap<u32, Option<String>> {
+    let mut names = HashMap::new();
+    for line in contents.lines().filter(|line| !line.starts_with('#')) {
+        let mut fields = line.split(':');
+        let (Some(name), Some(_), Some(id)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let Ok(id) = id.parse::<u32>() else {
+            continue;
+        };
+        // The first entry wins, like with getpwuid.
+        names.entry(id).or_insert_with(|| Some(name.to_string()));
+    }
+    names
+}
+
+/// Big enough for any sane entry, see sysconf(_SC_GETPW_R_SIZE_MAX).
+const BUFFER_SIZE: usize = 16 * 1024;
+
+fn lookup_user(uid: u32) -> Option<String> {
+    let mut buffer = vec![0 as c_char; BUFFER_SIZE];
+    // SAFETY: an all zero passwd is valid, getpwuid_r fills it in.
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: all pointers are valid for the duration of the call and the buffer length is
+    // correct.
+    let ret = unsafe {
+        libc::getpwuid_r(
+            uid,
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if ret != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: on success pw_name points to a string in the buffer.
+    let name = unsafe { CStr::from_ptr(passwd.pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+fn lookup_group(gid: u32) -> Option<String> {
+    let mut buffer = vec![0 as c_char; BUFFER_SIZE];
+    // SAFETY: an all zero group is valid, getgrgid_r fills it in.
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: all pointers are valid for the duration of the call and the buffer length is
+    // correct.
+    let ret = unsafe {
+        libc::getgrgid_r(
+            gid,
+            &mut group,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if ret != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: on success gr_name points to a string in the buffer.
+    let name = unsafe { CStr::from_ptr(group.gr_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+fn with_names<T>(f: impl FnOnce(&mut UserNames) -> T) -> T {
+    static NAMES: OnceLock<Mutex<UserNames>> = OnceLock::new();
+    let names = NAMES.get_or_init(|| Mutex::new(UserNames::load()));
+    // The cache stays consistent even if a lookup panicked.
+    f(&mut names.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Name of `uid` from the shared cache, the number if it has none.
+pub fn user_name(uid: u32) -> String {
+    with_names(|names| {
+        names
+            .user(uid)
+            .map_or_else(|| uid.to_string(), str::to_string)
+    })
+}
+
+/// Name of `gid` from the shared cache, the number if it has none.
+pub fn group_name(gid: u32) -> String {
+    with_names(|names| {
+        names
+            .group(gid)
+            .map_or_else(|| gid.to_string(), str::to_string)
+    })
+}
+
+/// Real uid of Linux Explorer itself.
+pub fn current_uid() -> u32 {
+    // SAFETY: getuid always succeeds.
+    unsafe { libc::getuid() }
+}